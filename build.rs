@@ -0,0 +1,11 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Use the protoc bundled with protoc-bin-vendored, so no system install is needed
+    let mut config = prost_build::Config::new();
+    config.protoc_executable(protoc_bin_vendored::protoc_bin_path()?);
+    tonic_prost_build::configure().compile_with_config(
+        config,
+        &["proto/backend/v1/auth.proto", "proto/backend/v1/user.proto"],
+        &["proto"],
+    )?;
+    Ok(())
+}
@@ -0,0 +1,17 @@
+-- Add `role` column to users (idempotent)
+-- Roles: 'user' (default) and 'admin'. Promote the first admin manually:
+--   UPDATE users SET role = 'admin' WHERE email = 'you@example.com';
+SET @exists := (
+    SELECT COUNT(*)
+    FROM INFORMATION_SCHEMA.COLUMNS
+    WHERE TABLE_SCHEMA = DATABASE()
+      AND TABLE_NAME = 'users'
+      AND COLUMN_NAME = 'role'
+);
+SET @stmt := IF(@exists = 0,
+    'ALTER TABLE users ADD COLUMN role VARCHAR(20) NOT NULL DEFAULT ''user'' AFTER password;',
+    'SELECT "column already exists";'
+);
+PREPARE stmt FROM @stmt;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
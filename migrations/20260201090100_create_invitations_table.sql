@@ -0,0 +1,17 @@
+-- Create invitations table (idempotent)
+-- Only the SHA-256 hash of the invitation token is stored; the raw token is returned once on creation.
+CREATE TABLE IF NOT EXISTS invitations (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    email VARCHAR(100) NOT NULL,
+    role VARCHAR(20) NOT NULL DEFAULT 'user',
+    token_hash CHAR(64) NOT NULL,
+    invited_by BIGINT NOT NULL,
+    expires_at TIMESTAMP NOT NULL,
+    accepted_at TIMESTAMP NULL DEFAULT NULL,
+    accepted_user_id BIGINT NULL DEFAULT NULL,
+    revoked_at TIMESTAMP NULL DEFAULT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    UNIQUE INDEX ux_invitations_token_hash (token_hash),
+    INDEX ix_invitations_email (email)
+);
@@ -0,0 +1,13 @@
+-- Create user_identities table (idempotent)
+-- Links an external identity provider subject (OIDC `sub`) to a local user.
+CREATE TABLE IF NOT EXISTS user_identities (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    user_id BIGINT NOT NULL,
+    provider VARCHAR(64) NOT NULL,
+    subject VARCHAR(255) NOT NULL,
+    email VARCHAR(100) NULL DEFAULT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    last_login_at TIMESTAMP NULL DEFAULT NULL,
+    UNIQUE INDEX ux_user_identities_provider_subject (provider, subject),
+    INDEX ix_user_identities_user_id (user_id)
+);
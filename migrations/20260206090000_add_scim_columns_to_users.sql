@@ -0,0 +1,32 @@
+-- Add SCIM provisioning columns to users (idempotent)
+-- - external_id: the identifier assigned by the provisioning client (SCIM `externalId`)
+-- - active: deactivated accounts (SCIM `active: false`) cannot log in
+SET @exists := (
+    SELECT COUNT(*)
+    FROM INFORMATION_SCHEMA.COLUMNS
+    WHERE TABLE_SCHEMA = DATABASE()
+      AND TABLE_NAME = 'users'
+      AND COLUMN_NAME = 'external_id'
+);
+SET @stmt := IF(@exists = 0,
+    'ALTER TABLE users ADD COLUMN external_id VARCHAR(255) NULL AFTER role;',
+    'SELECT "column already exists";'
+);
+PREPARE stmt FROM @stmt;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
+
+SET @exists := (
+    SELECT COUNT(*)
+    FROM INFORMATION_SCHEMA.COLUMNS
+    WHERE TABLE_SCHEMA = DATABASE()
+      AND TABLE_NAME = 'users'
+      AND COLUMN_NAME = 'active'
+);
+SET @stmt := IF(@exists = 0,
+    'ALTER TABLE users ADD COLUMN active BOOLEAN NOT NULL DEFAULT TRUE AFTER external_id;',
+    'SELECT "column already exists";'
+);
+PREPARE stmt FROM @stmt;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
@@ -0,0 +1,110 @@
+-- Soft delete for users (idempotent)
+-- - deleted_at: set by DELETE /users/{id}; rows are hard-deleted by the purge task after the retention period
+-- - email_active: generated copy of email for live rows only, so a deleted user's address can be reused
+--   while uniqueness is still enforced among live users (NULLs do not collide in a UNIQUE index)
+SET @exists := (
+    SELECT COUNT(*)
+    FROM INFORMATION_SCHEMA.COLUMNS
+    WHERE TABLE_SCHEMA = DATABASE()
+      AND TABLE_NAME = 'users'
+      AND COLUMN_NAME = 'deleted_at'
+);
+SET @stmt := IF(@exists = 0,
+    'ALTER TABLE users ADD COLUMN deleted_at TIMESTAMP NULL DEFAULT NULL AFTER active;',
+    'SELECT "column already exists";'
+);
+PREPARE stmt FROM @stmt;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
+
+SET @exists := (
+    SELECT COUNT(*)
+    FROM INFORMATION_SCHEMA.STATISTICS
+    WHERE TABLE_SCHEMA = DATABASE()
+      AND TABLE_NAME = 'users'
+      AND INDEX_NAME = 'ix_users_deleted_at'
+);
+SET @stmt := IF(@exists = 0,
+    'CREATE INDEX ix_users_deleted_at ON users(deleted_at);',
+    'SELECT "index already exists";'
+);
+PREPARE stmt FROM @stmt;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
+
+SET @exists := (
+    SELECT COUNT(*)
+    FROM INFORMATION_SCHEMA.COLUMNS
+    WHERE TABLE_SCHEMA = DATABASE()
+      AND TABLE_NAME = 'users'
+      AND COLUMN_NAME = 'email_active'
+);
+SET @stmt := IF(@exists = 0,
+    'ALTER TABLE users ADD COLUMN email_active VARCHAR(100) GENERATED ALWAYS AS (IF(deleted_at IS NULL, email, NULL)) STORED;',
+    'SELECT "column already exists";'
+);
+PREPARE stmt FROM @stmt;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
+
+SET @exists := (
+    SELECT COUNT(*)
+    FROM INFORMATION_SCHEMA.STATISTICS
+    WHERE TABLE_SCHEMA = DATABASE()
+      AND TABLE_NAME = 'users'
+      AND INDEX_NAME = 'ux_users_email_active'
+);
+SET @stmt := IF(@exists = 0,
+    'CREATE UNIQUE INDEX ux_users_email_active ON users(email_active);',
+    'SELECT "index already exists";'
+);
+PREPARE stmt FROM @stmt;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
+
+-- Email uniqueness now lives on email_active; drop the old unique indexes (both names seen in the wild)
+SET @exists := (
+    SELECT COUNT(*)
+    FROM INFORMATION_SCHEMA.STATISTICS
+    WHERE TABLE_SCHEMA = DATABASE()
+      AND TABLE_NAME = 'users'
+      AND INDEX_NAME = 'ux_users_email'
+);
+SET @stmt := IF(@exists > 0,
+    'DROP INDEX ux_users_email ON users;',
+    'SELECT "index already dropped";'
+);
+PREPARE stmt FROM @stmt;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
+
+SET @exists := (
+    SELECT COUNT(*)
+    FROM INFORMATION_SCHEMA.STATISTICS
+    WHERE TABLE_SCHEMA = DATABASE()
+      AND TABLE_NAME = 'users'
+      AND INDEX_NAME = 'email'
+);
+SET @stmt := IF(@exists > 0,
+    'DROP INDEX email ON users;',
+    'SELECT "index already dropped";'
+);
+PREPARE stmt FROM @stmt;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
+
+-- Keep email lookups indexed (non-unique)
+SET @exists := (
+    SELECT COUNT(*)
+    FROM INFORMATION_SCHEMA.STATISTICS
+    WHERE TABLE_SCHEMA = DATABASE()
+      AND TABLE_NAME = 'users'
+      AND INDEX_NAME = 'ix_users_email'
+);
+SET @stmt := IF(@exists = 0,
+    'CREATE INDEX ix_users_email ON users(email);',
+    'SELECT "index already exists";'
+);
+PREPARE stmt FROM @stmt;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
@@ -0,0 +1,16 @@
+-- Add a row version to users for optimistic concurrency (idempotent)
+-- Every write to a user increments `version`; the API exposes it as a strong ETag
+SET @exists := (
+    SELECT COUNT(*)
+    FROM INFORMATION_SCHEMA.COLUMNS
+    WHERE TABLE_SCHEMA = DATABASE()
+      AND TABLE_NAME = 'users'
+      AND COLUMN_NAME = 'version'
+);
+SET @stmt := IF(@exists = 0,
+    'ALTER TABLE users ADD COLUMN version BIGINT NOT NULL DEFAULT 1 AFTER deleted_at;',
+    'SELECT "column already exists";'
+);
+PREPARE stmt FROM @stmt;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
@@ -0,0 +1,32 @@
+-- Profile pictures (idempotent)
+-- - avatar_version: random token naming the current upload; blobs live under avatars/{id}/{avatar_version}/
+-- - avatar_content_type: media type of the stored variants (image/png or image/jpeg)
+SET @exists := (
+    SELECT COUNT(*)
+    FROM INFORMATION_SCHEMA.COLUMNS
+    WHERE TABLE_SCHEMA = DATABASE()
+      AND TABLE_NAME = 'users'
+      AND COLUMN_NAME = 'avatar_version'
+);
+SET @stmt := IF(@exists = 0,
+    'ALTER TABLE users ADD COLUMN avatar_version VARCHAR(32) NULL DEFAULT NULL AFTER version;',
+    'SELECT "column already exists";'
+);
+PREPARE stmt FROM @stmt;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
+
+SET @exists := (
+    SELECT COUNT(*)
+    FROM INFORMATION_SCHEMA.COLUMNS
+    WHERE TABLE_SCHEMA = DATABASE()
+      AND TABLE_NAME = 'users'
+      AND COLUMN_NAME = 'avatar_content_type'
+);
+SET @stmt := IF(@exists = 0,
+    'ALTER TABLE users ADD COLUMN avatar_content_type VARCHAR(32) NULL DEFAULT NULL AFTER avatar_version;',
+    'SELECT "column already exists";'
+);
+PREPARE stmt FROM @stmt;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
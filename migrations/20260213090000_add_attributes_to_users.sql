@@ -0,0 +1,16 @@
+-- Custom user attributes (idempotent)
+-- - attributes: JSON object validated against the schema in USER_ATTRIBUTES_FILE
+SET @exists := (
+    SELECT COUNT(*)
+    FROM INFORMATION_SCHEMA.COLUMNS
+    WHERE TABLE_SCHEMA = DATABASE()
+      AND TABLE_NAME = 'users'
+      AND COLUMN_NAME = 'attributes'
+);
+SET @stmt := IF(@exists = 0,
+    'ALTER TABLE users ADD COLUMN attributes JSON NULL DEFAULT NULL AFTER avatar_content_type;',
+    'SELECT "column already exists";'
+);
+PREPARE stmt FROM @stmt;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
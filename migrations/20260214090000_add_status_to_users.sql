@@ -0,0 +1,94 @@
+-- Account status lifecycle (idempotent)
+-- - status: active, suspended, banned or pending (awaiting admin approval)
+-- - status_reason / status_until: why the account was blocked and, for suspensions, when it ends
+-- - status_changed_at / status_changed_by: last status change and the admin who made it
+-- Independent of `active`, which SCIM clients use for provisioning; login needs both.
+SET @exists := (
+    SELECT COUNT(*)
+    FROM INFORMATION_SCHEMA.COLUMNS
+    WHERE TABLE_SCHEMA = DATABASE()
+      AND TABLE_NAME = 'users'
+      AND COLUMN_NAME = 'status'
+);
+SET @stmt := IF(@exists = 0,
+    'ALTER TABLE users ADD COLUMN status VARCHAR(16) NOT NULL DEFAULT ''active'' AFTER active;',
+    'SELECT "column already exists";'
+);
+PREPARE stmt FROM @stmt;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
+
+SET @exists := (
+    SELECT COUNT(*)
+    FROM INFORMATION_SCHEMA.COLUMNS
+    WHERE TABLE_SCHEMA = DATABASE()
+      AND TABLE_NAME = 'users'
+      AND COLUMN_NAME = 'status_reason'
+);
+SET @stmt := IF(@exists = 0,
+    'ALTER TABLE users ADD COLUMN status_reason VARCHAR(255) NULL DEFAULT NULL AFTER status;',
+    'SELECT "column already exists";'
+);
+PREPARE stmt FROM @stmt;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
+
+SET @exists := (
+    SELECT COUNT(*)
+    FROM INFORMATION_SCHEMA.COLUMNS
+    WHERE TABLE_SCHEMA = DATABASE()
+      AND TABLE_NAME = 'users'
+      AND COLUMN_NAME = 'status_until'
+);
+SET @stmt := IF(@exists = 0,
+    'ALTER TABLE users ADD COLUMN status_until TIMESTAMP NULL DEFAULT NULL AFTER status_reason;',
+    'SELECT "column already exists";'
+);
+PREPARE stmt FROM @stmt;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
+
+SET @exists := (
+    SELECT COUNT(*)
+    FROM INFORMATION_SCHEMA.COLUMNS
+    WHERE TABLE_SCHEMA = DATABASE()
+      AND TABLE_NAME = 'users'
+      AND COLUMN_NAME = 'status_changed_at'
+);
+SET @stmt := IF(@exists = 0,
+    'ALTER TABLE users ADD COLUMN status_changed_at TIMESTAMP NULL DEFAULT NULL AFTER status_until;',
+    'SELECT "column already exists";'
+);
+PREPARE stmt FROM @stmt;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
+
+SET @exists := (
+    SELECT COUNT(*)
+    FROM INFORMATION_SCHEMA.COLUMNS
+    WHERE TABLE_SCHEMA = DATABASE()
+      AND TABLE_NAME = 'users'
+      AND COLUMN_NAME = 'status_changed_by'
+);
+SET @stmt := IF(@exists = 0,
+    'ALTER TABLE users ADD COLUMN status_changed_by BIGINT NULL DEFAULT NULL AFTER status_changed_at;',
+    'SELECT "column already exists";'
+);
+PREPARE stmt FROM @stmt;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
+
+SET @exists := (
+    SELECT COUNT(*)
+    FROM INFORMATION_SCHEMA.STATISTICS
+    WHERE TABLE_SCHEMA = DATABASE()
+      AND TABLE_NAME = 'users'
+      AND INDEX_NAME = 'ix_users_status'
+);
+SET @stmt := IF(@exists = 0,
+    'CREATE INDEX ix_users_status ON users(status);',
+    'SELECT "index already exists";'
+);
+PREPARE stmt FROM @stmt;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
@@ -0,0 +1,39 @@
+-- Data subject requests (idempotent)
+-- - kind: `export` (downloadable JSON archive) or `erase` (anonymize the account after a grace period)
+-- - status: pending -> processing -> completed | failed; pending erasures can be cancelled and
+--   completed exports expire once their archive is removed
+-- - scheduled_for: when an erasure runs (end of the grace period)
+-- - archive_key: blob store key of a completed export, cleared when the archive expires
+CREATE TABLE IF NOT EXISTS user_data_requests (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    user_id BIGINT NOT NULL,
+    kind VARCHAR(16) NOT NULL,
+    status VARCHAR(16) NOT NULL DEFAULT 'pending',
+    requested_by BIGINT NOT NULL,
+    scheduled_for TIMESTAMP NULL DEFAULT NULL,
+    started_at TIMESTAMP NULL DEFAULT NULL,
+    completed_at TIMESTAMP NULL DEFAULT NULL,
+    archive_key VARCHAR(255) NULL DEFAULT NULL,
+    expires_at TIMESTAMP NULL DEFAULT NULL,
+    error VARCHAR(255) NULL DEFAULT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    INDEX ix_user_data_requests_user_id (user_id),
+    INDEX ix_user_data_requests_status (status, kind)
+);
+
+-- erased_at: set once a user's personal data has been anonymized. The row is kept (soft-deleted)
+-- so references to the user id stay valid, and the purge task leaves it alone.
+SET @exists := (
+    SELECT COUNT(*)
+    FROM INFORMATION_SCHEMA.COLUMNS
+    WHERE TABLE_SCHEMA = DATABASE()
+      AND TABLE_NAME = 'users'
+      AND COLUMN_NAME = 'erased_at'
+);
+SET @stmt := IF(@exists = 0,
+    'ALTER TABLE users ADD COLUMN erased_at TIMESTAMP NULL DEFAULT NULL AFTER deleted_at;',
+    'SELECT "column already exists";'
+);
+PREPARE stmt FROM @stmt;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
@@ -0,0 +1,19 @@
+-- Stored responses for `Idempotency-Key` retries (idempotent)
+-- - scope: SHA-256 of the caller identity (Authorization header, or client IP when anonymous), so
+--   keys of different callers never collide and nobody can replay another caller's response
+-- - request_hash: SHA-256 fingerprint of method, path and body; a reused key must match it
+-- - response_status IS NULL while the first request is still in flight (locked_at)
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    scope CHAR(64) NOT NULL,
+    idempotency_key VARCHAR(255) NOT NULL,
+    request_hash CHAR(64) NOT NULL,
+    response_status SMALLINT NULL DEFAULT NULL,
+    response_headers JSON NULL,
+    response_body MEDIUMBLOB NULL,
+    locked_at TIMESTAMP NULL DEFAULT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    expires_at TIMESTAMP NOT NULL,
+    UNIQUE INDEX ux_idempotency_keys_scope_key (scope, idempotency_key),
+    INDEX ix_idempotency_keys_expires_at (expires_at)
+);
@@ -0,0 +1,33 @@
+-- Full-text search over users (idempotent)
+-- - attributes_search: values of public string attributes, maintained by the application on every
+--   attributes write (and rebuilt at startup), so hidden attributes are never searchable
+-- - ft_users_search: FULLTEXT index used by GET /users/search
+SET @exists := (
+    SELECT COUNT(*)
+    FROM INFORMATION_SCHEMA.COLUMNS
+    WHERE TABLE_SCHEMA = DATABASE()
+      AND TABLE_NAME = 'users'
+      AND COLUMN_NAME = 'attributes_search'
+);
+SET @stmt := IF(@exists = 0,
+    'ALTER TABLE users ADD COLUMN attributes_search TEXT NULL AFTER attributes;',
+    'SELECT "column already exists";'
+);
+PREPARE stmt FROM @stmt;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
+
+SET @exists := (
+    SELECT COUNT(*)
+    FROM INFORMATION_SCHEMA.STATISTICS
+    WHERE TABLE_SCHEMA = DATABASE()
+      AND TABLE_NAME = 'users'
+      AND INDEX_NAME = 'ft_users_search'
+);
+SET @stmt := IF(@exists = 0,
+    'CREATE FULLTEXT INDEX ft_users_search ON users(name, email, attributes_search);',
+    'SELECT "index already exists";'
+);
+PREPARE stmt FROM @stmt;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
@@ -0,0 +1,18 @@
+-- Change history of users (idempotent)
+-- - actor_id: user who made the change (NULL when not made through the API by a signed-in user)
+-- - action: `create`, `update`, `delete`, `restore` or `revert`
+-- - changes: field-level diff `{ "<field>": { "from": ..., "to": ... } }` over name, email,
+--   `attributes.<name>` and deleted_at; passwords are never recorded
+-- - version: the user's version after the change (matches the ETag at that point)
+-- - reverted_to: for `revert`, the revision whose state was restored
+CREATE TABLE IF NOT EXISTS user_revisions (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    user_id BIGINT NOT NULL,
+    actor_id BIGINT NULL DEFAULT NULL,
+    action VARCHAR(16) NOT NULL,
+    changes JSON NOT NULL,
+    version BIGINT NOT NULL,
+    reverted_to BIGINT NULL DEFAULT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    INDEX ix_user_revisions_user_id (user_id, id)
+);
@@ -0,0 +1,45 @@
+-- Outgoing webhooks (idempotent)
+-- - webhook_subscriptions: receiver URL, signing secret and subscribed event types
+--   (`user.created`, `user.*` or `*`)
+-- - webhook_events: transactional outbox, written in the same transaction as the change;
+--   dispatched_at is set once the event has been fanned out into deliveries
+-- - webhook_deliveries: one per event and subscription; status pending -> delivering ->
+--   delivered, or dead once max attempts are exhausted (replayable by admins)
+CREATE TABLE IF NOT EXISTS webhook_subscriptions (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    url VARCHAR(2048) NOT NULL,
+    secret VARCHAR(255) NOT NULL,
+    events JSON NOT NULL,
+    description VARCHAR(255) NULL DEFAULT NULL,
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_by BIGINT NULL DEFAULT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS webhook_events (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    event_type VARCHAR(64) NOT NULL,
+    payload JSON NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    dispatched_at TIMESTAMP NULL DEFAULT NULL,
+    INDEX ix_webhook_events_dispatched_at (dispatched_at, id)
+);
+
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    event_id BIGINT NOT NULL,
+    subscription_id BIGINT NOT NULL,
+    status VARCHAR(16) NOT NULL DEFAULT 'pending',
+    attempts INT NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    locked_at TIMESTAMP NULL DEFAULT NULL,
+    last_status_code SMALLINT NULL DEFAULT NULL,
+    last_error VARCHAR(255) NULL DEFAULT NULL,
+    delivered_at TIMESTAMP NULL DEFAULT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    UNIQUE INDEX ux_webhook_deliveries_event_subscription (event_id, subscription_id),
+    INDEX ix_webhook_deliveries_due (status, next_attempt_at),
+    INDEX ix_webhook_deliveries_subscription (subscription_id, id)
+);
@@ -6,8 +6,13 @@ pub fn build_router() -> Router {
     use tower_http::cors::{Any, CorsLayer};
 
     let mut app = Router::new()
-        .merge(crate::routes::auth_routes::auth_routes())
-        .merge(crate::routes::user_routes::user_routes());
+        .merge(crate::routes::versioned_routes::versioned_routes())
+        .merge(crate::routes::invitation_routes::invitation_routes())
+        .merge(crate::routes::admin_routes::admin_routes())
+        .merge(crate::routes::scim_routes::scim_routes())
+        .merge(crate::routes::webhook_routes::webhook_routes())
+        .merge(crate::routes::ws_routes::ws_routes())
+        .merge(crate::routes::graphql_routes::graphql_routes());
 
     // Configure CORS based on environment variables:
 
@@ -17,19 +22,35 @@ pub fn build_router() -> Router {
         .unwrap_or(false);
 
     if enable_cors || cors_allowed.is_some() {
+        use axum::http::header::{self, HeaderName};
+        let methods = [
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::PATCH,
+            Method::DELETE,
+            Method::OPTIONS,
+        ];
+        // Request headers used by the API (a "*" wildcard never covers Authorization)
+        let headers = [
+            header::AUTHORIZATION,
+            header::CONTENT_TYPE,
+            header::ACCEPT,
+            header::IF_MATCH,
+            header::IF_NONE_MATCH,
+            HeaderName::from_static(crate::middlewares::idempotency::IDEMPOTENCY_KEY_HEADER),
+            HeaderName::from_static("last-event-id"),
+        ];
+        // Response headers readable by browser clients (versions and pagination/version links)
+        let exposed = [header::ETAG, header::LINK];
         // If CORS_ALLOWED_ORIGINS is exactly "*" treat it as permissive Any. Otherwise parse a CSV of origins.
         let cors_layer = if let Some(list) = cors_allowed {
             if list.trim() == "*" {
                 CorsLayer::new()
                     .allow_origin(Any)
-                    .allow_methods([
-                        Method::GET,
-                        Method::POST,
-                        Method::PUT,
-                        Method::DELETE,
-                        Method::OPTIONS,
-                    ])
-                    .allow_headers(Any)
+                    .allow_methods(methods)
+                    .allow_headers(headers)
+                    .expose_headers(exposed)
             } else {
                 use axum::http::header::HeaderValue;
                 use tower_http::cors::AllowOrigin;
@@ -39,26 +60,16 @@ pub fn build_router() -> Router {
                     .collect::<Vec<HeaderValue>>();
                 CorsLayer::new()
                     .allow_origin(AllowOrigin::list(origins))
-                    .allow_methods([
-                        Method::GET,
-                        Method::POST,
-                        Method::PUT,
-                        Method::DELETE,
-                        Method::OPTIONS,
-                    ])
-                    .allow_headers(Any)
+                    .allow_methods(methods)
+                    .allow_headers(headers)
+                    .expose_headers(exposed)
             }
         } else {
             CorsLayer::new()
                 .allow_origin(Any)
-                .allow_methods([
-                    Method::GET,
-                    Method::POST,
-                    Method::PUT,
-                    Method::DELETE,
-                    Method::OPTIONS,
-                ])
-                .allow_headers(Any)
+                .allow_methods(methods)
+                .allow_headers(headers)
+                .expose_headers(exposed)
         };
         app = app.layer(cors_layer);
     }
@@ -1 +1,4 @@
 pub mod database;
+pub mod oidc;
+pub mod policy;
+pub mod user_attributes;
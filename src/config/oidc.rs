@@ -0,0 +1,167 @@
+use jsonwebtoken::Algorithm;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::{Arc, RwLock};
+
+use crate::models::user::{ROLE_USER, ROLES};
+
+fn default_scopes() -> Vec<String> {
+    vec![
+        "openid".to_string(),
+        "email".to_string(),
+        "profile".to_string(),
+    ]
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_role() -> String {
+    ROLE_USER.to_string()
+}
+
+fn default_algorithms() -> Vec<Algorithm> {
+    vec![Algorithm::RS256]
+}
+
+/// One external OpenID Connect provider (the IdP) this API trusts for login.
+#[derive(Debug, Clone, Deserialize)]
+pub struct OidcProviderConfig {
+    /// Short name used in URLs: `/auth/oidc/{name}/start`
+    pub name: String,
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: String,
+    /// Must exactly match the redirect URI registered at the IdP (points to our `/callback`)
+    pub redirect_uri: String,
+    #[serde(default = "default_scopes")]
+    pub scopes: Vec<String>,
+    /// Optional explicit endpoints; discovered from `{issuer}/.well-known/openid-configuration` when omitted
+    #[serde(default)]
+    pub authorization_endpoint: Option<String>,
+    #[serde(default)]
+    pub token_endpoint: Option<String>,
+    #[serde(default)]
+    pub jwks_uri: Option<String>,
+    /// ID token signing algorithms accepted from this provider; tokens signed with any other
+    /// algorithm are rejected. `HS*` (verified with the client secret) must be listed explicitly.
+    #[serde(default = "default_algorithms")]
+    pub allowed_algorithms: Vec<Algorithm>,
+    /// Create a local account on first login when no account matches (just-in-time provisioning)
+    #[serde(default = "default_true")]
+    pub jit_provisioning: bool,
+    /// Role given to just-in-time provisioned accounts
+    #[serde(default = "default_role")]
+    pub default_role: String,
+    /// If non-empty, only emails in these domains may log in
+    #[serde(default)]
+    pub allowed_domains: Vec<String>,
+    /// ID token claims copied into our JWT `attrs` (used by permission policies)
+    #[serde(default)]
+    pub attribute_claims: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OidcProvidersFile {
+    #[serde(default)]
+    providers: Vec<OidcProviderConfig>,
+}
+
+static PROVIDERS: Lazy<RwLock<HashMap<String, Arc<OidcProviderConfig>>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Parse a providers document (`toml` or `json`) and validate each provider.
+pub fn parse_providers(raw: &str, format: &str) -> Result<Vec<OidcProviderConfig>, String> {
+    let file: OidcProvidersFile = match format {
+        "toml" => toml::from_str(raw).map_err(|e| format!("invalid TOML providers: {}", e))?,
+        "json" => {
+            serde_json::from_str(raw).map_err(|e| format!("invalid JSON providers: {}", e))?
+        }
+        other => return Err(format!("unsupported providers format: {}", other)),
+    };
+    for p in &file.providers {
+        if p.name.is_empty()
+            || !p
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(format!("invalid provider name '{}'", p.name));
+        }
+        if !ROLES.contains(&p.default_role.as_str()) {
+            return Err(format!(
+                "provider '{}' has unknown default_role '{}'",
+                p.name, p.default_role
+            ));
+        }
+        if p.allowed_algorithms.is_empty() {
+            return Err(format!(
+                "provider '{}' must allow at least one signing algorithm",
+                p.name
+            ));
+        }
+        if !p.scopes.iter().any(|s| s == "openid") {
+            return Err(format!(
+                "provider '{}' must request the openid scope",
+                p.name
+            ));
+        }
+    }
+    Ok(file.providers)
+}
+
+/// Replace the registered providers (called at startup, also useful for tests).
+pub fn set_providers(providers: Vec<OidcProviderConfig>) {
+    let map = providers
+        .into_iter()
+        .map(|p| (p.name.clone(), Arc::new(p)))
+        .collect::<HashMap<_, _>>();
+    if let Ok(mut guard) = PROVIDERS.write() {
+        *guard = map;
+    } else {
+        tracing::warn!("oidc: failed to acquire write lock to set providers");
+    }
+}
+
+/// Look up a configured provider by name.
+pub fn get_provider(name: &str) -> Option<Arc<OidcProviderConfig>> {
+    PROVIDERS.read().ok().and_then(|m| m.get(name).cloned())
+}
+
+/// Load OIDC providers from `OIDC_PROVIDERS_FILE` (TOML or JSON by extension).
+///
+/// When unset, federated login is disabled and the `/auth/oidc/*` routes return 404.
+pub fn load_providers() -> Result<(), Box<dyn Error + Send + Sync>> {
+    let path = match std::env::var("OIDC_PROVIDERS_FILE") {
+        Ok(p) if !p.trim().is_empty() => p,
+        _ => {
+            tracing::info!("OIDC_PROVIDERS_FILE not set; federated login disabled");
+            return Ok(());
+        }
+    };
+
+    let raw = std::fs::read_to_string(&path).map_err(|e| {
+        Box::<dyn Error + Send + Sync>::from(format!(
+            "failed to read OIDC_PROVIDERS_FILE {}: {}",
+            path, e
+        ))
+    })?;
+    let format = if path.to_lowercase().ends_with(".json") {
+        "json"
+    } else {
+        "toml"
+    };
+    let providers = parse_providers(&raw, format).map_err(|e| {
+        Box::<dyn Error + Send + Sync>::from(format!(
+            "failed to load OIDC_PROVIDERS_FILE {}: {}",
+            path, e
+        ))
+    })?;
+
+    tracing::info!("Loaded {} OIDC provider(s) from {}", providers.len(), path);
+    set_providers(providers);
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use std::error::Error;
+
+use crate::utils::policy::{PolicySet, set_policies};
+
+/// Load permission policies from `POLICY_FILE` (TOML or JSON, chosen by file extension).
+///
+/// When `POLICY_FILE` is unset the built-in permissive default stays active. A file that
+/// cannot be read or parsed is a startup error so a typo never silently opens access.
+pub fn load_policies() -> Result<(), Box<dyn Error + Send + Sync>> {
+    let path = match std::env::var("POLICY_FILE") {
+        Ok(p) if !p.trim().is_empty() => p,
+        _ => {
+            tracing::info!("POLICY_FILE not set; using built-in default policy");
+            return Ok(());
+        }
+    };
+
+    let raw = std::fs::read_to_string(&path).map_err(|e| {
+        Box::<dyn Error + Send + Sync>::from(format!("failed to read POLICY_FILE {}: {}", path, e))
+    })?;
+
+    let format = if path.to_lowercase().ends_with(".json") {
+        "json"
+    } else {
+        "toml"
+    };
+
+    let set = PolicySet::parse(&raw, format).map_err(|e| {
+        Box::<dyn Error + Send + Sync>::from(format!("failed to load POLICY_FILE {}: {}", path, e))
+    })?;
+
+    tracing::info!("Loaded {} policy rule(s) from {}", set.rules.len(), path);
+    set_policies(set);
+    Ok(())
+}
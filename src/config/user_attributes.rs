@@ -0,0 +1,44 @@
+use std::error::Error;
+
+use crate::utils::user_attributes::{AttributeSchema, set_attribute_schema};
+
+/// Load the custom user attribute schema from `USER_ATTRIBUTES_FILE` (TOML or JSON, chosen
+/// by file extension).
+///
+/// When unset no custom attributes are accepted. A file that cannot be read or parsed is a
+/// startup error.
+pub fn load_user_attributes() -> Result<(), Box<dyn Error + Send + Sync>> {
+    let path = match std::env::var("USER_ATTRIBUTES_FILE") {
+        Ok(p) if !p.trim().is_empty() => p,
+        _ => {
+            tracing::info!("USER_ATTRIBUTES_FILE not set; custom user attributes disabled");
+            return Ok(());
+        }
+    };
+
+    let raw = std::fs::read_to_string(&path).map_err(|e| {
+        Box::<dyn Error + Send + Sync>::from(format!(
+            "failed to read USER_ATTRIBUTES_FILE {}: {}",
+            path, e
+        ))
+    })?;
+    let format = if path.to_lowercase().ends_with(".json") {
+        "json"
+    } else {
+        "toml"
+    };
+    let schema = AttributeSchema::parse(&raw, format).map_err(|e| {
+        Box::<dyn Error + Send + Sync>::from(format!(
+            "failed to load USER_ATTRIBUTES_FILE {}: {}",
+            path, e
+        ))
+    })?;
+
+    tracing::info!(
+        "Loaded {} custom user attribute(s) from {}",
+        schema.attributes.len(),
+        path
+    );
+    set_attribute_schema(schema);
+    Ok(())
+}
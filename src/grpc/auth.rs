@@ -0,0 +1,101 @@
+use axum::{Json, http::StatusCode};
+use serde_json::Value;
+use sqlx::MySqlPool;
+use tonic::metadata::{MetadataMap, MetadataValue};
+use tonic::{Code, Request, Status};
+
+// Import policy guard, JWT and response helpers
+use crate::middlewares::policy_guard::Authorizer;
+use crate::utils::jwt::{Claims, verify_jwt_token};
+use crate::utils::response::ApiResponse;
+use crate::utils::user_status::account_state;
+
+/// Metadata key carrying the JSON `data` of a failed call (e.g. validation errors by field).
+pub const ERROR_DETAILS_KEY: &str = "error-details-bin";
+
+/// gRPC status for an API error response: the HTTP status mapped to the closest code, the
+/// message and `error` detail as the status message, and the response data as JSON in the
+/// [`ERROR_DETAILS_KEY`] metadata.
+pub fn to_status((status, Json(body)): (StatusCode, Json<ApiResponse<Value>>)) -> Status {
+    let code = match status {
+        StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => Code::InvalidArgument,
+        StatusCode::UNAUTHORIZED => Code::Unauthenticated,
+        StatusCode::FORBIDDEN => Code::PermissionDenied,
+        StatusCode::NOT_FOUND => Code::NotFound,
+        StatusCode::CONFLICT => Code::AlreadyExists,
+        StatusCode::PRECONDITION_FAILED | StatusCode::PRECONDITION_REQUIRED => {
+            Code::FailedPrecondition
+        }
+        StatusCode::TOO_MANY_REQUESTS => Code::ResourceExhausted,
+        _ => Code::Internal,
+    };
+    let message = match body.data.as_ref().and_then(|d| d["error"].as_str()) {
+        Some(error) => format!("{}: {}", body.message, error),
+        None => body.message,
+    };
+    let mut metadata = MetadataMap::new();
+    if let Some(data) = body.data {
+        metadata.insert_bin(
+            ERROR_DETAILS_KEY,
+            MetadataValue::from_bytes(data.to_string().as_bytes()),
+        );
+    }
+    Status::with_metadata(code, message, metadata)
+}
+
+/// Why a token cannot be used, with the matching status code.
+pub struct Rejection {
+    pub code: Code,
+    pub reason: &'static str,
+}
+
+/// Check a token as `auth_middleware` does: signature and expiry, then the account status
+/// (so suspensions and bans revoke tokens issued earlier).
+pub async fn check_token(db: &MySqlPool, token: &str) -> Result<Result<Claims, Rejection>, Status> {
+    let Ok(claims) = verify_jwt_token(token).await else {
+        return Ok(Err(Rejection {
+            code: Code::Unauthenticated,
+            reason: "Invalid or expired token",
+        }));
+    };
+    let status = account_state(db, claims.sub).await.map_err(|e| {
+        tracing::error!("grpc: failed to load account status: {}", e);
+        Status::internal("Failed to load account status")
+    })?;
+    Ok(match status {
+        None => Err(Rejection {
+            code: Code::Unauthenticated,
+            reason: "Account no longer exists",
+        }),
+        Some(status) => match status.login_error() {
+            Some(reason) => Err(Rejection {
+                code: Code::PermissionDenied,
+                reason,
+            }),
+            None => Ok(claims),
+        },
+    })
+}
+
+/// Authenticate a call from its `authorization: Bearer <jwt>` metadata.
+pub async fn authenticate<T>(db: &MySqlPool, request: &Request<T>) -> Result<Claims, Status> {
+    let header = request
+        .metadata()
+        .get("authorization")
+        .ok_or_else(|| Status::unauthenticated("Missing authorization metadata"))?;
+    let token = header
+        .to_str()
+        .ok()
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .ok_or_else(|| Status::unauthenticated("Invalid authorization metadata format"))?;
+    match check_token(db, token.trim()).await? {
+        Ok(claims) => Ok(claims),
+        Err(rejection) => Err(Status::new(rejection.code, rejection.reason)),
+    }
+}
+
+/// Policy guard for the caller of an authenticated call.
+pub async fn authorizer<T>(db: &MySqlPool, request: &Request<T>) -> Result<Authorizer, Status> {
+    let claims = authenticate(db, request).await?;
+    Authorizer::load(db, &claims).await.map_err(to_status)
+}
@@ -0,0 +1,79 @@
+use sqlx::MySqlPool;
+use tonic::{Request, Response, Status};
+
+// Import generated messages and the service trait
+use crate::grpc::pb::auth_service_server::AuthService;
+use crate::grpc::pb::{LoginRequest, LoginResponse, VerifyTokenRequest, VerifyTokenResponse};
+
+// Import gRPC auth and error mapping
+use crate::grpc::auth::{check_token, to_status};
+
+// Import the login and user lookups shared with the REST handlers
+use crate::handlers::login_handler::login;
+use crate::handlers::user_handler::{attribute_viewer, fetch_user};
+use crate::middlewares::policy_guard::Authorizer;
+use crate::schemas::login_schema::LoginSchema;
+use crate::utils::jwt::verify_jwt_token;
+
+/// `backend.v1.AuthService`: sign-in and token checks for other services.
+pub struct AuthGrpcService {
+    db: MySqlPool,
+}
+
+impl AuthGrpcService {
+    pub fn new(db: MySqlPool) -> Self {
+        Self { db }
+    }
+}
+
+#[tonic::async_trait]
+impl AuthService for AuthGrpcService {
+    async fn login(
+        &self,
+        request: Request<LoginRequest>,
+    ) -> Result<Response<LoginResponse>, Status> {
+        let LoginRequest { email, password } = request.into_inner();
+        let payload = LoginSchema { email, password };
+        let session = login(&self.db, &payload).await.map_err(to_status)?;
+
+        // Return the user as the new session sees it (attribute visibility follows the role)
+        let claims = verify_jwt_token(&session.token)
+            .await
+            .map_err(|_| Status::internal("Failed to read the issued token"))?;
+        let authz = Authorizer::load(&self.db, &claims)
+            .await
+            .map_err(to_status)?;
+        let viewer = attribute_viewer(&self.db, &authz)
+            .await
+            .map_err(to_status)?;
+        let user = fetch_user(&self.db, session.user.id, &viewer)
+            .await
+            .map_err(to_status)?;
+        Ok(Response::new(LoginResponse {
+            token: session.token,
+            user: Some(user.into()),
+        }))
+    }
+
+    async fn verify_token(
+        &self,
+        request: Request<VerifyTokenRequest>,
+    ) -> Result<Response<VerifyTokenResponse>, Status> {
+        let token = request.into_inner().token;
+        let response = match check_token(&self.db, token.trim()).await? {
+            Ok(claims) => VerifyTokenResponse {
+                valid: true,
+                reason: String::new(),
+                user_id: claims.sub,
+                role: claims.role,
+                expires_at: claims.exp as i64,
+            },
+            Err(rejection) => VerifyTokenResponse {
+                valid: false,
+                reason: rejection.reason.to_string(),
+                ..Default::default()
+            },
+        };
+        Ok(Response::new(response))
+    }
+}
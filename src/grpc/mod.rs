@@ -0,0 +1,9 @@
+pub mod auth;
+pub mod auth_service;
+pub mod server;
+pub mod user_service;
+
+/// Messages and service traits generated from `proto/backend/v1` by `build.rs`.
+pub mod pb {
+    tonic::include_proto!("backend.v1");
+}
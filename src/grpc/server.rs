@@ -0,0 +1,91 @@
+use axum::{Router, middleware};
+use sqlx::MySqlPool;
+use std::time::Duration;
+use tonic::server::NamedService;
+use tonic_health::ServingStatus;
+use tonic_health::server::HealthReporter;
+
+// Import generated servers and the service implementations
+use crate::grpc::auth_service::AuthGrpcService;
+use crate::grpc::pb::auth_service_server::AuthServiceServer;
+use crate::grpc::pb::user_service_server::UserServiceServer;
+use crate::grpc::user_service::UserGrpcService;
+
+/// Services reported by `grpc.health.v1.Health`; `""` is the server as a whole.
+const HEALTH_SERVICES: [&str; 3] = ["", "backend.v1.AuthService", "backend.v1.UserService"];
+
+fn env_u64(name: &str, default: u64) -> u64 {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+/// Whether the gRPC services are served at all (`GRPC_ENABLED`, default `true`).
+pub fn grpc_enabled() -> bool {
+    std::env::var("GRPC_ENABLED").map_or(true, |v| v != "false")
+}
+
+/// Dedicated port for gRPC (`GRPC_PORT`); `None` multiplexes it on the HTTP listener.
+pub fn grpc_port() -> Option<u16> {
+    std::env::var("GRPC_PORT").ok().and_then(|v| v.parse().ok())
+}
+
+/// Time between database checks behind the health status (`GRPC_HEALTH_INTERVAL_SECS`,
+/// default 10).
+fn health_interval() -> Duration {
+    Duration::from_secs(env_u64("GRPC_HEALTH_INTERVAL_SECS", 10).max(1))
+}
+
+/// Report every service as serving while the database answers, like `GET /health`.
+async fn watch_database(reporter: HealthReporter, db: MySqlPool) {
+    let mut interval = tokio::time::interval(health_interval());
+    let mut last = None;
+    loop {
+        interval.tick().await;
+        let status = match sqlx::query_scalar::<_, i64>("SELECT 1")
+            .fetch_one(&db)
+            .await
+        {
+            Ok(_) => ServingStatus::Serving,
+            Err(e) => {
+                tracing::warn!("grpc health: database check failed: {}", e);
+                ServingStatus::NotServing
+            }
+        };
+        if last != Some(status) {
+            for service in HEALTH_SERVICES {
+                reporter.set_service_status(service, status).await;
+            }
+            last = Some(status);
+        }
+    }
+}
+
+/// `/<package>.<Service>/{*method}`, the path prefix of a gRPC service.
+fn service_path<S: NamedService>(_: &S) -> String {
+    format!("/{}/{{*method}}", S::NAME)
+}
+
+/// gRPC services (`AuthService`, `UserService` and `grpc.health.v1.Health`) as a router,
+/// behind the same client IP and rate limiting middleware as the REST API. Requests are
+/// told apart from REST calls by their `/<package>.<Service>/<Method>` paths, so the router
+/// can be merged into the HTTP app or served on its own listener (it has no fallback, so
+/// unknown REST paths keep answering 404).
+pub fn grpc_router(db: MySqlPool) -> Router {
+    let (reporter, health) = tonic_health::server::health_reporter();
+    tokio::spawn(watch_database(reporter, db.clone()));
+
+    let auth = AuthServiceServer::new(AuthGrpcService::new(db.clone()));
+    let users = UserServiceServer::new(UserGrpcService::new(db));
+    Router::new()
+        .route_service(&service_path(&health), health)
+        .route_service(&service_path(&auth), auth)
+        .route_service(&service_path(&users), users)
+        .layer(middleware::from_fn(
+            crate::middlewares::rate_limiter::rate_limiter,
+        ))
+        .layer(middleware::from_fn(
+            crate::middlewares::proxy::proxy_middleware,
+        ))
+}
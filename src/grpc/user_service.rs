@@ -0,0 +1,205 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{Map, Value, json};
+use sqlx::MySqlPool;
+use std::collections::BTreeMap;
+use tonic::{Request, Response, Status};
+
+// Import generated messages and the service trait
+use crate::grpc::pb::user_service_server::UserService;
+use crate::grpc::pb::{GetUserRequest, ListUsersRequest, ListUsersResponse, User};
+
+// Import gRPC auth and error mapping
+use crate::grpc::auth::{authorizer, to_status};
+
+// Import the user operations shared with the REST handlers
+use crate::handlers::user_handler::{
+    VersionedUser, attribute_viewer, authorize_user_action, fetch_user, keyset_page,
+};
+use crate::schemas::user_schema::UserResponseSchema;
+
+// Import util response API and list helpers
+use crate::utils::response::ApiResponse;
+use crate::utils::user_attributes::current_attribute_schema;
+use crate::utils::user_query::{
+    PageDirection, UserFilters, decode_forward_cursor, encode_keyset_cursor, parse_sort,
+    query_fingerprint,
+};
+use crate::utils::user_status::UserStatus;
+
+/// Largest `page_size` accepted by `ListUsers`.
+const MAX_PAGE_SIZE: i32 = 100;
+
+fn timestamp(t: DateTime<Utc>) -> String {
+    t.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+fn attributes_json(attributes: Option<Value>) -> String {
+    attributes.unwrap_or_else(|| json!({})).to_string()
+}
+
+impl From<UserResponseSchema> for User {
+    fn from(user: UserResponseSchema) -> Self {
+        Self {
+            id: user.id,
+            name: user.name,
+            email: user.email,
+            status: user.status.unwrap_or_default(),
+            created_at: timestamp(user.created_at),
+            updated_at: timestamp(user.updated_at),
+            version: user.version,
+            avatar_url: user.avatar_url.unwrap_or_default(),
+            attributes_json: attributes_json(user.attributes),
+        }
+    }
+}
+
+impl From<VersionedUser> for User {
+    fn from(VersionedUser { user, version }: VersionedUser) -> Self {
+        Self {
+            id: user.id,
+            name: user.name,
+            email: user.email,
+            status: user.status.unwrap_or_default(),
+            created_at: timestamp(user.created_at),
+            updated_at: timestamp(user.updated_at),
+            version,
+            avatar_url: user.avatar_url.unwrap_or_default(),
+            attributes_json: attributes_json(user.attributes),
+        }
+    }
+}
+
+fn invalid_argument(errors: Map<String, Value>) -> Status {
+    let response = ApiResponse::error_with_data(
+        "Validation error",
+        json!({ "errors": Value::Object(errors) }),
+    );
+    to_status((axum::http::StatusCode::BAD_REQUEST, axum::Json(response)))
+}
+
+/// `backend.v1.UserService`: read access to users under the caller's policies.
+pub struct UserGrpcService {
+    db: MySqlPool,
+}
+
+impl UserGrpcService {
+    pub fn new(db: MySqlPool) -> Self {
+        Self { db }
+    }
+}
+
+#[tonic::async_trait]
+impl UserService for UserGrpcService {
+    async fn get_user(&self, request: Request<GetUserRequest>) -> Result<Response<User>, Status> {
+        let authz = authorizer(&self.db, &request).await?;
+        let id = request.get_ref().id;
+        authorize_user_action(&self.db, &authz, "users:read", id)
+            .await
+            .map_err(to_status)?;
+        let viewer = attribute_viewer(&self.db, &authz)
+            .await
+            .map_err(to_status)?;
+        let user = fetch_user(&self.db, id, &viewer).await.map_err(to_status)?;
+        Ok(Response::new(user.into()))
+    }
+
+    async fn list_users(
+        &self,
+        request: Request<ListUsersRequest>,
+    ) -> Result<Response<ListUsersResponse>, Status> {
+        let authz = authorizer(&self.db, &request).await?;
+        authz
+            .authorize("users:list", &json!({}))
+            .map_err(to_status)?;
+        let params = request.into_inner();
+
+        let mut errors = Map::new();
+        let page_size = match params.page_size {
+            0 => 50,
+            size if (1..=MAX_PAGE_SIZE).contains(&size) => size,
+            _ => {
+                errors.insert(
+                    "page_size".to_string(),
+                    json!([format!("Must be between 1 and {}", MAX_PAGE_SIZE)]),
+                );
+                0
+            }
+        };
+        let sort = parse_sort(Some(params.sort.as_str()).filter(|s| !s.trim().is_empty()))
+            .unwrap_or_else(|msg| {
+                errors.insert("sort".to_string(), json!([msg]));
+                Vec::new()
+            });
+        let mut status = Vec::new();
+        for raw in &params.status {
+            match UserStatus::parse(&raw.trim().to_ascii_lowercase()) {
+                Some(s) if !status.contains(&s) => status.push(s),
+                Some(_) => {}
+                None => {
+                    errors.insert(
+                        "status".to_string(),
+                        json!(["Must be one of: active, suspended, banned, pending"]),
+                    );
+                }
+            }
+        }
+        fn non_empty(v: &str) -> Option<String> {
+            Some(v.trim().to_string()).filter(|s| !s.is_empty())
+        }
+        let filters = UserFilters {
+            q: non_empty(&params.q),
+            email: non_empty(&params.email).map(|e| e.to_lowercase()),
+            status,
+            created_after: None,
+            created_before: None,
+            include_deleted: false,
+            attributes: BTreeMap::new(),
+        };
+        let fingerprint = query_fingerprint(&filters, &sort);
+        let cursor = match non_empty(&params.page_token) {
+            Some(raw) if !sort.is_empty() => {
+                match decode_forward_cursor(&raw, &fingerprint, &sort) {
+                    Ok(c) => Some(c),
+                    Err(msg) => {
+                        errors.insert("page_token".to_string(), json!([msg]));
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+        if !errors.is_empty() {
+            return Err(invalid_argument(errors));
+        }
+
+        let viewer = attribute_viewer(&self.db, &authz)
+            .await
+            .map_err(to_status)?;
+        let (rows, has_more) =
+            keyset_page(&self.db, &filters, &sort, cursor.as_ref(), page_size as u32)
+                .await
+                .map_err(|e| {
+                    tracing::error!("grpc: failed to list users: {}", e);
+                    Status::internal("Failed to fetch users")
+                })?;
+
+        let next_page_token = rows
+            .last()
+            .filter(|_| has_more)
+            .map(|row| encode_keyset_cursor(&fingerprint, PageDirection::Next, &row.user, &sort))
+            .unwrap_or_default();
+        let schema = current_attribute_schema();
+        let users = rows
+            .into_iter()
+            .map(|mut row| {
+                let user = &mut row.user;
+                user.attributes = Some(schema.visible(user.attributes.as_ref(), &viewer, user.id));
+                row.into()
+            })
+            .collect();
+        Ok(Response::new(ListUsersResponse {
+            users,
+            next_page_token,
+        }))
+    }
+}
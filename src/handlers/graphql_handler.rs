@@ -0,0 +1,355 @@
+use async_graphql::connection::{Connection, Edge, EmptyFields};
+use async_graphql::http::GraphiQLSource;
+use async_graphql::{Context, EmptySubscription, ErrorExtensions, Object, Schema};
+use axum::response::Html;
+use axum::{Extension, Json, http::StatusCode};
+use once_cell::sync::Lazy;
+use serde_json::{Map, Value, json};
+use sqlx::MySqlPool;
+use std::collections::BTreeMap;
+
+// Import GraphQL types and the user schemas they map to
+use crate::schemas::graphql_schema::{
+    CreateUserInput, GraphqlUser, UpdateUserInput, UserFilterInput,
+};
+use crate::schemas::user_schema::UserUpdateRequestSchema;
+
+// Import the user operations shared with the REST handlers
+use crate::handlers::user_handler::{
+    VersionedUser, actor_id, attribute_viewer, authorize_user_action, count_users, create_user,
+    delete_user, fetch_user, keyset_page, precondition_failed, update_user,
+};
+
+// Import util response API, list helpers and the policy guard
+use crate::middlewares::admin_middleware::is_admin;
+use crate::middlewares::policy_guard::Authorizer;
+use crate::utils::response::ApiResponse;
+use crate::utils::user_attributes::current_attribute_schema;
+use crate::utils::user_query::{
+    CountMode, PageDirection, UserFilters, decode_forward_cursor, encode_keyset_cursor, parse_sort,
+    query_fingerprint,
+};
+use crate::utils::validation::validate_payload;
+
+// Type error response alias
+type GraphqlErrorResponse = (StatusCode, Json<ApiResponse<Value>>);
+
+pub type UserGraphqlSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+
+/// Largest `first` accepted by connections.
+const MAX_PAGE_SIZE: i32 = 100;
+
+fn env_u64(name: &str, default: u64) -> u64 {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+/// Deepest selection nesting accepted (`GRAPHQL_MAX_DEPTH`, default 10).
+fn max_depth() -> usize {
+    env_u64("GRAPHQL_MAX_DEPTH", 10).max(1) as usize
+}
+
+/// Highest complexity accepted (`GRAPHQL_MAX_COMPLEXITY`, default 1000). Every field counts
+/// 1; connections count their selection once per requested item (`first`).
+fn max_complexity() -> usize {
+    env_u64("GRAPHQL_MAX_COMPLEXITY", 1000).max(1) as usize
+}
+
+/// Whether `GET /graphql` serves the GraphiQL IDE (`GRAPHQL_GRAPHIQL=true`).
+pub fn graphiql_enabled() -> bool {
+    std::env::var("GRAPHQL_GRAPHIQL").is_ok_and(|v| v == "true")
+}
+
+/// Built once; limits are read from the environment on first use.
+static SCHEMA: Lazy<UserGraphqlSchema> = Lazy::new(|| {
+    Schema::build(QueryRoot, MutationRoot, EmptySubscription)
+        .limit_depth(max_depth())
+        .limit_complexity(max_complexity())
+        .finish()
+});
+
+/// Turn an API error response into a GraphQL error: same message, with the HTTP status as
+/// `extensions.code` (e.g. `NOT_FOUND`) and `extensions.status`, and the response data as
+/// `extensions.details`.
+fn graphql_error((status, Json(body)): GraphqlErrorResponse) -> async_graphql::Error {
+    let code = status
+        .canonical_reason()
+        .unwrap_or("Error")
+        .to_ascii_uppercase()
+        .replace(' ', "_");
+    let details = body
+        .data
+        .and_then(|data| async_graphql::Value::from_json(data).ok());
+    async_graphql::Error::new(body.message).extend_with(|_, e| {
+        e.set("code", code);
+        e.set("status", status.as_u16() as i32);
+        if let Some(details) = details {
+            e.set("details", details);
+        }
+    })
+}
+
+fn validation_error(errors: Map<String, Value>) -> GraphqlErrorResponse {
+    let response = ApiResponse::error_with_data(
+        "Validation error",
+        json!({ "errors": Value::Object(errors) }),
+    );
+    (StatusCode::BAD_REQUEST, Json(response))
+}
+
+fn db_error(e: sqlx::Error) -> GraphqlErrorResponse {
+    let response = ApiResponse::error_with_data(
+        "Database error",
+        json!({ "error": "Failed to fetch users", "details": e.to_string() }),
+    );
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
+}
+
+/// Pool and caller, put into every request by [`graphql_handler`].
+fn request_data<'a>(ctx: &'a Context<'_>) -> (&'a MySqlPool, &'a Authorizer) {
+    (ctx.data_unchecked(), ctx.data_unchecked())
+}
+
+/// Fail with `412` when the caller read the user at another version.
+fn check_version(expected: Option<i64>, version: i64) -> Result<(), GraphqlErrorResponse> {
+    match expected {
+        Some(v) if v != version => Err(precondition_failed()),
+        _ => Ok(()),
+    }
+}
+
+/// Extra fields of `UserConnection`.
+pub struct UserConnectionFields {
+    filters: UserFilters,
+}
+
+#[Object]
+impl UserConnectionFields {
+    /// Users matching the filters across all pages (counted only when selected).
+    async fn total_count(&self, ctx: &Context<'_>) -> async_graphql::Result<i64> {
+        let (db, _) = request_data(ctx);
+        let total = count_users(db, &self.filters, CountMode::Exact)
+            .await
+            .map_err(|e| graphql_error(db_error(e)))?;
+        Ok(total.unwrap_or_default())
+    }
+}
+
+type UserConnection = Connection<String, GraphqlUser, UserConnectionFields, EmptyFields>;
+
+/// Keyset-paginated listing behind the `users` connection.
+async fn list_users(
+    db: &MySqlPool,
+    authz: &Authorizer,
+    filter: UserFilterInput,
+    sort: Option<String>,
+    first: i32,
+    after: Option<String>,
+) -> Result<UserConnection, GraphqlErrorResponse> {
+    authz.authorize("users:list", &json!({}))?;
+
+    let mut errors = Map::new();
+    let sort = parse_sort(sort.as_deref()).unwrap_or_else(|msg| {
+        errors.insert("sort".to_string(), json!([msg]));
+        Vec::new()
+    });
+    if !(1..=MAX_PAGE_SIZE).contains(&first) {
+        errors.insert(
+            "first".to_string(),
+            json!([format!("Must be between 1 and {}", MAX_PAGE_SIZE)]),
+        );
+    }
+    fn non_empty(v: Option<String>) -> Option<String> {
+        v.map(|s| s.trim().to_string()).filter(|s| !s.is_empty())
+    }
+    let mut status = Vec::new();
+    for s in filter.status.unwrap_or_default() {
+        let s = s.into();
+        if !status.contains(&s) {
+            status.push(s);
+        }
+    }
+    let filters = UserFilters {
+        q: non_empty(filter.q),
+        email: non_empty(filter.email).map(|e| e.to_lowercase()),
+        status,
+        created_after: filter.created_after,
+        created_before: filter.created_before,
+        include_deleted: filter.include_deleted,
+        attributes: BTreeMap::new(),
+    };
+    let fingerprint = query_fingerprint(&filters, &sort);
+    // Only forward cursors issued by this connection are accepted
+    let cursor = match non_empty(after) {
+        Some(raw) if !sort.is_empty() => match decode_forward_cursor(&raw, &fingerprint, &sort) {
+            Ok(c) => Some(c),
+            Err(msg) => {
+                errors.insert("after".to_string(), json!([msg]));
+                None
+            }
+        },
+        _ => None,
+    };
+    if !errors.is_empty() {
+        return Err(validation_error(errors));
+    }
+
+    // Soft-deleted users are only visible to admins (role read from the database)
+    let caller = actor_id(authz).unwrap_or_default();
+    if filters.include_deleted && !is_admin(db, caller).await.map_err(db_error)? {
+        let response = ApiResponse::error_with_data(
+            "Forbidden",
+            json!({ "error": "Admin privileges required for includeDeleted" }),
+        );
+        return Err((StatusCode::FORBIDDEN, Json(response)));
+    }
+    let viewer = attribute_viewer(db, authz).await?;
+
+    let (rows, has_next) = keyset_page(db, &filters, &sort, cursor.as_ref(), first as u32)
+        .await
+        .map_err(db_error)?;
+
+    let schema = current_attribute_schema();
+    let mut connection = Connection::with_additional_fields(
+        cursor.is_some(),
+        has_next,
+        UserConnectionFields { filters },
+    );
+    connection.edges = rows
+        .into_iter()
+        .map(|VersionedUser { mut user, version }| {
+            let cursor = encode_keyset_cursor(&fingerprint, PageDirection::Next, &user, &sort);
+            user.attributes = Some(schema.visible(user.attributes.as_ref(), &viewer, user.id));
+            Edge::new(cursor, GraphqlUser::from_list_row(user, version))
+        })
+        .collect();
+    Ok(connection)
+}
+
+pub struct QueryRoot;
+
+#[Object(name = "Query")]
+impl QueryRoot {
+    /// The signed-in user.
+    async fn me(&self, ctx: &Context<'_>) -> async_graphql::Result<GraphqlUser> {
+        let (db, authz) = request_data(ctx);
+        let viewer = attribute_viewer(db, authz).await.map_err(graphql_error)?;
+        let user = fetch_user(db, viewer.user_id, &viewer)
+            .await
+            .map_err(graphql_error)?;
+        Ok(user.into())
+    }
+
+    /// A user by id (`users:read`), or `null` when there is none.
+    async fn user(&self, ctx: &Context<'_>, id: i64) -> async_graphql::Result<Option<GraphqlUser>> {
+        let (db, authz) = request_data(ctx);
+        match authorize_user_action(db, authz, "users:read", id).await {
+            Ok(_) => {}
+            Err((StatusCode::NOT_FOUND, _)) => return Ok(None),
+            Err(e) => return Err(graphql_error(e)),
+        }
+        let viewer = attribute_viewer(db, authz).await.map_err(graphql_error)?;
+        let user = fetch_user(db, id, &viewer).await.map_err(graphql_error)?;
+        Ok(Some(user.into()))
+    }
+
+    /// Users the caller may list (`users:list`), as a Relay connection. `sort` takes the
+    /// `GET /users` syntax (e.g. `name,-created_at`); `after` is an edge cursor.
+    #[graphql(complexity = "first.clamp(1, MAX_PAGE_SIZE) as usize * child_complexity")]
+    async fn users(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<UserFilterInput>,
+        sort: Option<String>,
+        #[graphql(default = 20)] first: i32,
+        after: Option<String>,
+    ) -> async_graphql::Result<UserConnection> {
+        let (db, authz) = request_data(ctx);
+        list_users(db, authz, filter.unwrap_or_default(), sort, first, after)
+            .await
+            .map_err(graphql_error)
+    }
+}
+
+pub struct MutationRoot;
+
+#[Object(name = "Mutation")]
+impl MutationRoot {
+    /// Create a user (`users:create`), validated like `POST /users`.
+    async fn create_user(
+        &self,
+        ctx: &Context<'_>,
+        input: CreateUserInput,
+    ) -> async_graphql::Result<GraphqlUser> {
+        let (db, authz) = request_data(ctx);
+        let user = create_user(db, authz, &input.into())
+            .await
+            .map_err(graphql_error)?;
+        Ok(user.into())
+    }
+
+    /// Update a user (`users:update`), validated like `PUT /users/{id}`. With `version`, the
+    /// update fails with `PRECONDITION_FAILED` if the user changed since it was read.
+    async fn update_user(
+        &self,
+        ctx: &Context<'_>,
+        id: i64,
+        input: UpdateUserInput,
+        version: Option<i64>,
+    ) -> async_graphql::Result<GraphqlUser> {
+        let (db, authz) = request_data(ctx);
+        let result: Result<GraphqlUser, GraphqlErrorResponse> = async {
+            let current_version = authorize_user_action(db, authz, "users:update", id).await?;
+            check_version(version, current_version)?;
+            let viewer = attribute_viewer(db, authz).await?;
+            let current = fetch_user(db, id, &viewer).await?;
+            let payload = UserUpdateRequestSchema {
+                name: input.name.unwrap_or(current.name),
+                email: input.email.unwrap_or(current.email),
+                password: input.password,
+                attributes: input.attributes.map(|a| a.0),
+            };
+            validate_payload(&payload)?;
+            update_user(db, authz, id, current_version, &payload).await?;
+            Ok(fetch_user(db, id, &viewer).await?.into())
+        }
+        .await;
+        result.map_err(graphql_error)
+    }
+
+    /// Soft-delete a user (`users:delete`); returns its id. `version` works as in `updateUser`.
+    async fn delete_user(
+        &self,
+        ctx: &Context<'_>,
+        id: i64,
+        version: Option<i64>,
+    ) -> async_graphql::Result<i64> {
+        let (db, authz) = request_data(ctx);
+        let result: Result<i64, GraphqlErrorResponse> = async {
+            let current_version = authorize_user_action(db, authz, "users:delete", id).await?;
+            check_version(version, current_version)?;
+            delete_user(db, authz, id, current_version).await?;
+            Ok(id)
+        }
+        .await;
+        result.map_err(graphql_error)
+    }
+}
+
+/// `POST /graphql` — runs a query or mutation as the caller of the bearer token (checked by
+/// `auth_middleware`, like every other authenticated route).
+pub async fn graphql_handler(
+    Extension(db): Extension<MySqlPool>,
+    authz: Authorizer,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    Json(SCHEMA.execute(request.data(db).data(authz)).await)
+}
+
+/// `GET /graphql` — GraphiQL IDE (only routed when `GRAPHQL_GRAPHIQL=true`). Requests need
+/// an `Authorization: Bearer ...` header, set in its headers tab.
+pub async fn graphiql() -> Html<String> {
+    Html(GraphiQLSource::build().endpoint("/graphql").finish())
+}
@@ -0,0 +1,427 @@
+use axum::extract::Path;
+use axum::http::{HeaderMap, header};
+use axum::{Extension, Json, http::StatusCode};
+
+use crate::utils::handler::HandlerResult;
+use serde_json::{Value, json};
+use sqlx::MySqlPool;
+
+// Import models
+use crate::models::invitation::Invitation;
+use crate::models::user::{ROLE_ADMIN, ROLE_USER};
+
+// Import schemas for invitations
+use crate::schemas::invitation_schema::{
+    InvitationAcceptRequestSchema, InvitationResponseSchema, InvitationStoreRequestSchema,
+};
+
+// Import util response API, JWT claims and token helpers
+use crate::utils::jwt::{Claims, generate_jwt_token_for, verify_jwt_token};
+use crate::utils::notifications::{NOTIFY_ROLE_UPDATED, notify_user};
+use crate::utils::response::ApiResponse;
+use crate::utils::token::{generate_token, hash_token};
+
+// Shared mapper for unexpected database errors
+fn db_error(context: &str, e: sqlx::Error) -> (StatusCode, Json<ApiResponse<Value>>) {
+    let response = ApiResponse::error_with_data(
+        "Database error",
+        json!({ "error": context, "details": e.to_string() }),
+    );
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
+}
+
+// List pending (not accepted, not revoked, not expired) invitations
+pub async fn index(Extension(db): Extension<MySqlPool>) -> HandlerResult {
+    let invitations = sqlx::query_as::<_, InvitationResponseSchema>(
+        r#"
+        SELECT id, email, role, invited_by, expires_at, created_at
+        FROM invitations
+        WHERE accepted_at IS NULL
+          AND revoked_at IS NULL
+          AND expires_at > NOW()
+        ORDER BY id
+        "#,
+    )
+    .fetch_all(&db)
+    .await
+    .map_err(|e| db_error("Failed to fetch invitations", e))?;
+
+    let response = ApiResponse::success_with_data(
+        "Invitations fetched successfully",
+        json!({ "invitations": invitations }),
+    );
+    Ok((StatusCode::OK, Json(response)))
+}
+
+// Create an invitation and return the raw token once
+pub async fn store(
+    Extension(db): Extension<MySqlPool>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<InvitationStoreRequestSchema>,
+) -> HandlerResult {
+    // Validate the incoming payload (reusable helper)
+    crate::utils::validation::validate_payload(&payload)?;
+
+    let email_normalized = payload.email.trim().to_lowercase();
+    let role = payload
+        .role
+        .clone()
+        .unwrap_or_else(|| ROLE_USER.to_string());
+
+    // Default expiry configurable via INVITATION_TTL_HOURS (default 72h)
+    let expires_in_hours = payload.expires_in_hours.unwrap_or_else(|| {
+        std::env::var("INVITATION_TTL_HOURS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(72)
+    });
+
+    // Only one pending invitation per email at a time
+    let pending: i64 = sqlx::query_scalar(
+        r#"
+        SELECT EXISTS(
+            SELECT 1 FROM invitations
+            WHERE email = ?
+              AND accepted_at IS NULL
+              AND revoked_at IS NULL
+              AND expires_at > NOW()
+        )
+        "#,
+    )
+    .bind(&email_normalized)
+    .fetch_one(&db)
+    .await
+    .map_err(|e| db_error("Failed to check existing invitations", e))?;
+
+    if pending == 1 {
+        let response = ApiResponse::error_with_data(
+            "Conflict",
+            json!({ "error": "A pending invitation already exists for this email", "field": "email" }),
+        );
+        return Err((StatusCode::CONFLICT, Json(response)));
+    }
+
+    let token = generate_token();
+    let res = sqlx::query(
+        r#"
+        INSERT INTO invitations (email, role, token_hash, invited_by, expires_at)
+        VALUES (?, ?, ?, ?, DATE_ADD(NOW(), INTERVAL ? HOUR))
+        "#,
+    )
+    .bind(&email_normalized)
+    .bind(&role)
+    .bind(hash_token(&token))
+    .bind(claims.sub)
+    .bind(expires_in_hours)
+    .execute(&db)
+    .await
+    .map_err(|e| db_error("Failed to create invitation", e))?;
+
+    let invitation_id = res.last_insert_id() as i64;
+    let invitation = sqlx::query_as::<_, InvitationResponseSchema>(
+        r#"
+        SELECT id, email, role, invited_by, expires_at, created_at
+        FROM invitations
+        WHERE id = ?
+        "#,
+    )
+    .bind(invitation_id)
+    .fetch_one(&db)
+    .await
+    .map_err(|e| db_error("Failed to fetch invitation", e))?;
+
+    tracing::info!(
+        "invitation created: id={} email={} role={} invited_by={}",
+        invitation.id,
+        invitation.email,
+        invitation.role,
+        claims.sub
+    );
+
+    let response = ApiResponse::success_with_data(
+        "Invitation created",
+        json!({ "invitation": invitation, "token": token }),
+    );
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+// Revoke a pending invitation
+pub async fn destroy(
+    Path(id): Path<i64>,
+    Extension(db): Extension<MySqlPool>,
+    Extension(claims): Extension<Claims>,
+) -> HandlerResult {
+    let res = sqlx::query(
+        r#"
+        UPDATE invitations
+        SET revoked_at = NOW()
+        WHERE id = ?
+          AND accepted_at IS NULL
+          AND revoked_at IS NULL
+        "#,
+    )
+    .bind(id)
+    .execute(&db)
+    .await
+    .map_err(|e| db_error("Failed to revoke invitation", e))?;
+
+    if res.rows_affected() == 0 {
+        let response = ApiResponse::error_with_data(
+            "Not Found",
+            json!({ "error": "Pending invitation not found" }),
+        );
+        return Err((StatusCode::NOT_FOUND, Json(response)));
+    }
+
+    tracing::info!("invitation revoked: id={} revoked_by={}", id, claims.sub);
+
+    let response =
+        ApiResponse::success_with_data("Invitation revoked", json!({ "invitation_id": id }));
+    Ok((StatusCode::OK, Json(response)))
+}
+
+// Whether the caller controls the existing account `user_id`: a session of that account
+// (`Authorization: Bearer`) or its current password. The invitation token alone is not
+// enough, since the inviting admin has seen it.
+async fn proves_account(
+    tx: &mut sqlx::Transaction<'_, sqlx::MySql>,
+    headers: &HeaderMap,
+    user_id: i64,
+    password: Option<&str>,
+) -> Result<bool, (StatusCode, Json<ApiResponse<Value>>)> {
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    if let Some(token) = bearer
+        && let Ok(claims) = verify_jwt_token(token.trim()).await
+        && claims.sub == user_id
+    {
+        return Ok(true);
+    }
+
+    let Some(password) = password else {
+        return Ok(false);
+    };
+    let stored: String = sqlx::query_scalar("SELECT password FROM users WHERE id = ?")
+        .bind(user_id)
+        .fetch_one(&mut **tx)
+        .await
+        .map_err(|e| db_error("Failed to fetch user", e))?;
+    let timeout_secs: Option<u64> = std::env::var("BCRYPT_VERIFY_TIMEOUT_SECONDS")
+        .ok()
+        .and_then(|v| v.parse().ok());
+    crate::utils::auth::verify_password_blocking(password.to_string(), stored, timeout_secs)
+        .await
+        .map_err(|e| {
+            tracing::error!("hash verify error: {}", e);
+            let response = ApiResponse::error_with_data(
+                "Hash error",
+                json!({ "error": "Failed to verify password" }),
+            );
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
+        })
+}
+
+// Accept an invitation: attach an existing account (signed in, or with its password) or
+// create a new one
+pub async fn accept(
+    Extension(db): Extension<MySqlPool>,
+    headers: HeaderMap,
+    Json(payload): Json<InvitationAcceptRequestSchema>,
+) -> HandlerResult {
+    // Validate the incoming payload (reusable helper)
+    crate::utils::validation::validate_payload(&payload)?;
+
+    // Lock the invitation row for the whole acceptance so concurrent accepts cannot both succeed
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| db_error("Failed to start transaction", e))?;
+
+    let invitation = sqlx::query_as::<_, Invitation>(
+        r#"
+        SELECT id, email, role, invited_by, expires_at, accepted_at, accepted_user_id,
+               revoked_at, created_at, updated_at
+        FROM invitations
+        WHERE token_hash = ?
+        FOR UPDATE
+        "#,
+    )
+    .bind(hash_token(&payload.token))
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| db_error("Failed to fetch invitation", e))?;
+
+    let invitation = match invitation {
+        Some(inv) => inv,
+        None => {
+            let response = ApiResponse::error_with_data(
+                "Not Found",
+                json!({ "error": "Invitation not found" }),
+            );
+            return Err((StatusCode::NOT_FOUND, Json(response)));
+        }
+    };
+
+    let gone_reason = if invitation.accepted_at.is_some() {
+        Some("Invitation has already been used")
+    } else if invitation.revoked_at.is_some() {
+        Some("Invitation has been revoked")
+    } else if invitation.expires_at <= chrono::Utc::now() {
+        Some("Invitation has expired")
+    } else {
+        None
+    };
+    if let Some(reason) = gone_reason {
+        tracing::warn!(
+            "invitation accept rejected: id={} reason={}",
+            invitation.id,
+            reason
+        );
+        let response = ApiResponse::error_with_data("Gone", json!({ "error": reason }));
+        return Err((StatusCode::GONE, Json(response)));
+    }
+
+    let existing_user_id: Option<i64> = sqlx::query_scalar(
+        "SELECT id FROM users WHERE email = ? AND deleted_at IS NULL FOR UPDATE",
+    )
+    .bind(&invitation.email)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| db_error("Failed to check existing user", e))?;
+
+    let mut promoted = false;
+    let (user_id, created) = match existing_user_id {
+        Some(user_id) => {
+            if !proves_account(&mut tx, &headers, user_id, payload.password.as_deref()).await? {
+                tracing::warn!(
+                    "invitation accept rejected: id={} reason=existing account not proven",
+                    invitation.id
+                );
+                let response = ApiResponse::error_with_data(
+                    "Conflict",
+                    json!({
+                        "error": "An account already exists for this email; accept the invitation while signed in to it or with its current password"
+                    }),
+                );
+                return Err((StatusCode::CONFLICT, Json(response)));
+            }
+            // Attach the existing account; never demote an existing admin
+            if invitation.role == ROLE_ADMIN {
+                let res = sqlx::query(
+                    "UPDATE users SET role = ?, version = version + 1 WHERE id = ? AND role <> ?",
+                )
+                .bind(ROLE_ADMIN)
+                .bind(user_id)
+                .bind(ROLE_ADMIN)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| db_error("Failed to update user role", e))?;
+                promoted = res.rows_affected() > 0;
+            }
+            (user_id, false)
+        }
+        None => {
+            // Creating an account requires the registration fields
+            let mut errors_map = serde_json::Map::new();
+            if payload.name.is_none() {
+                errors_map.insert(
+                    "name".to_string(),
+                    json!(["Name is required to create an account"]),
+                );
+            }
+            if payload.password.is_none() {
+                errors_map.insert(
+                    "password".to_string(),
+                    json!(["Password is required to create an account"]),
+                );
+            }
+            if !errors_map.is_empty() {
+                let response = ApiResponse::error_with_data(
+                    "Validation error",
+                    json!({ "errors": Value::Object(errors_map) }),
+                );
+                return Err((StatusCode::BAD_REQUEST, Json(response)));
+            }
+
+            let user = crate::handlers::register_handler::insert_user(
+                &mut tx,
+                payload.name.as_deref().unwrap_or_default(),
+                &invitation.email,
+                payload.password.as_deref().unwrap_or_default(),
+                &invitation.role,
+                crate::utils::user_status::UserStatus::Active,
+            )
+            .await?;
+            (user.id, true)
+        }
+    };
+
+    sqlx::query(
+        r#"
+        UPDATE invitations
+        SET accepted_at = NOW(), accepted_user_id = ?
+        WHERE id = ?
+        "#,
+    )
+    .bind(user_id)
+    .bind(invitation.id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| db_error("Failed to mark invitation accepted", e))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| db_error("Failed to commit invitation acceptance", e))?;
+    if promoted {
+        notify_user(user_id, NOTIFY_ROLE_UPDATED, json!({ "role": ROLE_ADMIN }));
+    }
+
+    tracing::info!(
+        "invitation accepted: id={} email={} user_id={} created={}",
+        invitation.id,
+        invitation.email,
+        user_id,
+        created
+    );
+
+    let user = sqlx::query_as::<_, crate::schemas::register_schema::RegisterResponseSchema>(
+        r#"
+        SELECT id, name, email, created_at, updated_at, status
+        FROM users
+        WHERE id = ?
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(&db)
+    .await
+    .map_err(|e| db_error("Failed to fetch user", e))?;
+
+    let role: String = sqlx::query_scalar("SELECT role FROM users WHERE id = ?")
+        .bind(user_id)
+        .fetch_one(&db)
+        .await
+        .map_err(|e| db_error("Failed to fetch user role", e))?;
+
+    let token = generate_jwt_token_for(user_id, &role, serde_json::Map::new())
+        .await
+        .map_err(|e| {
+            let response = ApiResponse::error_with_data(
+                "Token error",
+                json!({ "error": "Failed to generate token", "details": e.to_string() }),
+            );
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
+        })?;
+
+    let status = if created {
+        StatusCode::CREATED
+    } else {
+        StatusCode::OK
+    };
+    let response = ApiResponse::success_with_data(
+        "Invitation accepted",
+        json!({ "user": user, "token": token, "created": created }),
+    );
+    Ok((status, Json(response)))
+}
@@ -1,21 +1,36 @@
 use axum::{Extension, Json, http::StatusCode};
 
 use crate::utils::handler::HandlerResult;
-use serde_json::json;
+use serde_json::{Value, json};
 use sqlx::MySqlPool;
 //import schemas for login request and response
 use crate::schemas::login_schema::{LoginResponseSchema, LoginSchema};
 //import util response API
 use crate::utils::response::ApiResponse;
 //import util JWT generation
-use crate::utils::jwt::generate_jwt_token;
+use crate::utils::jwt::generate_jwt_token_for;
+//import account status lifecycle
+use crate::utils::user_status::{EFFECTIVE_STATUS_SQL, UserStatus};
+//import webhook outbox
+use crate::utils::webhooks::record_login;
 // Handler for user login
 pub async fn login_handler(
     Extension(db_pool): Extension<MySqlPool>,
     Json(payload): Json<LoginSchema>,
 ) -> HandlerResult {
+    let login_response = login(&db_pool, &payload).await?;
+    let response = ApiResponse::success_with_data("Login successful", json!(login_response));
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// Check email and password, issue a token and record the login. Shared by `POST /login`
+/// and the gRPC `AuthService.Login`.
+pub(crate) async fn login(
+    db_pool: &MySqlPool,
+    payload: &LoginSchema,
+) -> Result<LoginResponseSchema, (StatusCode, Json<ApiResponse<Value>>)> {
     // Validate the incoming payload (reusable helper)
-    crate::utils::validation::validate_payload(&payload)?;
+    crate::utils::validation::validate_payload(payload)?;
     // Normalize email for consistent lookup
     let email_normalized = payload.email.trim().to_lowercase();
     // Fetch user by email (include password to avoid extra roundtrip)
@@ -27,20 +42,25 @@ pub async fn login_handler(
         created_at: chrono::DateTime<chrono::Utc>,
         updated_at: chrono::DateTime<chrono::Utc>,
         password: String,
+        role: String,
+        active: bool,
+        status: String,
     }
 
-    let user_record = sqlx::query_as::<_, UserWithPassword>(
+    let user_record = sqlx::query_as::<_, UserWithPassword>(&format!(
         r#"
-        SELECT id, name, email, created_at, updated_at, password
+        SELECT id, name, email, created_at, updated_at, password, role, active,
+               {} AS status
         FROM users
-        WHERE email = ?
+        WHERE email = ? AND deleted_at IS NULL
         "#,
-    )
+        EFFECTIVE_STATUS_SQL
+    ))
     .bind(&email_normalized)
-    .fetch_optional(&db_pool)
+    .fetch_optional(db_pool)
     .await;
 
-    let (user, stored_password) = match user_record {
+    let (user, stored_password, role, active, status) = match user_record {
         Ok(Some(u)) => {
             let user_response = crate::schemas::login_schema::UserLoginResponseSchema {
                 id: u.id,
@@ -49,7 +69,7 @@ pub async fn login_handler(
                 created_at: u.created_at,
                 updated_at: u.updated_at,
             };
-            (user_response, u.password)
+            (user_response, u.password, u.role, u.active, u.status)
         }
         _ => {
             let response = ApiResponse::error_with_data(
@@ -91,17 +111,43 @@ pub async fn login_handler(
             return Err((StatusCode::INTERNAL_SERVER_ERROR, Json(response)));
         }
     }
-    // Generate JWT token
-    let token = generate_jwt_token(user.id).await.map_err(|e| {
+    // Deactivated accounts (e.g. deprovisioned via SCIM) may not log in
+    if !active {
+        tracing::warn!(
+            "login rejected: account disabled for email={}",
+            email_normalized
+        );
+        let response =
+            ApiResponse::error_with_data("Forbidden", json!({ "error": "Account is disabled" }));
+        return Err((StatusCode::FORBIDDEN, Json(response)));
+    }
+    // Suspended, banned and pending accounts may not log in (checked after the password so
+    // the status is not revealed to someone without it)
+    let status = UserStatus::parse(&status).unwrap_or(UserStatus::Suspended);
+    if let Some(error) = status.login_error() {
+        tracing::warn!(
+            "login rejected: status={} for email={}",
+            status.as_str(),
+            email_normalized
+        );
         let response = ApiResponse::error_with_data(
-            "Token error",
-            json!({ "error": "Failed to generate token", "details": e.to_string() }),
+            "Forbidden",
+            json!({ "error": error, "status": status.as_str() }),
         );
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
-    })?;
+        return Err((StatusCode::FORBIDDEN, Json(response)));
+    }
+    // Generate JWT token
+    let token = generate_jwt_token_for(user.id, &role, serde_json::Map::new())
+        .await
+        .map_err(|e| {
+            let response = ApiResponse::error_with_data(
+                "Token error",
+                json!({ "error": "Failed to generate token", "details": e.to_string() }),
+            );
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
+        })?;
+    record_login(db_pool, user.id, json!({ "method": "password" })).await;
     // Build response schema
-    let login_response = LoginResponseSchema { user, token };
-    let response = ApiResponse::success_with_data("Login successful", json!(login_response));
-    Ok((StatusCode::OK, Json(response)))
+    Ok(LoginResponseSchema { user, token })
 }
 // Note: In a real application, consider logging failed login attempts for security monitoring.
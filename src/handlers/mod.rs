@@ -1,4 +1,19 @@
+pub mod graphql_handler;
 pub mod health_handler;
+pub mod invitation_handler;
 pub mod login_handler;
+pub mod oidc_handler;
+pub mod policy_handler;
 pub mod register_handler;
+pub mod scim_handler;
+pub mod user_avatar_handler;
+pub mod user_bulk_handler;
+pub mod user_data_handler;
+pub mod user_events_handler;
 pub mod user_handler;
+pub mod user_history_handler;
+pub mod user_search_handler;
+pub mod user_status_handler;
+pub mod user_transfer_handler;
+pub mod webhook_handler;
+pub mod ws_handler;
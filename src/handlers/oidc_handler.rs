@@ -0,0 +1,418 @@
+use axum::extract::{Path, Query};
+use axum::http::{HeaderMap, header};
+use axum::response::{AppendHeaders, IntoResponse, Redirect, Response};
+use axum::{Extension, Json, http::StatusCode};
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+use sqlx::MySqlPool;
+
+use crate::config::oidc::{OidcProviderConfig, get_provider};
+use crate::schemas::login_schema::{LoginResponseSchema, UserLoginResponseSchema};
+use crate::utils::handler::HandlerResult;
+use crate::utils::jwt::generate_jwt_token_for;
+use crate::utils::oidc::{
+    IdTokenClaims, LOGIN_COOKIE, authorization_url, begin_login, cookie_value, exchange_code,
+    login_cookie, open_login, provider_metadata, seal_login, state_ttl, verify_id_token,
+};
+use crate::utils::response::ApiResponse;
+use crate::utils::token::generate_token;
+use crate::utils::user_status::{EFFECTIVE_STATUS_SQL, UserStatus};
+use crate::utils::webhooks::record_login;
+
+type OidcErrorResponse = (StatusCode, Json<ApiResponse<Value>>);
+
+fn oidc_error(status: StatusCode, message: &str, data: Value) -> OidcErrorResponse {
+    (status, Json(ApiResponse::error_with_data(message, data)))
+}
+
+fn db_error(context: &str, e: sqlx::Error) -> OidcErrorResponse {
+    oidc_error(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "Database error",
+        json!({ "error": context, "details": e.to_string() }),
+    )
+}
+
+// Login cookies are signed with JWT_SECRET
+fn login_secret() -> Result<String, OidcErrorResponse> {
+    std::env::var("JWT_SECRET").map_err(|_| {
+        oidc_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Configuration error",
+            json!({ "error": "JWT_SECRET is not set" }),
+        )
+    })
+}
+
+fn lookup_provider(name: &str) -> Result<std::sync::Arc<OidcProviderConfig>, OidcErrorResponse> {
+    get_provider(name).ok_or_else(|| {
+        oidc_error(
+            StatusCode::NOT_FOUND,
+            "Not Found",
+            json!({ "error": "Unknown identity provider" }),
+        )
+    })
+}
+
+#[derive(Deserialize)]
+pub struct StartParams {
+    // `json` returns the authorization URL instead of redirecting (for SPA clients)
+    pub mode: Option<String>,
+}
+
+// Start a federated login: redirect the browser to the provider's authorization endpoint
+pub async fn start(
+    Path(provider_name): Path<String>,
+    Query(params): Query<StartParams>,
+) -> Result<Response, OidcErrorResponse> {
+    let provider = lookup_provider(&provider_name)?;
+    let meta = provider_metadata(&provider).await.map_err(|e| {
+        tracing::error!("oidc: discovery failed for {}: {}", provider.name, e);
+        oidc_error(
+            StatusCode::BAD_GATEWAY,
+            "Bad Gateway",
+            json!({ "error": "Identity provider unavailable", "details": e.to_string() }),
+        )
+    })?;
+
+    let pending = begin_login(&provider.name);
+    let url = authorization_url(&meta, &provider, &pending.state, &pending).map_err(|e| {
+        oidc_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Configuration error",
+            json!({ "error": "Invalid authorization endpoint", "details": e.to_string() }),
+        )
+    })?;
+    // The callback only accepts this state from the browser holding the cookie
+    let sealed = seal_login(&pending, &login_secret()?).map_err(|e| {
+        oidc_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Token error",
+            json!({ "error": "Failed to sign login state", "details": e.to_string() }),
+        )
+    })?;
+    let cookie = AppendHeaders([(
+        header::SET_COOKIE,
+        login_cookie(&provider, &sealed, state_ttl()),
+    )]);
+
+    if params.mode.as_deref() == Some("json") {
+        let response = ApiResponse::success_with_data(
+            "Authorization URL created",
+            json!({ "authorization_url": url }),
+        );
+        return Ok((StatusCode::OK, cookie, Json(response)).into_response());
+    }
+    Ok((cookie, Redirect::to(&url)).into_response())
+}
+
+#[derive(Deserialize)]
+pub struct CallbackParams {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
+
+// Complete a federated login: verify the ID token, link or provision the user, issue our JWT
+pub async fn callback(
+    Path(provider_name): Path<String>,
+    Query(params): Query<CallbackParams>,
+    headers: HeaderMap,
+    Extension(db): Extension<MySqlPool>,
+) -> Result<Response, OidcErrorResponse> {
+    let provider = lookup_provider(&provider_name)?;
+    let result = complete_login(&provider, params, &headers, &db).await;
+    // The login cookie is single-use, whatever the outcome
+    let clear = AppendHeaders([(
+        header::SET_COOKIE,
+        login_cookie(&provider, "", std::time::Duration::ZERO),
+    )]);
+    Ok((clear, result).into_response())
+}
+
+async fn complete_login(
+    provider: &OidcProviderConfig,
+    params: CallbackParams,
+    headers: &HeaderMap,
+    db: &MySqlPool,
+) -> HandlerResult {
+    if let Some(err) = params.error {
+        tracing::warn!("oidc: provider {} returned error {}", provider.name, err);
+        return Err(oidc_error(
+            StatusCode::BAD_REQUEST,
+            "Login failed",
+            json!({ "error": err, "details": params.error_description }),
+        ));
+    }
+    let (Some(code), Some(state)) = (params.code, params.state) else {
+        return Err(oidc_error(
+            StatusCode::BAD_REQUEST,
+            "Login failed",
+            json!({ "error": "Missing code or state" }),
+        ));
+    };
+
+    // State must match the login cookie of this browser, for the provider that started it
+    let secret = login_secret()?;
+    let pending = match headers
+        .get(header::COOKIE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| cookie_value(v, LOGIN_COOKIE))
+        .and_then(|v| open_login(v, &secret))
+    {
+        Some(p) if p.state == state && p.provider == provider.name => p,
+        _ => {
+            return Err(oidc_error(
+                StatusCode::BAD_REQUEST,
+                "Login failed",
+                json!({ "error": "Invalid or expired login state" }),
+            ));
+        }
+    };
+
+    let meta = provider_metadata(provider).await.map_err(|e| {
+        oidc_error(
+            StatusCode::BAD_GATEWAY,
+            "Bad Gateway",
+            json!({ "error": "Identity provider unavailable", "details": e.to_string() }),
+        )
+    })?;
+    let id_token = exchange_code(&meta, provider, &code, &pending.code_verifier)
+        .await
+        .map_err(|e| {
+            tracing::warn!("oidc: code exchange failed for {}: {}", provider.name, e);
+            oidc_error(
+                StatusCode::BAD_GATEWAY,
+                "Bad Gateway",
+                json!({ "error": "Failed to exchange authorization code", "details": e.to_string() }),
+            )
+        })?;
+    let claims = verify_id_token(&meta, provider, &id_token, &pending.nonce)
+        .await
+        .map_err(|e| {
+            tracing::warn!("oidc: invalid ID token from {}: {}", provider.name, e);
+            oidc_error(
+                StatusCode::UNAUTHORIZED,
+                "Unauthorized",
+                json!({ "error": "Invalid ID token", "details": e.to_string() }),
+            )
+        })?;
+
+    let user_id = resolve_user(db, provider, &claims).await?;
+
+    // Load the user and role for the response and our token
+    #[derive(sqlx::FromRow)]
+    struct UserWithRole {
+        id: i64,
+        name: String,
+        email: String,
+        created_at: chrono::DateTime<chrono::Utc>,
+        updated_at: chrono::DateTime<chrono::Utc>,
+        role: String,
+        active: bool,
+        status: String,
+    }
+    // A linked identity may still point at a soft-deleted user
+    let u = sqlx::query_as::<_, UserWithRole>(&format!(
+        "SELECT id, name, email, created_at, updated_at, role, active, {} AS status FROM users WHERE id = ? AND deleted_at IS NULL",
+        EFFECTIVE_STATUS_SQL
+    ))
+    .bind(user_id)
+    .fetch_optional(db)
+    .await
+    .map_err(|e| db_error("Failed to fetch user", e))?
+    .ok_or_else(|| {
+        oidc_error(
+            StatusCode::FORBIDDEN,
+            "Forbidden",
+            json!({ "error": "Account has been deleted" }),
+        )
+    })?;
+    if !u.active {
+        return Err(oidc_error(
+            StatusCode::FORBIDDEN,
+            "Forbidden",
+            json!({ "error": "Account is disabled" }),
+        ));
+    }
+    let status = UserStatus::parse(&u.status).unwrap_or(UserStatus::Suspended);
+    if let Some(error) = status.login_error() {
+        return Err(oidc_error(
+            StatusCode::FORBIDDEN,
+            "Forbidden",
+            json!({ "error": error, "status": status.as_str() }),
+        ));
+    }
+
+    // Copy configured ID token claims into our token for permission policies
+    let mut attrs = Map::new();
+    for name in &provider.attribute_claims {
+        if let Some(v) = claims.extra.get(name) {
+            attrs.insert(name.clone(), v.clone());
+        }
+    }
+    let token = generate_jwt_token_for(u.id, &u.role, attrs)
+        .await
+        .map_err(|e| {
+            oidc_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Token error",
+                json!({ "error": "Failed to generate token", "details": e.to_string() }),
+            )
+        })?;
+
+    record_login(
+        db,
+        u.id,
+        json!({ "method": "oidc", "provider": provider.name }),
+    )
+    .await;
+    tracing::info!(
+        "oidc: login via provider={} subject={} user_id={}",
+        provider.name,
+        claims.sub,
+        u.id
+    );
+
+    let login_response = LoginResponseSchema {
+        user: UserLoginResponseSchema {
+            id: u.id,
+            name: u.name,
+            email: u.email,
+            created_at: u.created_at,
+            updated_at: u.updated_at,
+        },
+        token,
+    };
+    let response = ApiResponse::success_with_data("Login successful", json!(login_response));
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// Find the local user for an external identity: existing link, then verified email,
+/// then just-in-time provisioning (when enabled for the provider).
+async fn resolve_user(
+    db: &MySqlPool,
+    provider: &OidcProviderConfig,
+    claims: &IdTokenClaims,
+) -> Result<i64, OidcErrorResponse> {
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| db_error("Failed to start transaction", e))?;
+
+    let linked: Option<i64> = sqlx::query_scalar(
+        "SELECT user_id FROM user_identities WHERE provider = ? AND subject = ? FOR UPDATE",
+    )
+    .bind(&provider.name)
+    .bind(&claims.sub)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| db_error("Failed to look up identity", e))?;
+
+    let user_id = match linked {
+        Some(user_id) => user_id,
+        None => {
+            let email = match claims.email.as_deref() {
+                Some(e) if !e.trim().is_empty() => e.trim().to_lowercase(),
+                _ => {
+                    return Err(oidc_error(
+                        StatusCode::BAD_REQUEST,
+                        "Login failed",
+                        json!({ "error": "Identity provider did not return an email" }),
+                    ));
+                }
+            };
+            if !provider.allowed_domains.is_empty() {
+                let domain = email.rsplit_once('@').map(|(_, d)| d).unwrap_or("");
+                if !provider
+                    .allowed_domains
+                    .iter()
+                    .any(|d| d.eq_ignore_ascii_case(domain))
+                {
+                    return Err(oidc_error(
+                        StatusCode::FORBIDDEN,
+                        "Forbidden",
+                        json!({ "error": "Email domain not allowed for this provider" }),
+                    ));
+                }
+            }
+
+            let existing: Option<i64> = sqlx::query_scalar(
+                "SELECT id FROM users WHERE email = ? AND deleted_at IS NULL FOR UPDATE",
+            )
+            .bind(&email)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| db_error("Failed to check existing user", e))?;
+
+            let user_id = match existing {
+                // Only link an existing account when the provider vouches for the email
+                Some(id) if claims.email_verified == Some(true) => id,
+                Some(_) => {
+                    return Err(oidc_error(
+                        StatusCode::CONFLICT,
+                        "Conflict",
+                        json!({ "error": "An account with this email exists; the provider must verify the email to link it" }),
+                    ));
+                }
+                None if provider.jit_provisioning => {
+                    let name = claims
+                        .name
+                        .clone()
+                        .filter(|n| !n.trim().is_empty())
+                        .unwrap_or_else(|| email.split('@').next().unwrap_or("").to_string());
+                    // Federated accounts get a random, never-disclosed password
+                    let user = crate::handlers::register_handler::insert_user(
+                        &mut tx,
+                        &name,
+                        &email,
+                        &generate_token(),
+                        &provider.default_role,
+                        UserStatus::Active,
+                    )
+                    .await?;
+                    tracing::info!(
+                        "oidc: provisioned user_id={} via provider={}",
+                        user.id,
+                        provider.name
+                    );
+                    user.id
+                }
+                None => {
+                    return Err(oidc_error(
+                        StatusCode::FORBIDDEN,
+                        "Forbidden",
+                        json!({ "error": "No local account for this identity and provisioning is disabled" }),
+                    ));
+                }
+            };
+
+            sqlx::query(
+                "INSERT INTO user_identities (user_id, provider, subject, email) VALUES (?, ?, ?, ?)",
+            )
+            .bind(user_id)
+            .bind(&provider.name)
+            .bind(&claims.sub)
+            .bind(&email)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| db_error("Failed to link identity", e))?;
+            user_id
+        }
+    };
+
+    sqlx::query(
+        "UPDATE user_identities SET last_login_at = NOW() WHERE provider = ? AND subject = ?",
+    )
+    .bind(&provider.name)
+    .bind(&claims.sub)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| db_error("Failed to record login", e))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| db_error("Failed to commit login", e))?;
+    Ok(user_id)
+}
@@ -0,0 +1,39 @@
+use axum::{Json, http::StatusCode};
+use serde_json::json;
+
+use crate::middlewares::policy_guard::Authorizer;
+use crate::schemas::policy_schema::PolicyEvaluateRequestSchema;
+use crate::utils::handler::HandlerResult;
+use crate::utils::policy::current_policies;
+use crate::utils::response::ApiResponse;
+
+// Dry-run a policy decision without performing the action (admin debugging aid)
+pub async fn evaluate(
+    authz: Authorizer,
+    Json(payload): Json<PolicyEvaluateRequestSchema>,
+) -> HandlerResult {
+    // Validate the incoming payload (reusable helper)
+    crate::utils::validation::validate_payload(&payload)?;
+
+    let principal = payload.principal.unwrap_or(authz.principal);
+    let resource = payload.resource.unwrap_or_else(|| json!({}));
+    if !principal.is_object() || !resource.is_object() {
+        let response = ApiResponse::error_with_data(
+            "Validation error",
+            json!({ "errors": { "principal": ["Principal and resource must be JSON objects"] } }),
+        );
+        return Err((StatusCode::BAD_REQUEST, Json(response)));
+    }
+
+    let decision = current_policies().evaluate(&principal, &payload.action, &resource);
+    let response = ApiResponse::success_with_data(
+        "Policy evaluated",
+        json!({
+            "decision": decision,
+            "principal": principal,
+            "action": payload.action,
+            "resource": resource,
+        }),
+    );
+    Ok((StatusCode::OK, Json(response)))
+}
@@ -1,11 +1,17 @@
 use crate::utils::handler::HandlerResult;
 use axum::{Extension, Json, http::StatusCode};
-use serde_json::json;
-use sqlx::MySqlPool;
+use serde_json::{Value, json};
+use sqlx::{MySqlConnection, MySqlPool};
+// Import default role for self-registered accounts
+use crate::models::user::ROLE_USER;
 // Import schemas request and response register
 use crate::schemas::register_schema::{RegisterResponseSchema, RegisterSchema};
 // Import util response API
 use crate::utils::response::ApiResponse;
+// Import account status lifecycle
+use crate::utils::user_status::{UserStatus, registration_status};
+// Import webhook outbox
+use crate::utils::webhooks::{EVENT_USER_CREATED, enqueue_user_event};
 
 // Handler for user registration
 pub async fn register_handler(
@@ -18,17 +24,19 @@ pub async fn register_handler(
     // Normalize email for consistent duplicate checks and storage
     let email_normalized = payload.email.trim().to_lowercase();
     // Efficient existence check using EXISTS (returns 1 if found)
-    let exists: i64 = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM users WHERE email = ?)")
-        .bind(&email_normalized)
-        .fetch_one(&db_pool)
-        .await
-        .map_err(|e| {
-            let response = ApiResponse::error_with_data(
-                "Database error",
-                json!({ "error": "Failed to check existing user", "details": e.to_string() }),
-            );
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
-        })?;
+    let exists: i64 = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM users WHERE email = ? AND deleted_at IS NULL)",
+    )
+    .bind(&email_normalized)
+    .fetch_one(&db_pool)
+    .await
+    .map_err(|e| {
+        let response = ApiResponse::error_with_data(
+            "Database error",
+            json!({ "error": "Failed to check existing user", "details": e.to_string() }),
+        );
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
+    })?;
 
     if exists == 1 {
         let response = ApiResponse::error_with_data(
@@ -38,8 +46,53 @@ pub async fn register_handler(
         return Err((StatusCode::CONFLICT, Json(response)));
     }
 
+    // The user and its `user.created` webhook event are committed together
+    let tx_error = |e: sqlx::Error| {
+        let response = ApiResponse::error_with_data(
+            "Database error",
+            json!({ "error": "Failed to register user", "details": e.to_string() }),
+        );
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
+    };
+    let mut tx = db_pool.begin().await.map_err(tx_error)?;
+    let user = insert_user(
+        &mut tx,
+        &payload.name,
+        &email_normalized,
+        &payload.password,
+        ROLE_USER,
+        registration_status(),
+    )
+    .await?;
+    tx.commit().await.map_err(tx_error)?;
+
+    // Serialize user into JSON value
+    let user_value = serde_json::to_value(user).map_err(|e| {
+        let response = ApiResponse::error_with_data(
+            "Serialization error",
+            json!({ "error": "Failed to serialize user", "details": e.to_string() }),
+        );
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
+    })?;
+    let response = ApiResponse::success_with_data("User registered", user_value);
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+/// Hash the password and insert a new user, returning the stored row.
+///
+/// Shared by registration and invitation acceptance so both paths hash and map
+/// duplicate-email errors the same way. Also writes the `user.created` webhook event, so
+/// callers should run it inside a transaction.
+pub async fn insert_user(
+    conn: &mut MySqlConnection,
+    name: &str,
+    email_normalized: &str,
+    password: &str,
+    role: &str,
+    status: UserStatus,
+) -> Result<RegisterResponseSchema, (StatusCode, Json<ApiResponse<Value>>)> {
     // Hash the password in a blocking thread to avoid blocking the async runtime
-    let pw_to_hash = payload.password.clone();
+    let pw_to_hash = password.to_string();
     let hashed_password =
         match tokio::task::spawn_blocking(move || bcrypt::hash(&pw_to_hash, 4)).await {
             Ok(Ok(h)) => h,
@@ -62,14 +115,16 @@ pub async fn register_handler(
     // Insert the new user into the database
     let res = sqlx::query(
         r#"
-        INSERT INTO users (name, email, password)
-        VALUES (?, ?, ?)
+        INSERT INTO users (name, email, password, role, status)
+        VALUES (?, ?, ?, ?, ?)
         "#,
     )
-    .bind(&payload.name)
-    .bind(&email_normalized)
+    .bind(name)
+    .bind(email_normalized)
     .bind(&hashed_password)
-    .execute(&db_pool)
+    .bind(role)
+    .bind(status.as_str())
+    .execute(&mut *conn)
     .await
     .map_err(|e| {
         if let sqlx::Error::Database(db_err) = &e {
@@ -107,16 +162,25 @@ pub async fn register_handler(
     })?;
 
     let user_id = res.last_insert_id() as i64;
+    enqueue_user_event(&mut *conn, EVENT_USER_CREATED, user_id, json!({}))
+        .await
+        .map_err(|e| {
+            let response = ApiResponse::error_with_data(
+                "Database error",
+                json!({ "error": "Failed to register user", "details": e.to_string() }),
+            );
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
+        })?;
     // Fetch the newly created user
-    let user = sqlx::query_as::<_, RegisterResponseSchema>(
+    sqlx::query_as::<_, RegisterResponseSchema>(
         r#"
-        SELECT id, name, email, created_at, updated_at
+        SELECT id, name, email, created_at, updated_at, status
         FROM users
         WHERE id = ?
         "#,
     )
     .bind(user_id)
-    .fetch_one(&db_pool)
+    .fetch_one(&mut *conn)
     .await
     .map_err(|e| {
         let response = ApiResponse::error_with_data(
@@ -124,16 +188,5 @@ pub async fn register_handler(
             json!({ "error": "Failed to fetch registered user", "details": e.to_string() }),
         );
         (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
-    })?;
-
-    // Serialize user into JSON value
-    let user_value = serde_json::to_value(user).map_err(|e| {
-        let response = ApiResponse::error_with_data(
-            "Serialization error",
-            json!({ "error": "Failed to serialize user", "details": e.to_string() }),
-        );
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
-    })?;
-    let response = ApiResponse::success_with_data("User registered", user_value);
-    Ok((StatusCode::CREATED, Json(response)))
+    })
 }
@@ -0,0 +1,613 @@
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{Path, Query};
+use axum::http::{StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json};
+use serde_json::{Value, json};
+use sqlx::{MySql, MySqlPool, QueryBuilder};
+use validator::ValidateEmail;
+
+use crate::handlers::register_handler::insert_user;
+use crate::models::user::ROLE_USER;
+use crate::schemas::scim_schema::{
+    ScimListParams, ScimPatchRequestSchema, ScimUserRequestSchema, ScimUserRow,
+};
+use crate::utils::notifications::revoke_sessions;
+use crate::utils::response::ApiResponse;
+use crate::utils::scim::{
+    MAX_RESULTS, SCHEMA_LIST_RESPONSE, SCHEMA_PATCH_OP, SCHEMA_SERVICE_PROVIDER_CONFIG,
+    SCHEMA_USER, ScimError, ScimJson, normalize_attr, parse_filter, push_filter_sql,
+};
+use crate::utils::token::generate_token;
+use crate::utils::user_revisions::{
+    ACTION_CREATE, ACTION_DELETE, ACTION_UPDATE, ChangeContext, record_change, snapshot,
+};
+use crate::utils::user_status::UserStatus;
+use crate::utils::webhooks::{EVENT_USER_DELETED, EVENT_USER_UPDATED, enqueue_user_event};
+
+type ScimResult = Result<Response, ScimError>;
+
+const USER_COLUMNS: &str = "id, name, email, external_id, active, created_at, updated_at";
+
+fn base_url() -> String {
+    std::env::var("SCIM_BASE_URL")
+        .map(|v| v.trim_end_matches('/').to_string())
+        .unwrap_or_default()
+}
+
+fn user_location(id: i64) -> String {
+    format!("{}/scim/v2/Users/{}", base_url(), id)
+}
+
+/// Render a `users` row as a SCIM User resource (RFC 7643 §4.1).
+fn user_resource(row: &ScimUserRow) -> Value {
+    let mut resource = json!({
+        "schemas": [SCHEMA_USER],
+        "id": row.id.to_string(),
+        "userName": row.email,
+        "name": { "formatted": row.name },
+        "displayName": row.name,
+        "emails": [{ "value": row.email, "type": "work", "primary": true }],
+        "active": row.active,
+        "meta": {
+            "resourceType": "User",
+            "created": row.created_at.to_rfc3339(),
+            "lastModified": row.updated_at.to_rfc3339(),
+            "location": user_location(row.id),
+        }
+    });
+    if let Some(external_id) = &row.external_id {
+        resource["externalId"] = json!(external_id);
+    }
+    resource
+}
+
+fn parse_id(id: &str) -> Result<i64, ScimError> {
+    id.parse().map_err(|_| ScimError::not_found())
+}
+
+fn json_body<T>(payload: Result<Json<T>, JsonRejection>) -> Result<T, ScimError> {
+    payload
+        .map(|Json(v)| v)
+        .map_err(|e| ScimError::bad_request("invalidSyntax", e.body_text()))
+}
+
+fn is_duplicate(e: &sqlx::Error) -> bool {
+    if let sqlx::Error::Database(db_err) = e
+        && (db_err.code().as_deref() == Some("1062")
+            || db_err.message().to_lowercase().contains("duplicate"))
+    {
+        return true;
+    }
+    false
+}
+
+fn user_name_taken() -> ScimError {
+    ScimError::new(
+        StatusCode::CONFLICT,
+        Some("uniqueness"),
+        "userName is already in use",
+    )
+}
+
+async fn fetch_user(db: &MySqlPool, id: i64) -> Result<ScimUserRow, ScimError> {
+    sqlx::query_as::<_, ScimUserRow>(&format!(
+        "SELECT {} FROM users WHERE id = ? AND deleted_at IS NULL",
+        USER_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(db)
+    .await
+    .map_err(|e| ScimError::database("Failed to fetch user", e))?
+    .ok_or_else(ScimError::not_found)
+}
+
+/// Writable user state shared by create, replace and patch.
+struct UserDraft {
+    name: String,
+    email: String,
+    external_id: Option<String>,
+    active: bool,
+    password: Option<String>,
+}
+
+impl UserDraft {
+    fn from_row(row: &ScimUserRow) -> Self {
+        Self {
+            name: row.name.clone(),
+            email: row.email.clone(),
+            external_id: row.external_id.clone(),
+            active: row.active,
+            password: None,
+        }
+    }
+
+    /// Build the full state from a POST/PUT body; omitted optional attributes are cleared.
+    fn from_request(req: ScimUserRequestSchema) -> Result<Self, ScimError> {
+        // Our users are identified by email: use userName, falling back to the primary email
+        let email = req
+            .user_name
+            .as_deref()
+            .map(|u| u.trim().to_lowercase())
+            .filter(|u| u.validate_email())
+            .or_else(|| {
+                req.emails
+                    .iter()
+                    .find(|e| e.primary == Some(true))
+                    .or(req.emails.first())
+                    .map(|e| e.value.trim().to_lowercase())
+            })
+            .ok_or_else(|| {
+                ScimError::bad_request("invalidValue", "userName must be an email address")
+            })?;
+
+        let name = req
+            .display_name
+            .clone()
+            .or_else(|| req.name.as_ref().and_then(|n| n.formatted.clone()))
+            .or_else(|| {
+                req.name.as_ref().and_then(|n| {
+                    let full = [n.given_name.as_deref(), n.family_name.as_deref()]
+                        .into_iter()
+                        .flatten()
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    (!full.trim().is_empty()).then_some(full)
+                })
+            })
+            .unwrap_or_else(|| email.split('@').next().unwrap_or("").to_string());
+
+        let draft = Self {
+            name,
+            email,
+            external_id: req.external_id,
+            active: req.active.unwrap_or(true),
+            password: req.password,
+        };
+        draft.validate()?;
+        Ok(draft)
+    }
+
+    fn validate(&self) -> Result<(), ScimError> {
+        if !self.email.validate_email() {
+            return Err(ScimError::bad_request(
+                "invalidValue",
+                "userName must be an email address",
+            ));
+        }
+        if self.name.trim().is_empty() {
+            return Err(ScimError::bad_request(
+                "invalidValue",
+                "displayName cannot be empty",
+            ));
+        }
+        if let Some(pw) = &self.password
+            && pw.len() < 6
+        {
+            return Err(ScimError::bad_request(
+                "invalidValue",
+                "password must be at least 6 characters long",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Apply one `add`/`replace` to a (lowercased) attribute path.
+    fn set(&mut self, path: &str, value: &Value) -> Result<(), ScimError> {
+        let invalid = |attr: &str| {
+            ScimError::bad_request("invalidValue", format!("Invalid value for '{}'", attr))
+        };
+        match path {
+            "username" => {
+                self.email = value
+                    .as_str()
+                    .ok_or_else(|| invalid(path))?
+                    .trim()
+                    .to_lowercase();
+            }
+            "displayname" | "name.formatted" => {
+                self.name = value.as_str().ok_or_else(|| invalid(path))?.to_string();
+            }
+            "name" => {
+                if let Some(formatted) = value.get("formatted").and_then(Value::as_str) {
+                    self.name = formatted.to_string();
+                }
+            }
+            // Only a single display name is stored
+            "name.givenname" | "name.familyname" => {}
+            p if p == "emails" || p.starts_with("emails[") => {
+                let email = match value {
+                    Value::String(s) => Some(s.as_str()),
+                    Value::Array(items) => items
+                        .iter()
+                        .find(|e| e.get("primary").and_then(Value::as_bool) == Some(true))
+                        .or(items.first())
+                        .and_then(|e| e.get("value"))
+                        .and_then(Value::as_str),
+                    Value::Object(_) => value.get("value").and_then(Value::as_str),
+                    _ => None,
+                };
+                self.email = email
+                    .ok_or_else(|| invalid("emails"))?
+                    .trim()
+                    .to_lowercase();
+            }
+            "externalid" => {
+                self.external_id = match value {
+                    Value::Null => None,
+                    Value::String(s) => Some(s.clone()),
+                    _ => return Err(invalid(path)),
+                };
+            }
+            "active" => {
+                // Some clients send booleans as strings ("True"/"False")
+                self.active = match value {
+                    Value::Bool(b) => *b,
+                    Value::String(s) if s.eq_ignore_ascii_case("true") => true,
+                    Value::String(s) if s.eq_ignore_ascii_case("false") => false,
+                    _ => return Err(invalid(path)),
+                };
+            }
+            "password" => {
+                self.password = Some(value.as_str().ok_or_else(|| invalid(path))?.to_string());
+            }
+            _ => {
+                return Err(ScimError::bad_request(
+                    "invalidPath",
+                    format!("Attribute '{}' is not supported", path),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+async fn hash_password(password: String) -> Result<String, ScimError> {
+    let cost: u32 = std::env::var("BCRYPT_COST")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(bcrypt::DEFAULT_COST);
+    match tokio::task::spawn_blocking(move || bcrypt::hash(password, cost)).await {
+        Ok(Ok(h)) => Ok(h),
+        _ => Err(ScimError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            None,
+            "Failed to hash password",
+        )),
+    }
+}
+
+/// Persist a draft over an existing user, recording the revision and `user.updated` event
+/// in the same transaction.
+async fn save_user(db: &MySqlPool, id: i64, draft: UserDraft) -> Result<(), ScimError> {
+    let hashed = match draft.password {
+        Some(pw) => Some(hash_password(pw).await?),
+        None => None,
+    };
+    let db_error = |e| ScimError::database("Failed to update user", e);
+    let mut tx = db.begin().await.map_err(db_error)?;
+    let before = snapshot(&mut tx, id).await.map_err(db_error)?;
+    let res = sqlx::query(
+        r#"
+        UPDATE users
+        SET name = ?, email = ?, external_id = ?, active = ?, password = COALESCE(?, password),
+            version = version + 1
+        WHERE id = ? AND deleted_at IS NULL
+        "#,
+    )
+    .bind(&draft.name)
+    .bind(&draft.email)
+    .bind(&draft.external_id)
+    .bind(draft.active)
+    .bind(hashed)
+    .bind(id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        if is_duplicate(&e) {
+            user_name_taken()
+        } else {
+            db_error(e)
+        }
+    })?;
+    if res.rows_affected() == 0 {
+        return Err(ScimError::not_found());
+    }
+    let context = ChangeContext::new(ACTION_UPDATE, None);
+    record_change(&mut tx, id, context, before.as_ref())
+        .await
+        .map_err(db_error)?;
+    enqueue_user_event(
+        &mut tx,
+        EVENT_USER_UPDATED,
+        id,
+        json!({ "actor_id": context.actor_id }),
+    )
+    .await
+    .map_err(db_error)?;
+    tx.commit().await.map_err(db_error)?;
+    Ok(())
+}
+
+// GET /scim/v2/ServiceProviderConfig
+pub async fn service_provider_config() -> Response {
+    ScimJson(
+        StatusCode::OK,
+        json!({
+            "schemas": [SCHEMA_SERVICE_PROVIDER_CONFIG],
+            "patch": { "supported": true },
+            "bulk": { "supported": false, "maxOperations": 0, "maxPayloadSize": 0 },
+            "filter": { "supported": true, "maxResults": MAX_RESULTS },
+            "changePassword": { "supported": true },
+            "sort": { "supported": false },
+            "etag": { "supported": false },
+            "authenticationSchemes": [{
+                "type": "oauthbearertoken",
+                "name": "OAuth Bearer Token",
+                "description": "Authentication with the dedicated SCIM bearer token",
+                "primary": true
+            }],
+            "meta": {
+                "resourceType": "ServiceProviderConfig",
+                "location": format!("{}/scim/v2/ServiceProviderConfig", base_url()),
+            }
+        }),
+    )
+    .into_response()
+}
+
+// GET /scim/v2/Users
+pub async fn list_users(
+    Extension(db): Extension<MySqlPool>,
+    Query(params): Query<ScimListParams>,
+) -> ScimResult {
+    // startIndex is 1-based; count=0 returns only totalResults (RFC 7644 §3.4.2.4)
+    let start_index = params.start_index.unwrap_or(1).max(1);
+    let count = params.count.unwrap_or(100).clamp(0, MAX_RESULTS);
+    let filter = params.filter.as_deref().map(parse_filter).transpose()?;
+
+    let push_where = |qb: &mut QueryBuilder<'_, MySql>| -> Result<(), ScimError> {
+        qb.push(" WHERE deleted_at IS NULL");
+        if let Some(f) = &filter {
+            qb.push(" AND ");
+            push_filter_sql(qb, f)?;
+        }
+        Ok(())
+    };
+
+    let mut count_qb = QueryBuilder::<MySql>::new("SELECT COUNT(*) FROM users");
+    push_where(&mut count_qb)?;
+    let total: i64 = count_qb
+        .build_query_scalar()
+        .fetch_one(&db)
+        .await
+        .map_err(|e| ScimError::database("Failed to count users", e))?;
+
+    let mut qb = QueryBuilder::<MySql>::new(format!("SELECT {} FROM users", USER_COLUMNS));
+    push_where(&mut qb)?;
+    qb.push(" ORDER BY id LIMIT ")
+        .push_bind(count)
+        .push(" OFFSET ")
+        .push_bind(start_index - 1);
+    let rows = qb
+        .build_query_as::<ScimUserRow>()
+        .fetch_all(&db)
+        .await
+        .map_err(|e| ScimError::database("Failed to fetch users", e))?;
+
+    let resources: Vec<Value> = rows.iter().map(user_resource).collect();
+    Ok(ScimJson(
+        StatusCode::OK,
+        json!({
+            "schemas": [SCHEMA_LIST_RESPONSE],
+            "totalResults": total,
+            "startIndex": start_index,
+            "itemsPerPage": resources.len(),
+            "Resources": resources,
+        }),
+    )
+    .into_response())
+}
+
+// GET /scim/v2/Users/{id}
+pub async fn get_user(Extension(db): Extension<MySqlPool>, Path(id): Path<String>) -> ScimResult {
+    let row = fetch_user(&db, parse_id(&id)?).await?;
+    Ok(ScimJson(StatusCode::OK, user_resource(&row)).into_response())
+}
+
+// POST /scim/v2/Users
+pub async fn create_user(
+    Extension(db): Extension<MySqlPool>,
+    payload: Result<Json<ScimUserRequestSchema>, JsonRejection>,
+) -> ScimResult {
+    let draft = UserDraft::from_request(json_body(payload)?)?;
+
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| ScimError::database("Failed to start transaction", e))?;
+    // Provisioned accounts without a password get a random one (they sign in via SSO)
+    let password = draft.password.clone().unwrap_or_else(generate_token);
+    let user = insert_user(
+        &mut tx,
+        &draft.name,
+        &draft.email,
+        &password,
+        ROLE_USER,
+        UserStatus::Active,
+    )
+    .await
+    .map_err(
+        |(status, Json(api)): (StatusCode, Json<ApiResponse<Value>>)| {
+            if status == StatusCode::CONFLICT {
+                return user_name_taken();
+            }
+            let detail = api
+                .data
+                .as_ref()
+                .and_then(|d| d.get("error"))
+                .and_then(Value::as_str)
+                .unwrap_or("Failed to create user")
+                .to_string();
+            ScimError::new(status, None, detail)
+        },
+    )?;
+    sqlx::query("UPDATE users SET external_id = ?, active = ? WHERE id = ?")
+        .bind(&draft.external_id)
+        .bind(draft.active)
+        .bind(user.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| ScimError::database("Failed to create user", e))?;
+    // `insert_user` already enqueued `user.created`; the revision is recorded here, after
+    // the SCIM columns are set
+    record_change(
+        &mut tx,
+        user.id,
+        ChangeContext::new(ACTION_CREATE, None),
+        None,
+    )
+    .await
+    .map_err(|e| ScimError::database("Failed to create user", e))?;
+    tx.commit()
+        .await
+        .map_err(|e| ScimError::database("Failed to create user", e))?;
+
+    tracing::info!("scim: provisioned user_id={}", user.id);
+    let row = fetch_user(&db, user.id).await?;
+    Ok((
+        [(header::LOCATION, user_location(row.id))],
+        ScimJson(StatusCode::CREATED, user_resource(&row)),
+    )
+        .into_response())
+}
+
+// PUT /scim/v2/Users/{id}
+pub async fn replace_user(
+    Extension(db): Extension<MySqlPool>,
+    Path(id): Path<String>,
+    payload: Result<Json<ScimUserRequestSchema>, JsonRejection>,
+) -> ScimResult {
+    let id = parse_id(&id)?;
+    let draft = UserDraft::from_request(json_body(payload)?)?;
+    fetch_user(&db, id).await?;
+    let active = draft.active;
+    save_user(&db, id, draft).await?;
+    if !active {
+        revoke_sessions(id, "Account deactivated");
+    }
+
+    let row = fetch_user(&db, id).await?;
+    Ok(ScimJson(StatusCode::OK, user_resource(&row)).into_response())
+}
+
+// PATCH /scim/v2/Users/{id}
+pub async fn patch_user(
+    Extension(db): Extension<MySqlPool>,
+    Path(id): Path<String>,
+    payload: Result<Json<ScimPatchRequestSchema>, JsonRejection>,
+) -> ScimResult {
+    let id = parse_id(&id)?;
+    let patch = json_body(payload)?;
+    if !patch.schemas.iter().any(|s| s == SCHEMA_PATCH_OP) {
+        return Err(ScimError::bad_request(
+            "invalidSyntax",
+            format!("PATCH requests must use the {} schema", SCHEMA_PATCH_OP),
+        ));
+    }
+
+    let current = fetch_user(&db, id).await?;
+    let mut draft = UserDraft::from_row(&current);
+    for op in &patch.operations {
+        let path = op.path.as_deref().map(normalize_attr);
+        match (op.op.to_ascii_lowercase().as_str(), path) {
+            ("add" | "replace", Some(path)) => {
+                let value = op.value.as_ref().ok_or_else(|| {
+                    ScimError::bad_request("invalidValue", "Operation requires a value")
+                })?;
+                draft.set(&path, value)?;
+            }
+            // Without a path the value is an object of attributes to set
+            ("add" | "replace", None) => {
+                let attrs = op
+                    .value
+                    .as_ref()
+                    .and_then(Value::as_object)
+                    .ok_or_else(|| {
+                        ScimError::bad_request(
+                            "invalidValue",
+                            "Operation without path requires an object value",
+                        )
+                    })?;
+                for (attr, value) in attrs {
+                    draft.set(&normalize_attr(attr), value)?;
+                }
+            }
+            ("remove", Some(path)) if path == "externalid" => draft.external_id = None,
+            ("remove", Some(path)) => {
+                return Err(ScimError::bad_request(
+                    "mutability",
+                    format!("Attribute '{}' cannot be removed", path),
+                ));
+            }
+            ("remove", None) => {
+                return Err(ScimError::bad_request("noTarget", "remove requires a path"));
+            }
+            (other, _) => {
+                return Err(ScimError::bad_request(
+                    "invalidSyntax",
+                    format!("Unsupported operation '{}'", other),
+                ));
+            }
+        }
+    }
+    draft.validate()?;
+
+    let deactivated = current.active && !draft.active;
+    save_user(&db, id, draft).await?;
+    if deactivated {
+        tracing::info!("scim: deactivated user_id={}", id);
+        revoke_sessions(id, "Account deactivated");
+    }
+
+    let row = fetch_user(&db, id).await?;
+    Ok(ScimJson(StatusCode::OK, user_resource(&row)).into_response())
+}
+
+// DELETE /scim/v2/Users/{id}
+pub async fn delete_user(
+    Extension(db): Extension<MySqlPool>,
+    Path(id): Path<String>,
+) -> ScimResult {
+    let id = parse_id(&id)?;
+    // Soft delete, like DELETE /users/{id}; the purge task removes the row later
+    let db_error = |e| ScimError::database("Failed to delete user", e);
+    let mut tx = db.begin().await.map_err(db_error)?;
+    let before = snapshot(&mut tx, id).await.map_err(db_error)?;
+    let res = sqlx::query(
+        "UPDATE users SET deleted_at = NOW(), version = version + 1 WHERE id = ? AND deleted_at IS NULL",
+    )
+    .bind(id)
+    .execute(&mut *tx)
+    .await
+    .map_err(db_error)?;
+    if res.rows_affected() == 0 {
+        return Err(ScimError::not_found());
+    }
+    let context = ChangeContext::new(ACTION_DELETE, None);
+    record_change(&mut tx, id, context, before.as_ref())
+        .await
+        .map_err(db_error)?;
+    enqueue_user_event(
+        &mut tx,
+        EVENT_USER_DELETED,
+        id,
+        json!({ "actor_id": context.actor_id }),
+    )
+    .await
+    .map_err(db_error)?;
+    tx.commit().await.map_err(db_error)?;
+    tracing::info!("scim: deprovisioned user_id={}", id);
+    revoke_sessions(id, "Account deleted");
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
@@ -0,0 +1,361 @@
+use axum::body::Bytes;
+use axum::extract::multipart::MultipartRejection;
+use axum::extract::{Multipart, Path, Query};
+use axum::http::{HeaderMap, HeaderValue, header};
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json, http::StatusCode};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use sqlx::MySqlPool;
+
+use crate::handlers::user_handler::{authorize_user_action, check_if_match, updated_user_response};
+use crate::middlewares::policy_guard::Authorizer;
+use crate::storage::{BlobStore, blob_store};
+use crate::utils::avatar::{
+    ACCEPTED_CONTENT_TYPES, AvatarError, AvatarSize, blob_key, delete_blobs, process_avatar,
+};
+use crate::utils::etag::if_none_match;
+use crate::utils::response::ApiResponse;
+use crate::utils::token::generate_token;
+
+type AvatarErrorResponse = (StatusCode, Json<ApiResponse<Value>>);
+
+/// Multipart field carrying the image.
+const AVATAR_FIELD: &str = "avatar";
+
+/// Upload limit in bytes (`AVATAR_MAX_BYTES`, default 5 MiB).
+pub fn max_upload_bytes() -> usize {
+    std::env::var("AVATAR_MAX_BYTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(5 * 1024 * 1024)
+}
+
+fn avatar_error(status: StatusCode, message: &str, data: Value) -> AvatarErrorResponse {
+    (status, Json(ApiResponse::error_with_data(message, data)))
+}
+
+fn field_error(message: &str) -> AvatarErrorResponse {
+    avatar_error(
+        StatusCode::BAD_REQUEST,
+        "Validation error",
+        json!({ "errors": { AVATAR_FIELD: [message] } }),
+    )
+}
+
+fn storage_error(e: impl std::fmt::Display) -> AvatarErrorResponse {
+    tracing::error!("avatar storage: {}", e);
+    avatar_error(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "Storage error",
+        json!({ "error": "Failed to access avatar storage" }),
+    )
+}
+
+fn db_error(e: sqlx::Error) -> AvatarErrorResponse {
+    avatar_error(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "Database error",
+        json!({ "error": "Failed to update user", "details": e.to_string() }),
+    )
+}
+
+fn precondition_failed() -> AvatarErrorResponse {
+    avatar_error(
+        StatusCode::PRECONDITION_FAILED,
+        "Precondition Failed",
+        json!({ "error": "User was modified by another request; fetch it again and retry" }),
+    )
+}
+
+fn store() -> Result<std::sync::Arc<dyn BlobStore>, AvatarErrorResponse> {
+    blob_store().map_err(storage_error)
+}
+
+/// Current avatar (`avatar_version`, `avatar_content_type`) of a live user, read at `version`.
+///
+/// `Ok(None)` when the user has no avatar; `412` when the version moved on.
+async fn current_avatar(
+    db: &MySqlPool,
+    user_id: i64,
+    version: Option<i64>,
+) -> Result<Option<(String, String)>, AvatarErrorResponse> {
+    let row: Option<(Option<String>, Option<String>)> = sqlx::query_as(
+        r#"
+        SELECT avatar_version, avatar_content_type
+        FROM users
+        WHERE id = ? AND deleted_at IS NULL AND (? IS NULL OR version = ?)
+        "#,
+    )
+    .bind(user_id)
+    .bind(version)
+    .bind(version)
+    .fetch_optional(db)
+    .await
+    .map_err(db_error)?;
+    match row {
+        None if version.is_some() => Err(precondition_failed()),
+        None => Err(avatar_error(
+            StatusCode::NOT_FOUND,
+            "Not Found",
+            json!({ "error": "User not found" }),
+        )),
+        Some((Some(token), Some(content_type))) => Ok(Some((token, content_type))),
+        Some(_) => Ok(None),
+    }
+}
+
+/// Point the user at a new avatar (or none) if the version still matches.
+async fn set_avatar(
+    db: &MySqlPool,
+    user_id: i64,
+    version: i64,
+    avatar: Option<(&str, &str)>,
+) -> Result<bool, AvatarErrorResponse> {
+    let result = sqlx::query(
+        r#"
+        UPDATE users
+        SET avatar_version = ?, avatar_content_type = ?, version = version + 1
+        WHERE id = ? AND deleted_at IS NULL AND version = ?
+        "#,
+    )
+    .bind(avatar.map(|(token, _)| token))
+    .bind(avatar.map(|(_, content_type)| content_type))
+    .bind(user_id)
+    .bind(version)
+    .execute(db)
+    .await
+    .map_err(db_error)?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Best-effort cleanup of a replaced upload; orphans are only logged.
+async fn discard(store: &dyn BlobStore, user_id: i64, token: &str, content_type: &str) {
+    if let Err(e) = delete_blobs(store, user_id, token, content_type).await {
+        tracing::warn!(
+            "avatar storage: failed to remove avatars/{}/{}: {}",
+            user_id,
+            token,
+            e
+        );
+    }
+}
+
+/// Read the `avatar` part of the form, enforcing the size limit while streaming.
+async fn read_upload(mut multipart: Multipart) -> Result<(String, Bytes), AvatarErrorResponse> {
+    let max_bytes = max_upload_bytes();
+    let too_large = || {
+        avatar_error(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "Payload Too Large",
+            json!({ "error": format!("Avatar must be at most {} bytes", max_bytes) }),
+        )
+    };
+    let multipart_error = |e: axum::extract::multipart::MultipartError| {
+        if e.status() == StatusCode::PAYLOAD_TOO_LARGE {
+            too_large()
+        } else {
+            field_error(&e.body_text())
+        }
+    };
+
+    while let Some(mut field) = multipart.next_field().await.map_err(multipart_error)? {
+        if field.name() != Some(AVATAR_FIELD) {
+            continue;
+        }
+        let content_type = field
+            .content_type()
+            .map(|v| {
+                v.split(';')
+                    .next()
+                    .unwrap_or("")
+                    .trim()
+                    .to_ascii_lowercase()
+            })
+            .unwrap_or_default();
+        if !ACCEPTED_CONTENT_TYPES.contains(&content_type.as_str()) {
+            return Err(avatar_error(
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "Unsupported Media Type",
+                json!({ "error": format!("Avatar must be one of {}", ACCEPTED_CONTENT_TYPES.join(", ")) }),
+            ));
+        }
+        let mut data = Vec::new();
+        while let Some(chunk) = field.chunk().await.map_err(multipart_error)? {
+            if data.len() + chunk.len() > max_bytes {
+                return Err(too_large());
+            }
+            data.extend_from_slice(&chunk);
+        }
+        if data.is_empty() {
+            return Err(field_error("The uploaded file is empty"));
+        }
+        return Ok((content_type, Bytes::from(data)));
+    }
+    Err(field_error("An image file is required"))
+}
+
+/// `PUT /users/{id}/avatar` (multipart form with an `avatar` file part).
+pub async fn upload_avatar(
+    Extension(db): Extension<MySqlPool>,
+    authz: Authorizer,
+    headers: HeaderMap,
+    Path(user_id): Path<i64>,
+    multipart: Result<Multipart, MultipartRejection>,
+) -> Result<Response, AvatarErrorResponse> {
+    let version = authorize_user_action(&db, &authz, "users:update", user_id).await?;
+    check_if_match(&headers, version)?;
+    let multipart = multipart.map_err(|e| {
+        avatar_error(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "Unsupported Media Type",
+            json!({ "error": "Send the image as multipart/form-data", "details": e.body_text() }),
+        )
+    })?;
+    let (content_type, data) = read_upload(multipart).await?;
+
+    // Decoding and resizing are CPU-bound
+    let processed = tokio::task::spawn_blocking(move || process_avatar(&data, &content_type))
+        .await
+        .map_err(|e| {
+            avatar_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Processing error",
+                json!({ "error": "Failed to process image", "details": e.to_string() }),
+            )
+        })?
+        .map_err(|e| match e {
+            AvatarError::Unsupported(message) => avatar_error(
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "Unsupported Media Type",
+                json!({ "error": message }),
+            ),
+            AvatarError::Invalid(message) => {
+                field_error(&format!("The file is not a valid image: {}", message))
+            }
+        })?;
+
+    let previous = current_avatar(&db, user_id, Some(version)).await?;
+    let store = store()?;
+    // Every upload gets fresh keys, so the old avatar stays readable until the switch
+    let token = generate_token()[..16].to_string();
+    for (size, bytes) in processed.variants {
+        let key = blob_key(user_id, &token, processed.content_type, size);
+        if let Err(e) = store.put(&key, processed.content_type, bytes).await {
+            discard(store.as_ref(), user_id, &token, processed.content_type).await;
+            return Err(storage_error(e));
+        }
+    }
+
+    if !set_avatar(
+        &db,
+        user_id,
+        version,
+        Some((&token, processed.content_type)),
+    )
+    .await?
+    {
+        discard(store.as_ref(), user_id, &token, processed.content_type).await;
+        return Err(precondition_failed());
+    }
+    if let Some((old_token, old_content_type)) = previous {
+        discard(store.as_ref(), user_id, &old_token, &old_content_type).await;
+    }
+
+    Ok(updated_user_response(&db, &authz, user_id)
+        .await?
+        .into_response())
+}
+
+#[derive(Deserialize)]
+pub struct AvatarParams {
+    /// `small` (64x64), `medium` (256x256) or `large` (default, up to 1024 px)
+    pub size: Option<String>,
+}
+
+/// `GET /users/{id}/avatar?size=small|medium|large`
+pub async fn show_avatar(
+    Extension(db): Extension<MySqlPool>,
+    authz: Authorizer,
+    headers: HeaderMap,
+    Path(user_id): Path<i64>,
+    Query(params): Query<AvatarParams>,
+) -> Result<Response, AvatarErrorResponse> {
+    let size = match params.size.as_deref() {
+        None => AvatarSize::Large,
+        Some(raw) => AvatarSize::parse(raw).ok_or_else(|| {
+            avatar_error(
+                StatusCode::BAD_REQUEST,
+                "Validation error",
+                json!({ "errors": { "size": ["Must be one of small, medium, large"] } }),
+            )
+        })?,
+    };
+    authorize_user_action(&db, &authz, "users:read", user_id).await?;
+    let not_found = || {
+        avatar_error(
+            StatusCode::NOT_FOUND,
+            "Not Found",
+            json!({ "error": "User has no avatar" }),
+        )
+    };
+    let (token, content_type) = current_avatar(&db, user_id, None)
+        .await?
+        .ok_or_else(not_found)?;
+
+    let mut response_headers = HeaderMap::new();
+    let etag = format!("\"{}-{}\"", token, size.as_str());
+    if let Ok(v) = HeaderValue::from_str(&etag) {
+        response_headers.insert(header::ETAG, v);
+    }
+    response_headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static("private, max-age=86400"),
+    );
+    if if_none_match(&headers, &etag) {
+        return Ok((StatusCode::NOT_MODIFIED, response_headers).into_response());
+    }
+
+    let data = store()?
+        .get(&blob_key(user_id, &token, &content_type, size))
+        .await
+        .map_err(storage_error)?
+        .ok_or_else(not_found)?;
+    if let Ok(v) = HeaderValue::from_str(&content_type) {
+        response_headers.insert(header::CONTENT_TYPE, v);
+    }
+    response_headers.insert(
+        header::X_CONTENT_TYPE_OPTIONS,
+        HeaderValue::from_static("nosniff"),
+    );
+    Ok((StatusCode::OK, response_headers, data).into_response())
+}
+
+/// `DELETE /users/{id}/avatar`
+pub async fn destroy_avatar(
+    Extension(db): Extension<MySqlPool>,
+    authz: Authorizer,
+    headers: HeaderMap,
+    Path(user_id): Path<i64>,
+) -> Result<Response, AvatarErrorResponse> {
+    let version = authorize_user_action(&db, &authz, "users:update", user_id).await?;
+    check_if_match(&headers, version)?;
+    let (token, content_type) = current_avatar(&db, user_id, Some(version))
+        .await?
+        .ok_or_else(|| {
+            avatar_error(
+                StatusCode::NOT_FOUND,
+                "Not Found",
+                json!({ "error": "User has no avatar" }),
+            )
+        })?;
+    if !set_avatar(&db, user_id, version, None).await? {
+        return Err(precondition_failed());
+    }
+    if let Ok(store) = store() {
+        discard(store.as_ref(), user_id, &token, &content_type).await;
+    }
+    Ok(updated_user_response(&db, &authz, user_id)
+        .await?
+        .into_response())
+}
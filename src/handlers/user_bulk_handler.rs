@@ -0,0 +1,480 @@
+use axum::{Extension, Json, http::StatusCode};
+use serde_json::{Value, json};
+use sqlx::{Acquire, MySqlConnection, MySqlPool};
+
+use crate::handlers::user_handler::{attribute_viewer, hash_password, load_user_resource};
+use crate::middlewares::policy_guard::Authorizer;
+use crate::models::user::ROLE_USER;
+use crate::schemas::user_schema::{
+    BulkMode, UserBulkOperation, UserBulkRequestSchema, UserResponseSchema,
+};
+use crate::utils::response::ApiResponse;
+use crate::utils::user_attributes::{Viewer, current_attribute_schema};
+use crate::utils::user_fields::{SHOW_FIELDS, select_list};
+use crate::utils::validation::validation_errors;
+
+// Type error response alias
+type BulkErrorResponse = (StatusCode, Json<ApiResponse<Value>>);
+
+/// Default cap on operations per request (override with `USER_BULK_MAX_OPERATIONS`).
+const DEFAULT_MAX_OPERATIONS: usize = 500;
+
+fn max_operations() -> usize {
+    std::env::var("USER_BULK_MAX_OPERATIONS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(DEFAULT_MAX_OPERATIONS)
+}
+
+/// Outcome of one operation, shaped like the response of the equivalent single-user endpoint.
+struct ItemResult {
+    status: StatusCode,
+    message: String,
+    data: Value,
+}
+
+impl ItemResult {
+    fn new(status: StatusCode, message: &str, data: Value) -> Self {
+        Self {
+            status,
+            message: message.to_string(),
+            data,
+        }
+    }
+
+    fn database(context: &str, e: sqlx::Error) -> Self {
+        Self::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database error",
+            json!({ "error": context, "details": e.to_string() }),
+        )
+    }
+
+    /// Map a write error, turning unique-key violations on email into `409`.
+    fn write_error(context: &str, e: sqlx::Error) -> Self {
+        match &e {
+            sqlx::Error::Database(db_err) if db_err.code().as_deref() == Some("1062") => Self::new(
+                StatusCode::CONFLICT,
+                "Conflict",
+                json!({ "error": "Email already registered", "field": "email" }),
+            ),
+            _ => Self::database(context, e),
+        }
+    }
+
+    fn precondition_failed() -> Self {
+        Self::new(
+            StatusCode::PRECONDITION_FAILED,
+            "Precondition Failed",
+            json!({ "error": "User was modified by another request; fetch it again and retry" }),
+        )
+    }
+
+    fn to_json(&self, index: usize, op: &str) -> Value {
+        json!({
+            "index": index,
+            "op": op,
+            "status": self.status.as_u16(),
+            "success": self.status.is_success(),
+            "message": self.message,
+            "data": self.data,
+        })
+    }
+}
+
+impl From<BulkErrorResponse> for ItemResult {
+    fn from((status, Json(response)): BulkErrorResponse) -> Self {
+        Self {
+            status,
+            message: response.message,
+            data: response.data.unwrap_or(Value::Null),
+        }
+    }
+}
+
+/// A parsed and validated operation; passwords are replaced by their hashes before running.
+enum Prepared {
+    Create {
+        name: String,
+        email: String,
+        password: String,
+    },
+    Update {
+        id: i64,
+        name: String,
+        email: String,
+        password: Option<String>,
+        version: Option<i64>,
+    },
+    Delete {
+        id: i64,
+        version: Option<i64>,
+    },
+}
+
+impl Prepared {
+    fn password_mut(&mut self) -> Option<&mut String> {
+        match self {
+            Prepared::Create { password, .. } => Some(password),
+            Prepared::Update { password, .. } => password.as_mut(),
+            Prepared::Delete { .. } => None,
+        }
+    }
+}
+
+fn validation_failed(errors: Value) -> ItemResult {
+    ItemResult::new(
+        StatusCode::BAD_REQUEST,
+        "Validation error",
+        json!({ "errors": errors }),
+    )
+}
+
+/// Custom attributes depend on the caller's view of each user; they are only written by the
+/// single-user endpoints.
+fn attributes_unsupported() -> ItemResult {
+    validation_failed(json!({
+        "attributes": ["Not supported in bulk operations; use POST /users or PUT /users/{id}"]
+    }))
+}
+
+/// Parse and validate one operation (no database access).
+fn prepare(raw: Value, authz: &Authorizer) -> Result<Prepared, ItemResult> {
+    let op: UserBulkOperation = serde_json::from_value(raw)
+        .map_err(|e| validation_failed(json!({ "operation": [e.to_string()] })))?;
+    match op {
+        UserBulkOperation::Create { data } => {
+            if let Some(errors) = validation_errors(&data) {
+                return Err(validation_failed(Value::Object(errors)));
+            }
+            if data.attributes.is_some() {
+                return Err(attributes_unsupported());
+            }
+            let email = data.email.trim().to_lowercase();
+            authz.authorize(
+                "users:create",
+                &json!({ "email": email, "role": ROLE_USER }),
+            )?;
+            Ok(Prepared::Create {
+                name: data.name,
+                email,
+                password: data.password,
+            })
+        }
+        UserBulkOperation::Update { id, data, version } => {
+            if let Some(errors) = validation_errors(&data) {
+                return Err(validation_failed(Value::Object(errors)));
+            }
+            if data.attributes.is_some() {
+                return Err(attributes_unsupported());
+            }
+            Ok(Prepared::Update {
+                id,
+                name: data.name,
+                email: data.email.trim().to_lowercase(),
+                password: data.password,
+                version,
+            })
+        }
+        UserBulkOperation::Delete { id, version } => Ok(Prepared::Delete { id, version }),
+    }
+}
+
+/// Hash all passwords concurrently on the blocking pool instead of one request at a time.
+async fn hash_passwords(items: &mut [Result<Prepared, ItemResult>]) {
+    let mut jobs = Vec::new();
+    for (i, item) in items.iter_mut().enumerate() {
+        if let Ok(op) = item
+            && let Some(password) = op.password_mut()
+        {
+            let password = password.clone();
+            jobs.push((
+                i,
+                tokio::task::spawn_blocking(move || hash_password(&password)),
+            ));
+        }
+    }
+    for (i, job) in jobs {
+        let hashed = match job.await {
+            Ok(res) => res.map_err(ItemResult::from),
+            Err(_) => Err(ItemResult::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Hash error",
+                json!({ "error": "Failed to hash password" }),
+            )),
+        };
+        match hashed {
+            Ok(hash) => {
+                if let Ok(op) = &mut items[i]
+                    && let Some(password) = op.password_mut()
+                {
+                    *password = hash;
+                }
+            }
+            Err(e) => items[i] = Err(e),
+        }
+    }
+}
+
+async fn fetch_user(
+    conn: &mut MySqlConnection,
+    id: i64,
+    viewer: &Viewer,
+) -> Result<Value, ItemResult> {
+    let mut user = sqlx::query_as::<_, UserResponseSchema>(&format!(
+        "SELECT {} FROM users WHERE id = ?",
+        select_list(&SHOW_FIELDS, &[])
+    ))
+    .bind(id)
+    .fetch_one(conn)
+    .await
+    .map_err(|e| ItemResult::database("Failed to fetch user", e))?;
+    user.attributes =
+        Some(current_attribute_schema().visible(user.attributes.as_ref(), viewer, id));
+    Ok(json!(user))
+}
+
+/// Load a live user and authorize `action` on it; returns its current version.
+async fn authorize_existing(
+    conn: &mut MySqlConnection,
+    authz: &Authorizer,
+    action: &str,
+    id: i64,
+    expected_version: Option<i64>,
+) -> Result<i64, ItemResult> {
+    let (resource, version) = load_user_resource(&mut *conn, id)
+        .await
+        .map_err(|e| ItemResult::database("Failed to fetch user", e))?
+        .ok_or_else(|| {
+            ItemResult::new(
+                StatusCode::NOT_FOUND,
+                "Not Found",
+                json!({ "error": "User not found" }),
+            )
+        })?;
+    authz.authorize(action, &resource)?;
+    if expected_version.is_some_and(|v| v != version) {
+        return Err(ItemResult::precondition_failed());
+    }
+    Ok(version)
+}
+
+/// Run one operation with the same rules as the single-user endpoints.
+async fn execute(
+    conn: &mut MySqlConnection,
+    authz: &Authorizer,
+    viewer: &Viewer,
+    op: &Prepared,
+) -> Result<ItemResult, ItemResult> {
+    match op {
+        Prepared::Create {
+            name,
+            email,
+            password,
+        } => {
+            let res = sqlx::query("INSERT INTO users (name, email, password) VALUES (?, ?, ?)")
+                .bind(name)
+                .bind(email)
+                .bind(password)
+                .execute(&mut *conn)
+                .await
+                .map_err(|e| ItemResult::write_error("Failed to create user", e))?;
+            let user = fetch_user(conn, res.last_insert_id() as i64, viewer).await?;
+            Ok(ItemResult::new(StatusCode::CREATED, "User created", user))
+        }
+        Prepared::Update {
+            id,
+            name,
+            email,
+            password,
+            version,
+        } => {
+            let current = authorize_existing(conn, authz, "users:update", *id, *version).await?;
+            let res = sqlx::query(
+                r#"
+                UPDATE users
+                SET name = ?,
+                    email = ?,
+                    password = COALESCE(?, password),
+                    version = version + 1
+                WHERE id = ? AND deleted_at IS NULL AND version = ?
+                "#,
+            )
+            .bind(name)
+            .bind(email)
+            .bind(password)
+            .bind(id)
+            .bind(current)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| ItemResult::write_error("Failed to update user", e))?;
+            if res.rows_affected() == 0 {
+                return Err(ItemResult::precondition_failed());
+            }
+            let user = fetch_user(conn, *id, viewer).await?;
+            Ok(ItemResult::new(
+                StatusCode::OK,
+                "User updated successfully",
+                user,
+            ))
+        }
+        Prepared::Delete { id, version } => {
+            let current = authorize_existing(conn, authz, "users:delete", *id, *version).await?;
+            let res = sqlx::query(
+                r#"
+                UPDATE users
+                SET deleted_at = NOW(), version = version + 1
+                WHERE id = ? AND deleted_at IS NULL AND version = ?
+                "#,
+            )
+            .bind(id)
+            .bind(current)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| ItemResult::database("Failed to delete user", e))?;
+            if res.rows_affected() == 0 {
+                return Err(ItemResult::precondition_failed());
+            }
+            Ok(ItemResult::new(
+                StatusCode::OK,
+                "User deleted successfully",
+                json!({ "user_id": id }),
+            ))
+        }
+    }
+}
+
+// POST /users/bulk
+pub async fn bulk(
+    Extension(db): Extension<MySqlPool>,
+    authz: Authorizer,
+    Json(payload): Json<UserBulkRequestSchema>,
+) -> Result<(StatusCode, Json<ApiResponse<Value>>), BulkErrorResponse> {
+    let max = max_operations();
+    let count = payload.operations.len();
+    if count == 0 || count > max {
+        let response = ApiResponse::error_with_data(
+            "Validation error",
+            json!({ "errors": {
+                "operations": [format!("Must contain between 1 and {} operations", max)]
+            } }),
+        );
+        return Err((StatusCode::BAD_REQUEST, Json(response)));
+    }
+    let db_error = |e: sqlx::Error| {
+        let response = ApiResponse::error_with_data(
+            "Database error",
+            json!({ "error": "Bulk operation failed", "details": e.to_string() }),
+        );
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
+    };
+
+    let mode = payload.mode;
+    let ops: Vec<String> = payload
+        .operations
+        .iter()
+        .map(|raw| {
+            raw.get("op")
+                .and_then(Value::as_str)
+                .unwrap_or("")
+                .to_string()
+        })
+        .collect();
+    let mut items: Vec<Result<Prepared, ItemResult>> = payload
+        .operations
+        .into_iter()
+        .map(|raw| prepare(raw, &authz))
+        .collect();
+
+    // In atomic mode nothing is written unless every operation is valid
+    let mut run = mode == BulkMode::BestEffort || items.iter().all(Result::is_ok);
+    if run {
+        hash_passwords(&mut items).await;
+        run = mode == BulkMode::BestEffort || items.iter().all(Result::is_ok);
+    }
+
+    let mut results: Vec<Option<ItemResult>> = (0..count).map(|_| None).collect();
+    let mut committed = false;
+    if run {
+        let viewer = attribute_viewer(&db, &authz).await?;
+        let mut tx = db.begin().await.map_err(db_error)?;
+        let mut aborted = false;
+        for (i, item) in items.iter().enumerate() {
+            let Ok(op) = item else { continue };
+            // Each operation runs in a savepoint so a failure only undoes its own writes
+            let mut savepoint = tx.begin().await.map_err(db_error)?;
+            match execute(&mut savepoint, &authz, &viewer, op).await {
+                Ok(result) => {
+                    savepoint.commit().await.map_err(db_error)?;
+                    results[i] = Some(result);
+                }
+                Err(result) => {
+                    savepoint.rollback().await.map_err(db_error)?;
+                    results[i] = Some(result);
+                    if mode == BulkMode::Atomic {
+                        aborted = true;
+                        break;
+                    }
+                }
+            }
+        }
+        if aborted {
+            tx.rollback().await.map_err(db_error)?;
+        } else {
+            tx.commit().await.map_err(db_error)?;
+            committed = true;
+        }
+    }
+
+    let (mut succeeded, mut failed) = (0usize, 0usize);
+    let mut rendered = Vec::with_capacity(count);
+    for (i, (item, result)) in items.into_iter().zip(results).enumerate() {
+        let result = match (item, result) {
+            (Err(e), _) => e,
+            (Ok(_), Some(r)) if committed || !r.status.is_success() => r,
+            // Valid operations that were skipped or rolled back with the atomic batch
+            _ => ItemResult::new(
+                StatusCode::FAILED_DEPENDENCY,
+                "Not applied",
+                json!({ "error": "Another operation failed; nothing was applied" }),
+            ),
+        };
+        if result.status.is_success() {
+            succeeded += 1;
+        } else {
+            failed += 1;
+        }
+        rendered.push(result.to_json(i, &ops[i]));
+    }
+
+    tracing::info!(
+        "users bulk: mode={:?} operations={} succeeded={} failed={} committed={}",
+        mode,
+        count,
+        succeeded,
+        failed,
+        committed
+    );
+
+    let data = json!({
+        "mode": mode,
+        "committed": committed,
+        "succeeded": succeeded,
+        "failed": failed,
+        "results": rendered,
+    });
+    if failed == 0 {
+        let response = ApiResponse::success_with_data("Bulk operation completed", data);
+        return Ok((StatusCode::OK, Json(response)));
+    }
+    let message = if committed {
+        "Bulk operation partially applied"
+    } else {
+        "Bulk operation rolled back"
+    };
+    Ok((
+        StatusCode::MULTI_STATUS,
+        Json(ApiResponse::error_with_data(message, data)),
+    ))
+}
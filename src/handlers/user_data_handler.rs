@@ -0,0 +1,336 @@
+use axum::extract::Path;
+use axum::http::{HeaderMap, HeaderValue, header};
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json, http::StatusCode};
+
+use crate::utils::handler::HandlerResult;
+use serde_json::{Value, json};
+use sqlx::MySqlPool;
+
+// Import schemas for data requests
+use crate::schemas::user_data_schema::UserDataRequestResponseSchema;
+
+// Import util response API, JWT claims, admin check and data request helpers
+use crate::middlewares::admin_middleware::is_admin;
+use crate::storage::blob_store;
+use crate::tasks::user_data_requests::run_export;
+use crate::utils::jwt::Claims;
+use crate::utils::response::ApiResponse;
+use crate::utils::user_data::{KIND_ERASE, KIND_EXPORT, REQUEST_COLUMNS, erasure_grace_days};
+
+type ErrorResponse = (StatusCode, Json<ApiResponse<Value>>);
+
+// Shared mapper for unexpected database errors
+fn db_error(context: &str, e: sqlx::Error) -> ErrorResponse {
+    let response = ApiResponse::error_with_data(
+        "Database error",
+        json!({ "error": context, "details": e.to_string() }),
+    );
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
+}
+
+fn not_found(error: &str) -> ErrorResponse {
+    let response = ApiResponse::error_with_data("Not Found", json!({ "error": error }));
+    (StatusCode::NOT_FOUND, Json(response))
+}
+
+async fn fetch_request(
+    db: &MySqlPool,
+    request_id: i64,
+) -> Result<UserDataRequestResponseSchema, ErrorResponse> {
+    sqlx::query_as::<_, UserDataRequestResponseSchema>(&format!(
+        "SELECT {} FROM user_data_requests WHERE id = ?",
+        REQUEST_COLUMNS
+    ))
+    .bind(request_id)
+    .fetch_one(db)
+    .await
+    .map_err(|e| db_error("Failed to fetch data request", e))
+}
+
+/// Admin routes act on any user that has not been erased (soft-deleted users included).
+async fn ensure_erasable_user(db: &MySqlPool, user_id: i64) -> Result<(), ErrorResponse> {
+    let erased_at: Option<Option<chrono::DateTime<chrono::Utc>>> =
+        sqlx::query_scalar("SELECT erased_at FROM users WHERE id = ?")
+            .bind(user_id)
+            .fetch_optional(db)
+            .await
+            .map_err(|e| db_error("Failed to fetch user", e))?;
+    match erased_at {
+        None => Err(not_found("User not found")),
+        Some(Some(_)) => {
+            let response =
+                ApiResponse::error_with_data("Conflict", json!({ "error": "User was erased" }));
+            Err((StatusCode::CONFLICT, Json(response)))
+        }
+        Some(None) => Ok(()),
+    }
+}
+
+/// The user's open request of a kind, if any (only one may be in progress at a time).
+async fn open_request(
+    db: &MySqlPool,
+    user_id: i64,
+    kind: &str,
+) -> Result<Option<UserDataRequestResponseSchema>, ErrorResponse> {
+    sqlx::query_as::<_, UserDataRequestResponseSchema>(&format!(
+        "SELECT {} FROM user_data_requests \
+         WHERE user_id = ? AND kind = ? AND status IN ('pending', 'processing') \
+         ORDER BY id LIMIT 1",
+        REQUEST_COLUMNS
+    ))
+    .bind(user_id)
+    .bind(kind)
+    .fetch_optional(db)
+    .await
+    .map_err(|e| db_error("Failed to fetch data requests", e))
+}
+
+fn already_requested(error: &str, request: UserDataRequestResponseSchema) -> ErrorResponse {
+    let response =
+        ApiResponse::error_with_data("Conflict", json!({ "error": error, "request": request }));
+    (StatusCode::CONFLICT, Json(response))
+}
+
+async fn create_export(db: &MySqlPool, user_id: i64, requested_by: i64) -> HandlerResult {
+    if let Some(open) = open_request(db, user_id, KIND_EXPORT).await? {
+        return Err(already_requested("An export is already in progress", open));
+    }
+    let res = sqlx::query(
+        "INSERT INTO user_data_requests (user_id, kind, requested_by) VALUES (?, ?, ?)",
+    )
+    .bind(user_id)
+    .bind(KIND_EXPORT)
+    .bind(requested_by)
+    .execute(db)
+    .await
+    .map_err(|e| db_error("Failed to create export request", e))?;
+    let request_id = res.last_insert_id() as i64;
+    let request = fetch_request(db, request_id).await?;
+
+    // Build the archive in the background; the periodic worker retries it if this instance stops
+    let pool = db.clone();
+    tokio::spawn(async move {
+        if let Err(e) = run_export(&pool, request_id).await {
+            tracing::error!("user data export: request_id={}: {}", request_id, e);
+        }
+    });
+
+    tracing::info!(
+        "user data export requested: request_id={} user_id={} requested_by={}",
+        request_id,
+        user_id,
+        requested_by
+    );
+    let response = ApiResponse::success_with_data("Export requested", json!(request));
+    Ok((StatusCode::ACCEPTED, Json(response)))
+}
+
+async fn create_erasure(db: &MySqlPool, user_id: i64, requested_by: i64) -> HandlerResult {
+    if let Some(open) = open_request(db, user_id, KIND_ERASE).await? {
+        return Err(already_requested("An erasure is already scheduled", open));
+    }
+    let res = sqlx::query(
+        r#"
+        INSERT INTO user_data_requests (user_id, kind, requested_by, scheduled_for)
+        VALUES (?, ?, ?, NOW() + INTERVAL ? DAY)
+        "#,
+    )
+    .bind(user_id)
+    .bind(KIND_ERASE)
+    .bind(requested_by)
+    .bind(erasure_grace_days())
+    .execute(db)
+    .await
+    .map_err(|e| db_error("Failed to create erasure request", e))?;
+    let request = fetch_request(db, res.last_insert_id() as i64).await?;
+
+    tracing::info!(
+        "user erasure scheduled: request_id={} user_id={} requested_by={}",
+        request.id,
+        user_id,
+        requested_by
+    );
+    let response = ApiResponse::success_with_data("Erasure scheduled", json!(request));
+    Ok((StatusCode::ACCEPTED, Json(response)))
+}
+
+async fn cancel_erasure(db: &MySqlPool, user_id: i64, cancelled_by: i64) -> HandlerResult {
+    // Only pending requests can be cancelled; once processing starts the data is going away
+    let open = open_request(db, user_id, KIND_ERASE)
+        .await?
+        .filter(|request| request.status == "pending")
+        .ok_or_else(|| not_found("No pending erasure"))?;
+    let res = sqlx::query(
+        "UPDATE user_data_requests SET status = 'cancelled', completed_at = NOW() \
+         WHERE id = ? AND status = 'pending'",
+    )
+    .bind(open.id)
+    .execute(db)
+    .await
+    .map_err(|e| db_error("Failed to cancel erasure", e))?;
+    if res.rows_affected() == 0 {
+        return Err(not_found("No pending erasure"));
+    }
+    let request = fetch_request(db, open.id).await?;
+
+    tracing::info!(
+        "user erasure cancelled: request_id={} user_id={} cancelled_by={}",
+        request.id,
+        user_id,
+        cancelled_by
+    );
+    let response = ApiResponse::success_with_data("Erasure cancelled", json!(request));
+    Ok((StatusCode::OK, Json(response)))
+}
+
+async fn list_requests(db: &MySqlPool, user_id: i64) -> HandlerResult {
+    let requests = sqlx::query_as::<_, UserDataRequestResponseSchema>(&format!(
+        "SELECT {} FROM user_data_requests WHERE user_id = ? ORDER BY id DESC",
+        REQUEST_COLUMNS
+    ))
+    .bind(user_id)
+    .fetch_all(db)
+    .await
+    .map_err(|e| db_error("Failed to fetch data requests", e))?;
+    let response = ApiResponse::success_with_data(
+        "Data requests fetched successfully",
+        json!({ "requests": requests }),
+    );
+    Ok((StatusCode::OK, Json(response)))
+}
+
+// Request an export of the caller's data
+pub async fn export_me(
+    Extension(db): Extension<MySqlPool>,
+    Extension(claims): Extension<Claims>,
+) -> HandlerResult {
+    create_export(&db, claims.sub, claims.sub).await
+}
+
+// Schedule erasure of the caller's account after the grace period
+pub async fn erase_me(
+    Extension(db): Extension<MySqlPool>,
+    Extension(claims): Extension<Claims>,
+) -> HandlerResult {
+    create_erasure(&db, claims.sub, claims.sub).await
+}
+
+// Cancel the caller's pending erasure
+pub async fn cancel_erase_me(
+    Extension(db): Extension<MySqlPool>,
+    Extension(claims): Extension<Claims>,
+) -> HandlerResult {
+    cancel_erasure(&db, claims.sub, claims.sub).await
+}
+
+// List the caller's export and erasure requests
+pub async fn index_me(
+    Extension(db): Extension<MySqlPool>,
+    Extension(claims): Extension<Claims>,
+) -> HandlerResult {
+    list_requests(&db, claims.sub).await
+}
+
+// Admin: request an export of a user's data
+pub async fn export_user(
+    Path(id): Path<i64>,
+    Extension(db): Extension<MySqlPool>,
+    Extension(claims): Extension<Claims>,
+) -> HandlerResult {
+    ensure_erasable_user(&db, id).await?;
+    create_export(&db, id, claims.sub).await
+}
+
+// Admin: schedule erasure of a user
+pub async fn erase_user(
+    Path(id): Path<i64>,
+    Extension(db): Extension<MySqlPool>,
+    Extension(claims): Extension<Claims>,
+) -> HandlerResult {
+    ensure_erasable_user(&db, id).await?;
+    create_erasure(&db, id, claims.sub).await
+}
+
+// Admin: cancel a user's pending erasure
+pub async fn cancel_erase_user(
+    Path(id): Path<i64>,
+    Extension(db): Extension<MySqlPool>,
+    Extension(claims): Extension<Claims>,
+) -> HandlerResult {
+    cancel_erasure(&db, id, claims.sub).await
+}
+
+// Admin: list a user's export and erasure requests
+pub async fn index_user(Path(id): Path<i64>, Extension(db): Extension<MySqlPool>) -> HandlerResult {
+    list_requests(&db, id).await
+}
+
+/// `GET /data-requests/{id}/download` — the archive of a completed export, for its user or an
+/// admin. Other callers get `404` so request ids are not revealed.
+pub async fn download(
+    Path(id): Path<i64>,
+    Extension(db): Extension<MySqlPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Response, ErrorResponse> {
+    let row: Option<(i64, String, Option<String>)> = sqlx::query_as(
+        "SELECT user_id, status, archive_key FROM user_data_requests WHERE id = ? AND kind = ?",
+    )
+    .bind(id)
+    .bind(KIND_EXPORT)
+    .fetch_optional(&db)
+    .await
+    .map_err(|e| db_error("Failed to fetch data request", e))?;
+    let (user_id, status, key) = row.ok_or_else(|| not_found("Export not found"))?;
+    if user_id != claims.sub
+        && !is_admin(&db, claims.sub)
+            .await
+            .map_err(|e| db_error("Failed to check role", e))?
+    {
+        return Err(not_found("Export not found"));
+    }
+
+    let Some(key) = key else {
+        let (code, message, error) = match status.as_str() {
+            "pending" | "processing" => {
+                (StatusCode::CONFLICT, "Conflict", "Export is not ready yet")
+            }
+            "failed" => (StatusCode::CONFLICT, "Conflict", "Export failed"),
+            _ => (StatusCode::GONE, "Gone", "Export has expired"),
+        };
+        let response =
+            ApiResponse::error_with_data(message, json!({ "error": error, "status": status }));
+        return Err((code, Json(response)));
+    };
+    let storage_error = |e: crate::storage::BlobError| {
+        let response = ApiResponse::error_with_data(
+            "Storage error",
+            json!({ "error": "Failed to read export", "details": e.to_string() }),
+        );
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
+    };
+    let data = blob_store()
+        .map_err(storage_error)?
+        .get(&key)
+        .await
+        .map_err(storage_error)?
+        .ok_or_else(|| not_found("Export not found"))?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    let disposition = format!(
+        "attachment; filename=\"user-{}-export-{}.json\"",
+        user_id, id
+    );
+    if let Ok(v) = HeaderValue::from_str(&disposition) {
+        headers.insert(header::CONTENT_DISPOSITION, v);
+    }
+    headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static("private, no-store"),
+    );
+    Ok((StatusCode::OK, headers, data).into_response())
+}
@@ -0,0 +1,164 @@
+use axum::http::HeaderMap;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::{Extension, Json, http::StatusCode};
+use futures_util::stream::{self, Stream, StreamExt};
+use serde_json::{Value, json};
+use sqlx::MySqlPool;
+use std::convert::Infallible;
+use std::time::{Duration, Instant};
+
+// Import policy guard, JWT claims and response helpers
+use crate::middlewares::policy_guard::{Authorizer, load_principal};
+use crate::utils::jwt::Claims;
+use crate::utils::response::ApiResponse;
+use crate::utils::user_events::{
+    GAP_WAIT, STREAM_EVENT_TYPES, contiguous_prefix, events_after, keep_alive_interval, log_bounds,
+    poll_interval, replay_limit, resume_from, visible_to,
+};
+use crate::utils::user_status::account_state;
+use crate::utils::webhooks::event_body;
+
+/// Events read from the log per poll.
+const BATCH_SIZE: i64 = 200;
+
+/// How often an open stream re-checks that the account may still sign in (and reloads its
+/// role and attributes for policy checks).
+const STATUS_RECHECK: Duration = Duration::from_secs(30);
+
+type EventsErrorResponse = (StatusCode, Json<ApiResponse<Value>>);
+
+/// State of one open stream.
+struct UserEventStream {
+    db: MySqlPool,
+    claims: Claims,
+    principal: Value,
+    user_id: i64,
+    /// Token expiry (Unix seconds); the stream ends there and the client reconnects
+    expires_at: i64,
+    cursor: i64,
+    gap_since: Option<Instant>,
+    status_checked: Instant,
+    poll: Duration,
+}
+
+impl UserEventStream {
+    /// Whether the stream may go on: the token is unexpired and the account still active.
+    async fn still_authorized(&mut self) -> bool {
+        if chrono::Utc::now().timestamp() >= self.expires_at {
+            return false;
+        }
+        if self.status_checked.elapsed() < STATUS_RECHECK {
+            return true;
+        }
+        self.status_checked = Instant::now();
+        if let Ok(principal) = load_principal(&self.db, &self.claims).await {
+            self.principal = principal;
+        }
+        match account_state(&self.db, self.user_id).await {
+            Ok(Some(status)) => status.login_error().is_none(),
+            Ok(None) => false,
+            // Keep the stream open through transient database errors
+            Err(_) => true,
+        }
+    }
+
+    /// Read new events and move the cursor; returns the ones to send to this caller.
+    async fn poll_once(&mut self) -> Result<Vec<Event>, sqlx::Error> {
+        let rows = events_after(&self.db, self.cursor, BATCH_SIZE).await?;
+        let ids: Vec<i64> = rows.iter().map(|row| row.id).collect();
+        let ready = contiguous_prefix(self.cursor, &ids, &mut self.gap_since, GAP_WAIT);
+
+        let mut events = Vec::new();
+        for row in rows.into_iter().take(ready) {
+            self.cursor = row.id;
+            if !STREAM_EVENT_TYPES.contains(&row.event_type.as_str())
+                || !visible_to(&self.principal, &row.payload)
+            {
+                continue;
+            }
+            let body = event_body(row.id, &row.event_type, row.created_at, &row.payload);
+            events.push(
+                Event::default()
+                    .id(row.id.to_string())
+                    .event(row.event_type)
+                    .data(body.to_string()),
+            );
+        }
+        Ok(events)
+    }
+
+    /// Wait for the next batch of events; `None` ends the stream.
+    async fn next_batch(mut self) -> Option<(Vec<Event>, Self)> {
+        loop {
+            if !self.still_authorized().await {
+                tracing::info!("user events: closing stream of user_id={}", self.user_id);
+                return None;
+            }
+            match self.poll_once().await {
+                Ok(events) if !events.is_empty() => return Some((events, self)),
+                Ok(_) => {}
+                Err(e) => tracing::warn!("user events: failed to read event log: {}", e),
+            }
+            tokio::time::sleep(self.poll).await;
+        }
+    }
+}
+
+/// `GET /events/users` — Server-Sent Events stream of user creations, updates and deletions
+/// the caller may read. Sending `Last-Event-ID` resumes after that event.
+pub async fn stream_user_events(
+    Extension(db): Extension<MySqlPool>,
+    Extension(claims): Extension<Claims>,
+    authz: Authorizer,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, EventsErrorResponse> {
+    let last_event_id = match headers.get("last-event-id") {
+        None => None,
+        Some(v) => match v.to_str().ok().and_then(|v| v.trim().parse::<i64>().ok()) {
+            Some(id) => Some(id),
+            None => {
+                let response = ApiResponse::error_with_data(
+                    "Validation error",
+                    json!({ "errors": { "Last-Event-ID": ["Must be an event id"] } }),
+                );
+                return Err((StatusCode::BAD_REQUEST, Json(response)));
+            }
+        },
+    };
+    let bounds = log_bounds(&db).await.map_err(|e| {
+        let response = ApiResponse::error_with_data(
+            "Database error",
+            json!({ "error": "Failed to read event log", "details": e.to_string() }),
+        );
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
+    })?;
+    let resume = resume_from(last_event_id, bounds, replay_limit());
+
+    // A client that cannot resume is told to reload, with an id to resume from next time
+    let reset = resume.reset.map(|reason| {
+        Event::default()
+            .id(resume.cursor.to_string())
+            .event("reset")
+            .data(json!({ "reason": reason }).to_string())
+    });
+    let state = UserEventStream {
+        principal: authz.principal,
+        user_id: claims.sub,
+        expires_at: claims.exp as i64,
+        cursor: resume.cursor,
+        gap_since: None,
+        status_checked: Instant::now(),
+        poll: poll_interval(),
+        claims,
+        db,
+    };
+    let events = stream::iter(reset)
+        .chain(stream::unfold(state, UserEventStream::next_batch).flat_map(stream::iter))
+        .map(Ok);
+
+    Ok(Sse::new(events).keep_alive(
+        KeepAlive::new()
+            .interval(keep_alive_interval())
+            .text("keep-alive"),
+    ))
+}
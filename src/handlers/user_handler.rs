@@ -1,11 +1,16 @@
-use axum::extract::Path;
+use axum::body::Bytes;
+use axum::extract::{OriginalUri, Path};
+use axum::http::{HeaderMap, HeaderValue, Uri, header};
+use axum::response::{IntoResponse, Response};
 use axum::{Extension, Json, http::StatusCode};
 
 use crate::utils::handler::HandlerResult;
 use bcrypt::hash;
 use serde::Deserialize;
 use serde_json::{Value, json};
-use sqlx::MySqlPool;
+use sqlx::types::Json as SqlJson;
+use sqlx::{MySql, MySqlPool, QueryBuilder};
+use std::collections::BTreeMap;
 
 // Import Models User
 use crate::models::user::User;
@@ -19,85 +24,682 @@ use crate::schemas::user_schema::{
 // Import util response API
 use crate::utils::response::ApiResponse;
 
+// Import ETag helpers (optimistic concurrency)
+use crate::utils::etag::{etag_for, if_match, if_none_match};
+
+// Import sparse fieldset / include helpers
+use crate::utils::user_fields::{
+    LIST_FIELDS, SHOW_FIELDS, embed_includes, parse_fields, parse_includes, project, select_list,
+};
+
+// Import custom attribute schema
+use crate::utils::user_attributes::{AttributeSchema, Viewer, current_attribute_schema};
+
+// Import account status lifecycle
+use crate::utils::user_status::UserStatus;
+
+// Import change history
+use crate::utils::user_revisions::{
+    ACTION_CREATE, ACTION_DELETE, ACTION_RESTORE, ACTION_UPDATE, ChangeContext, record_change,
+    snapshot,
+};
+
+// Import WebSocket notifications
+use crate::utils::notifications::{NOTIFY_PASSWORD_CHANGED, notify_user, revoke_sessions};
+
+// Import webhook outbox
+use crate::utils::webhooks::{
+    EVENT_USER_CREATED, EVENT_USER_DELETED, EVENT_USER_UPDATED, enqueue_user_event,
+};
+
+// Import list filtering/sorting helpers
+use crate::utils::user_query::{
+    CountMode, KeysetCursor, PageDirection, SortField, UserFilters, decode_keyset_cursor,
+    encode_keyset_cursor, parse_datetime_param, parse_sort, push_keyset, push_order_by,
+    query_fingerprint, reversed, sort_to_string,
+};
+
+// Import policy guard (attribute-based permissions)
+use crate::middlewares::admin_middleware::is_admin;
+use crate::middlewares::policy_guard::{Authorizer, user_attributes};
+use crate::utils::jwt::Claims;
+
+// Type error response alias
+type UserErrorResponse = (StatusCode, Json<ApiResponse<Value>>);
+
+/// Load the attributes of a user resource for policy evaluation (`id`, `email`, `role`,
+/// `status` and the custom attributes) and its current version (`None` if missing).
+pub(crate) async fn load_user_resource<'e, E>(
+    db: E,
+    user_id: i64,
+) -> Result<Option<(Value, i64)>, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = MySql>,
+{
+    type ResourceRow = (i64, String, String, String, Option<SqlJson<Value>>, i64);
+    let row: Option<ResourceRow> = sqlx::query_as(&format!(
+        "SELECT id, email, role, {} AS status, attributes, version \
+         FROM users WHERE id = ? AND deleted_at IS NULL",
+        crate::utils::user_status::EFFECTIVE_STATUS_SQL
+    ))
+    .bind(user_id)
+    .fetch_optional(db)
+    .await?;
+    Ok(row.map(|(id, email, role, status, attributes, version)| {
+        let resource = user_attributes(
+            attributes.as_ref().map(|a| &a.0),
+            json!({ "id": id, "email": email, "role": role, "status": status }),
+        );
+        (resource, version)
+    }))
+}
+
+/// Resolve a user resource and authorize `action` on it (404 if missing, 403 if denied).
+///
+/// Returns the user's current version for conditional requests.
+pub(crate) async fn authorize_user_action(
+    db: &MySqlPool,
+    authz: &Authorizer,
+    action: &str,
+    user_id: i64,
+) -> Result<i64, UserErrorResponse> {
+    let (resource, version) = match load_user_resource(db, user_id).await {
+        Ok(Some(r)) => r,
+        Ok(None) => {
+            let response =
+                ApiResponse::error_with_data("Not Found", json!({ "error": "User not found" }));
+            return Err((StatusCode::NOT_FOUND, Json(response)));
+        }
+        Err(e) => {
+            let response = ApiResponse::error_with_data(
+                "Database error",
+                json!({ "error": "Failed to fetch user", "details": e.to_string() }),
+            );
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, Json(response)));
+        }
+    };
+    authz.authorize(action, &resource)?;
+    Ok(version)
+}
+
+/// Id of the caller, recorded as the actor of changes.
+pub(crate) fn actor_id(authz: &Authorizer) -> Option<i64> {
+    authz.principal["id"].as_i64()
+}
+
+/// The caller, for custom attribute visibility (role read from the database).
+pub(crate) async fn attribute_viewer(
+    db: &MySqlPool,
+    authz: &Authorizer,
+) -> Result<Viewer, UserErrorResponse> {
+    let user_id = authz.principal["id"].as_i64().unwrap_or_default();
+    let admin = is_admin(db, user_id).await.map_err(|e| {
+        let response = ApiResponse::error_with_data(
+            "Database error",
+            json!({ "error": "Failed to fetch user", "details": e.to_string() }),
+        );
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
+    })?;
+    Ok(Viewer { user_id, admin })
+}
+
+/// Apply submitted attributes on top of the stored ones (400 with an errors map).
+fn apply_attributes(
+    schema: &AttributeSchema,
+    stored: Option<&Value>,
+    submitted: &serde_json::Map<String, Value>,
+    viewer: &Viewer,
+    subject: Option<i64>,
+) -> Result<Value, UserErrorResponse> {
+    schema
+        .apply(stored, submitted, viewer, subject)
+        .map_err(|errors| {
+            let response = ApiResponse::error_with_data(
+                "Validation error",
+                json!({ "errors": Value::Object(errors) }),
+            );
+            (StatusCode::BAD_REQUEST, Json(response))
+        })
+}
+
+pub(crate) fn precondition_failed() -> UserErrorResponse {
+    let response = ApiResponse::error_with_data(
+        "Precondition Failed",
+        json!({ "error": "User was modified by another request; fetch it again and retry" }),
+    );
+    (StatusCode::PRECONDITION_FAILED, Json(response))
+}
+
+/// Check `If-Match` on writes against the current version.
+///
+/// A mismatch is `412`; a missing header is `428` when `USER_REQUIRE_IF_MATCH=true`.
+pub(crate) fn check_if_match(headers: &HeaderMap, version: i64) -> Result<(), UserErrorResponse> {
+    match if_match(headers, &etag_for(version)) {
+        Some(true) => Ok(()),
+        Some(false) => Err(precondition_failed()),
+        None if std::env::var("USER_REQUIRE_IF_MATCH").is_ok_and(|v| v == "true") => {
+            let response = ApiResponse::error_with_data(
+                "Precondition Required",
+                json!({ "error": "If-Match header with the user's ETag is required" }),
+            );
+            Err((StatusCode::PRECONDITION_REQUIRED, Json(response)))
+        }
+        None => Ok(()),
+    }
+}
+
+fn etag_header(version: i64) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Ok(v) = HeaderValue::from_str(&etag_for(version)) {
+        headers.insert(header::ETAG, v);
+    }
+    headers
+}
+
 #[derive(Deserialize)]
-pub struct PaginationParams {
+pub struct UserListParams {
     pub page: Option<u32>,
     pub per_page: Option<u32>,
+    /// Opaque cursor from a previous response (`meta.next_cursor` / `meta.prev_cursor`)
+    pub cursor: Option<String>,
+    /// Page size for cursor pagination; setting it without `cursor` starts at the first page
+    pub limit: Option<u32>,
+    /// `exact` (default for page/per_page), `estimate` or `none` (default for cursors)
+    pub count: Option<String>,
+    /// Case-insensitive substring match over name and email
+    pub q: Option<String>,
+    /// Exact email match
+    pub email: Option<String>,
+    /// Comma-separated account statuses (e.g. `suspended,banned`)
+    pub status: Option<String>,
+    /// RFC 3339 timestamp or `YYYY-MM-DD` (inclusive)
+    pub created_after: Option<String>,
+    /// RFC 3339 timestamp or `YYYY-MM-DD` (exclusive)
+    pub created_before: Option<String>,
+    /// Comma-separated sortable columns, `-` prefix for descending (e.g. `name,-created_at`)
+    pub sort: Option<String>,
+    /// Include soft-deleted users (admin only)
+    #[serde(default)]
+    pub include_deleted: bool,
+    /// Comma-separated subset of the user fields to return (e.g. `id,name`)
+    pub fields: Option<String>,
+    /// Comma-separated related data to embed (e.g. `identities`)
+    pub include: Option<String>,
+}
+
+enum Pagination {
+    Offset {
+        page: u32,
+        per_page: u32,
+    },
+    Keyset {
+        limit: u32,
+        cursor: Option<KeysetCursor>,
+    },
+}
+
+/// Validated `GET /users` query.
+struct ListQuery {
+    filters: UserFilters,
+    sort: Vec<SortField>,
+    fingerprint: String,
+    count: CountMode,
+    pagination: Pagination,
+    /// `None` returns the full representation
+    fields: Option<Vec<&'static str>>,
+    includes: Vec<&'static str>,
+}
+
+/// Validate list query parameters (400 with an errors map).
+///
+/// `attribute_filters` are the `attr.<name>=value` pairs of the query string; only attributes
+/// the viewer may see can be filtered on.
+fn parse_list_params(
+    params: &UserListParams,
+    attribute_filters: &[(String, String)],
+    schema: &AttributeSchema,
+    viewer: &Viewer,
+) -> Result<ListQuery, UserErrorResponse> {
+    let mut errors = serde_json::Map::new();
+
+    let mut parse_time = |field: &str, raw: &Option<String>| match raw.as_deref() {
+        Some(v) if !v.trim().is_empty() => {
+            let parsed = parse_datetime_param(v);
+            if parsed.is_none() {
+                errors.insert(
+                    field.to_string(),
+                    json!(["Must be an RFC 3339 timestamp or YYYY-MM-DD date"]),
+                );
+            }
+            parsed
+        }
+        _ => None,
+    };
+    let created_after = parse_time("created_after", &params.created_after);
+    let created_before = parse_time("created_before", &params.created_before);
+
+    let sort = match parse_sort(params.sort.as_deref()) {
+        Ok(fields) => fields,
+        Err(msg) => {
+            errors.insert("sort".to_string(), json!([msg]));
+            Vec::new()
+        }
+    };
+
+    let mut status = Vec::new();
+    for part in params.status.as_deref().unwrap_or("").split(',') {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+        match UserStatus::parse(&part.to_ascii_lowercase()) {
+            Some(s) if !status.contains(&s) => status.push(s),
+            Some(_) => {}
+            None => {
+                errors.insert(
+                    "status".to_string(),
+                    json!(["Must be one of: active, suspended, banned, pending"]),
+                );
+            }
+        }
+    }
+
+    let mut attributes = BTreeMap::new();
+    for (name, raw) in attribute_filters {
+        let key = format!("attr.{}", name);
+        match schema.get(name) {
+            Some(def) if viewer.can_access(def.visibility, None) => match def.parse_filter(raw) {
+                Ok(value) => {
+                    attributes.insert(name.clone(), value);
+                }
+                Err(msg) => {
+                    errors.insert(key, json!([msg]));
+                }
+            },
+            _ => {
+                errors.insert(key, json!(["Unknown attribute"]));
+            }
+        }
+    }
+
+    fn non_empty(v: &Option<String>) -> Option<&str> {
+        v.as_deref().map(str::trim).filter(|s| !s.is_empty())
+    }
+    let filters = UserFilters {
+        q: non_empty(&params.q).map(str::to_string),
+        email: non_empty(&params.email).map(str::to_lowercase),
+        status,
+        created_after,
+        created_before,
+        include_deleted: params.include_deleted,
+        attributes,
+    };
+    let fingerprint = query_fingerprint(&filters, &sort);
+
+    let keyset = params.cursor.is_some() || params.limit.is_some();
+    if keyset && (params.page.is_some() || params.per_page.is_some()) {
+        errors.insert(
+            "page".to_string(),
+            json!(["Cannot be combined with cursor/limit pagination"]),
+        );
+    }
+    let cursor = match non_empty(&params.cursor) {
+        Some(raw) if !sort.is_empty() => match decode_keyset_cursor(raw, &fingerprint, &sort) {
+            Ok(c) => Some(c),
+            Err(msg) => {
+                errors.insert("cursor".to_string(), json!([msg]));
+                None
+            }
+        },
+        _ => None,
+    };
+
+    let fields = parse_fields(params.fields.as_deref(), &LIST_FIELDS).unwrap_or_else(|msg| {
+        errors.insert("fields".to_string(), json!([msg]));
+        None
+    });
+    let includes = parse_includes(params.include.as_deref()).unwrap_or_else(|msg| {
+        errors.insert("include".to_string(), json!([msg]));
+        Vec::new()
+    });
+
+    let count = match non_empty(&params.count) {
+        Some(raw) => CountMode::parse(raw).unwrap_or_else(|| {
+            errors.insert(
+                "count".to_string(),
+                json!(["Must be one of: exact, estimate, none"]),
+            );
+            CountMode::None
+        }),
+        None if keyset => CountMode::None,
+        None => CountMode::Exact,
+    }
+    .for_filters(&filters);
+
+    if !errors.is_empty() {
+        let response = ApiResponse::error_with_data(
+            "Validation error",
+            json!({ "errors": Value::Object(errors) }),
+        );
+        return Err((StatusCode::BAD_REQUEST, Json(response)));
+    }
+
+    let pagination = if keyset {
+        Pagination::Keyset {
+            limit: params.limit.unwrap_or(50).clamp(1, 100),
+            cursor,
+        }
+    } else {
+        Pagination::Offset {
+            page: params.page.unwrap_or(1).max(1),
+            per_page: params.per_page.unwrap_or(50).clamp(1, 100),
+        }
+    };
+    Ok(ListQuery {
+        filters,
+        sort,
+        fingerprint,
+        count,
+        pagination,
+        fields,
+        includes,
+    })
+}
+
+/// Total for `meta.total` according to the requested count mode.
+pub(crate) async fn count_users(
+    db: &MySqlPool,
+    filters: &UserFilters,
+    mode: CountMode,
+) -> Result<Option<i64>, sqlx::Error> {
+    match mode {
+        CountMode::Exact => {
+            let mut qb = QueryBuilder::<MySql>::new("SELECT COUNT(1) FROM users");
+            filters.push_where(&mut qb);
+            qb.build_query_scalar().fetch_one(db).await.map(Some)
+        }
+        CountMode::Estimate => {
+            let rows: Option<Option<i64>> = sqlx::query_scalar(
+                r#"
+                SELECT CAST(TABLE_ROWS AS SIGNED)
+                FROM information_schema.TABLES
+                WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = 'users'
+                "#,
+            )
+            .fetch_optional(db)
+            .await?;
+            Ok(Some(rows.flatten().unwrap_or(0)))
+        }
+        CountMode::None => Ok(None),
+    }
+}
+
+/// A `GET /users` row with its version, for listings that hand versions out.
+#[derive(sqlx::FromRow)]
+pub(crate) struct VersionedUser {
+    #[sqlx(flatten)]
+    pub user: User,
+    pub version: i64,
+}
+
+/// Forward keyset page: up to `limit` users after `cursor` and whether more follow. Shared by
+/// the GraphQL `users` connection and gRPC `ListUsers`.
+pub(crate) async fn keyset_page(
+    db: &MySqlPool,
+    filters: &UserFilters,
+    sort: &[SortField],
+    cursor: Option<&KeysetCursor>,
+    limit: u32,
+) -> Result<(Vec<VersionedUser>, bool), sqlx::Error> {
+    // All user input is bound; sort columns are whitelisted
+    let mut qb = QueryBuilder::<MySql>::new(format!(
+        "SELECT {} FROM users",
+        select_list(&LIST_FIELDS, &["version"])
+    ));
+    let has_where = filters.push_where(&mut qb);
+    if let Some(c) = cursor {
+        qb.push(if has_where { " AND " } else { " WHERE " });
+        push_keyset(&mut qb, sort, &c.keys);
+    }
+    push_order_by(&mut qb, sort);
+    // Fetch one extra row to learn whether another page exists
+    qb.push(" LIMIT ").push_bind(limit as i64 + 1);
+    let mut rows = qb.build_query_as::<VersionedUser>().fetch_all(db).await?;
+    let has_more = rows.len() > limit as usize;
+    rows.truncate(limit as usize);
+    Ok((rows, has_more))
+}
+
+/// Same path and query as the current request, with `cursor` replaced.
+fn link_with_cursor(uri: &Uri, cursor: &str) -> String {
+    let mut query = url::form_urlencoded::Serializer::new(String::new());
+    for (k, v) in url::form_urlencoded::parse(uri.query().unwrap_or("").as_bytes()) {
+        if k != "cursor" {
+            query.append_pair(&k, &v);
+        }
+    }
+    query.append_pair("cursor", cursor);
+    format!("{}?{}", uri.path(), query.finish())
 }
 
 pub async fn index(
     Extension(db): Extension<MySqlPool>,
-    axum::extract::Query(params): axum::extract::Query<PaginationParams>,
-) -> HandlerResult {
-    // Bound and default pagination values
-    let page = params.page.unwrap_or(1).max(1);
-    let per_page = params.per_page.unwrap_or(50).clamp(1, 100);
-    let offset = (page - 1) as i64 * per_page as i64;
+    Extension(claims): Extension<Claims>,
+    authz: Authorizer,
+    OriginalUri(uri): OriginalUri,
+    axum::extract::Query(params): axum::extract::Query<UserListParams>,
+) -> Result<(StatusCode, HeaderMap, Json<ApiResponse<Value>>), UserErrorResponse> {
+    authz.authorize("users:list", &json!({}))?;
 
-    // Fetch paginated users
-    let users_result = sqlx::query_as::<_, User>(
-        r#"
-        SELECT id, name, email, created_at, updated_at
-        FROM users
-        ORDER BY id
-        LIMIT ? OFFSET ?
-        "#,
-    )
-    .bind(per_page as i64)
-    .bind(offset)
-    .fetch_all(&db)
-    .await;
+    let schema = current_attribute_schema();
+    let viewer = attribute_viewer(&db, &authz).await?;
+    let attribute_filters: Vec<(String, String)> =
+        url::form_urlencoded::parse(uri.query().unwrap_or("").as_bytes())
+            .filter_map(|(k, v)| {
+                k.strip_prefix("attr.")
+                    .map(|name| (name.to_string(), v.into_owned()))
+            })
+            .collect();
+    let query = parse_list_params(&params, &attribute_filters, &schema, &viewer)?;
+    let db_error = |e: sqlx::Error| {
+        let response = ApiResponse::error_with_data(
+            "Database error",
+            json!({ "error": "Failed to fetch users", "details": e.to_string() }),
+        );
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
+    };
 
-    // Fetch total count for pagination metadata
-    let total_result: Result<i64, sqlx::Error> = sqlx::query_scalar("SELECT COUNT(1) FROM users")
-        .fetch_one(&db)
-        .await;
+    // Soft-deleted users are only visible to admins (role read from the database)
+    if query.filters.include_deleted && !is_admin(&db, claims.sub).await.map_err(db_error)? {
+        let response = ApiResponse::error_with_data(
+            "Forbidden",
+            json!({ "error": "Admin privileges required for include_deleted" }),
+        );
+        return Err((StatusCode::FORBIDDEN, Json(response)));
+    }
 
-    match (users_result, total_result) {
-        (Ok(users), Ok(total)) => {
-            let response = ApiResponse::success_with_data(
-                "Users fetched successfully",
-                json!({
-                    "users": users,
-                    "meta": { "page": page, "per_page": per_page, "total": total }
-                }),
-            );
-            Ok((StatusCode::OK, Json(response)))
+    let mut meta = serde_json::Map::new();
+    let mut headers = HeaderMap::new();
+    // All user input is bound; sort columns are whitelisted
+    // Sparse fieldsets select only the requested columns plus the sort keys cursors need
+    let columns = match &query.fields {
+        Some(fields) => {
+            let sort_columns: Vec<&str> = query.sort.iter().map(|f| f.column).collect();
+            select_list(fields, &sort_columns)
         }
-        (Err(e), _) | (_, Err(e)) => {
-            let response = ApiResponse::error_with_data(
-                "Database error",
-                json!({ "error": "Failed to fetch users", "details": e.to_string() }),
-            );
-            Err((StatusCode::INTERNAL_SERVER_ERROR, Json(response)))
+        None => select_list(&LIST_FIELDS, &[]),
+    };
+    let mut qb = QueryBuilder::<MySql>::new(format!("SELECT {} FROM users", columns));
+
+    let users = match &query.pagination {
+        Pagination::Offset { page, per_page } => {
+            let offset = (page - 1) as i64 * *per_page as i64;
+            query.filters.push_where(&mut qb);
+            push_order_by(&mut qb, &query.sort);
+            qb.push(" LIMIT ")
+                .push_bind(*per_page as i64)
+                .push(" OFFSET ")
+                .push_bind(offset);
+            meta.insert("page".to_string(), json!(page));
+            meta.insert("per_page".to_string(), json!(per_page));
+            qb.build_query_as::<User>()
+                .fetch_all(&db)
+                .await
+                .map_err(db_error)?
+        }
+        Pagination::Keyset { limit, cursor } => {
+            // Pages before a cursor are read in reverse order, then flipped back
+            let backward = matches!(cursor, Some(c) if c.direction == PageDirection::Prev);
+            let fields = if backward {
+                reversed(&query.sort)
+            } else {
+                query.sort.clone()
+            };
+            let has_where = query.filters.push_where(&mut qb);
+            if let Some(c) = cursor {
+                qb.push(if has_where { " AND " } else { " WHERE " });
+                push_keyset(&mut qb, &fields, &c.keys);
+            }
+            push_order_by(&mut qb, &fields);
+            // Fetch one extra row to learn whether another page exists
+            qb.push(" LIMIT ").push_bind(*limit as i64 + 1);
+            let mut users = qb
+                .build_query_as::<User>()
+                .fetch_all(&db)
+                .await
+                .map_err(db_error)?;
+            let has_more = users.len() > *limit as usize;
+            users.truncate(*limit as usize);
+            if backward {
+                users.reverse();
+            }
+
+            let has_next = if backward { true } else { has_more };
+            let has_prev = if backward { has_more } else { cursor.is_some() };
+            let next_cursor = users.last().filter(|_| has_next).map(|u| {
+                encode_keyset_cursor(&query.fingerprint, PageDirection::Next, u, &query.sort)
+            });
+            let prev_cursor = users.first().filter(|_| has_prev).map(|u| {
+                encode_keyset_cursor(&query.fingerprint, PageDirection::Prev, u, &query.sort)
+            });
+
+            // RFC 8288 Link header for clients that follow relations instead of reading meta
+            let links: Vec<String> = [("next", &next_cursor), ("prev", &prev_cursor)]
+                .into_iter()
+                .filter_map(|(rel, c)| {
+                    c.as_ref()
+                        .map(|c| format!("<{}>; rel=\"{}\"", link_with_cursor(&uri, c), rel))
+                })
+                .collect();
+            if !links.is_empty()
+                && let Ok(v) = HeaderValue::from_str(&links.join(", "))
+            {
+                headers.insert(header::LINK, v);
+            }
+
+            meta.insert("limit".to_string(), json!(limit));
+            meta.insert("next_cursor".to_string(), json!(next_cursor));
+            meta.insert("prev_cursor".to_string(), json!(prev_cursor));
+            users
         }
+    };
+
+    if let Some(total) = count_users(&db, &query.filters, query.count)
+        .await
+        .map_err(db_error)?
+    {
+        meta.insert("total".to_string(), json!(total));
+    }
+    meta.insert("count".to_string(), json!(query.count.as_str()));
+    meta.insert("filters".to_string(), json!(query.filters));
+    meta.insert("sort".to_string(), json!(sort_to_string(&query.sort)));
+    if let Some(fields) = &query.fields {
+        meta.insert("fields".to_string(), json!(fields));
+    }
+    if !query.includes.is_empty() {
+        meta.insert("include".to_string(), json!(query.includes));
     }
+
+    let mut users: Vec<(i64, Value)> = users
+        .into_iter()
+        .map(|mut u| {
+            u.attributes = Some(schema.visible(u.attributes.as_ref(), &viewer, u.id));
+            let value = json!(u);
+            let value = match &query.fields {
+                Some(fields) => project(&value, fields),
+                None => value,
+            };
+            (u.id, value)
+        })
+        .collect();
+    embed_includes(&db, &query.includes, &mut users)
+        .await
+        .map_err(db_error)?;
+    let users: Vec<Value> = users.into_iter().map(|(_, u)| u).collect();
+
+    let response = ApiResponse::success_with_data(
+        "Users fetched successfully",
+        json!({ "users": users, "meta": Value::Object(meta) }),
+    );
+    Ok((StatusCode::OK, headers, Json(response)))
 }
 
 pub async fn store(
     Extension(db_pool): Extension<MySqlPool>,
+    authz: Authorizer,
     Json(payload): Json<UserStoreRequestSchema>,
 ) -> Result<(StatusCode, Json<ApiResponse<Value>>), (StatusCode, Json<ApiResponse<Value>>)> {
+    let user = create_user(&db_pool, &authz, &payload).await?;
+
+    let user_value = match serde_json::to_value(user) {
+        Ok(v) => v,
+        Err(e) => {
+            let response = ApiResponse::error_with_data(
+                "Serialization error",
+                json!({ "error": "Failed to serialize user", "details": e.to_string() }),
+            );
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, Json(response)));
+        }
+    };
+    let response = ApiResponse::success_with_data("User created", user_value);
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+/// Validate, authorize and insert a new user; returns it with the attributes the caller may
+/// see. Shared by `POST /users` and the GraphQL `createUser` mutation.
+pub(crate) async fn create_user(
+    db_pool: &MySqlPool,
+    authz: &Authorizer,
+    payload: &UserStoreRequestSchema,
+) -> Result<UserResponseSchema, UserErrorResponse> {
     // Validate the incoming payload (reusable helper)
-    crate::utils::validation::validate_payload(&payload)?;
+    crate::utils::validation::validate_payload(payload)?;
 
     // Normalize email
     let email_normalized = payload.email.trim().to_lowercase();
 
-    // Configurable bcrypt cost (useful to lower cost in tests via BCRYPT_COST env var)
-    let bcrypt_cost: u32 = std::env::var("BCRYPT_COST")
-        .ok()
-        .and_then(|v| v.parse().ok())
-        .unwrap_or(bcrypt::DEFAULT_COST);
+    let submitted = payload.attributes.clone().map(Value::Object);
+    authz.authorize(
+        "users:create",
+        &user_attributes(
+            submitted.as_ref(),
+            json!({ "email": email_normalized, "role": crate::models::user::ROLE_USER }),
+        ),
+    )?;
+
+    // Custom attributes (required ones are checked even when none are sent)
+    let schema = current_attribute_schema();
+    let viewer = attribute_viewer(db_pool, authz).await?;
+    let attributes = apply_attributes(
+        &schema,
+        None,
+        &payload.attributes.clone().unwrap_or_default(),
+        &viewer,
+        None,
+    )?;
 
     // Hash the password (cost configurable)
-    let hashed_password = hash(&payload.password, bcrypt_cost).map_err(|_| {
-        let response = ApiResponse::error_with_data(
-            "Hash error",
-            json!({ "error": "Failed to hash password" }),
-        );
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
-    })?;
+    let hashed_password = hash_password(&payload.password)?;
 
     // Helper to map DB errors consistently (handles unique constraint -> 409)
     let map_db_err = |e: sqlx::Error| -> (StatusCode, Json<ApiResponse<Value>>) {
@@ -135,32 +737,45 @@ pub async fn store(
         (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
     };
 
-    // Insert the new user into the database
+    // Insert the new user into the database, with its first revision
+    let mut tx = db_pool.begin().await.map_err(map_db_err)?;
     let res = sqlx::query(
         r#"
-        INSERT INTO users (name, email, password)
-        VALUES (?, ?, ?)
+        INSERT INTO users (name, email, password, attributes, attributes_search)
+        VALUES (?, ?, ?, ?, ?)
         "#,
     )
     .bind(&payload.name)
     .bind(&email_normalized)
     .bind(&hashed_password)
-    .execute(&db_pool)
+    .bind(&attributes)
+    .bind(schema.search_text(Some(&attributes)))
+    .execute(&mut *tx)
     .await
     .map_err(map_db_err)?;
 
     let user_id = res.last_insert_id() as i64;
+    let context = ChangeContext::new(ACTION_CREATE, actor_id(authz));
+    record_change(&mut tx, user_id, context, None)
+        .await
+        .map_err(map_db_err)?;
+    enqueue_user_event(
+        &mut tx,
+        EVENT_USER_CREATED,
+        user_id,
+        json!({ "actor_id": context.actor_id }),
+    )
+    .await
+    .map_err(map_db_err)?;
+    tx.commit().await.map_err(map_db_err)?;
 
     // Fetch the newly created user
-    let user = match sqlx::query_as::<_, UserResponseSchema>(
-        r#"
-        SELECT id, name, email, created_at, updated_at
-        FROM users
-        WHERE id = ?
-        "#,
-    )
+    let mut user = match sqlx::query_as::<_, UserResponseSchema>(&format!(
+        "SELECT {} FROM users WHERE id = ?",
+        select_list(&SHOW_FIELDS, &[])
+    ))
     .bind(user_id)
-    .fetch_one(&db_pool)
+    .fetch_one(db_pool)
     .await
     {
         Ok(user) => user,
@@ -172,39 +787,71 @@ pub async fn store(
             return Err((StatusCode::INTERNAL_SERVER_ERROR, Json(response)));
         }
     };
+    user.attributes = Some(schema.visible(user.attributes.as_ref(), &viewer, user.id));
+    Ok(user)
+}
 
-    let user_value = match serde_json::to_value(user) {
-        Ok(v) => v,
-        Err(e) => {
-            let response = ApiResponse::error_with_data(
-                "Serialization error",
-                json!({ "error": "Failed to serialize user", "details": e.to_string() }),
-            );
-            return Err((StatusCode::INTERNAL_SERVER_ERROR, Json(response)));
-        }
-    };
-    let response = ApiResponse::success_with_data("User created", user_value);
-    Ok((StatusCode::CREATED, Json(response)))
+#[derive(Deserialize)]
+pub struct UserShowParams {
+    /// Comma-separated subset of the user fields to return (e.g. `id,name`)
+    pub fields: Option<String>,
+    /// Comma-separated related data to embed (e.g. `identities`)
+    pub include: Option<String>,
 }
 
 pub async fn show(
     Extension(db_pool): Extension<MySqlPool>,
+    authz: Authorizer,
+    headers: HeaderMap,
     axum::extract::Path(user_id): axum::extract::Path<i64>,
-) -> HandlerResult {
-    // Fetch user by ID
-    let user_result = sqlx::query_as::<_, UserResponseSchema>(
-        r#"
-        SELECT id, name, email, created_at, updated_at
-        FROM users
-        WHERE id = ?
-        "#,
-    )
-    .bind(user_id)
-    .fetch_optional(&db_pool)
-    .await;
+    axum::extract::Query(params): axum::extract::Query<UserShowParams>,
+) -> Result<Response, UserErrorResponse> {
+    let mut errors = serde_json::Map::new();
+    let fields = parse_fields(params.fields.as_deref(), &SHOW_FIELDS).unwrap_or_else(|msg| {
+        errors.insert("fields".to_string(), json!([msg]));
+        None
+    });
+    let includes = parse_includes(params.include.as_deref()).unwrap_or_else(|msg| {
+        errors.insert("include".to_string(), json!([msg]));
+        Vec::new()
+    });
+    if !errors.is_empty() {
+        let response = ApiResponse::error_with_data(
+            "Validation error",
+            json!({ "errors": Value::Object(errors) }),
+        );
+        return Err((StatusCode::BAD_REQUEST, Json(response)));
+    }
+
+    authorize_user_action(&db_pool, &authz, "users:read", user_id).await?;
+    let viewer = attribute_viewer(&db_pool, &authz).await?;
+
+    // Fetch user by ID; the version is always read for the ETag
+    let columns = match &fields {
+        Some(fields) => select_list(fields, &["id", "version"]),
+        None => select_list(&SHOW_FIELDS, &[]),
+    };
+    let sql = format!(
+        "SELECT {} FROM users WHERE id = ? AND deleted_at IS NULL",
+        columns
+    );
+    let user_result = sqlx::query_as::<_, UserResponseSchema>(&sql)
+        .bind(user_id)
+        .fetch_optional(&db_pool)
+        .await;
 
     match user_result {
-        Ok(Some(user)) => {
+        Ok(Some(mut user)) => {
+            let version = user.version;
+            // The client's cached copy is still current
+            if if_none_match(&headers, &etag_for(version)) {
+                return Ok((StatusCode::NOT_MODIFIED, etag_header(version)).into_response());
+            }
+            user.attributes = Some(current_attribute_schema().visible(
+                user.attributes.as_ref(),
+                &viewer,
+                user_id,
+            ));
             let user_value = match serde_json::to_value(user) {
                 Ok(v) => v,
                 Err(e) => {
@@ -215,8 +862,21 @@ pub async fn show(
                     return Err((StatusCode::INTERNAL_SERVER_ERROR, Json(response)));
                 }
             };
+            let user_value = match &fields {
+                Some(fields) => project(&user_value, fields),
+                None => user_value,
+            };
+            let mut users = [(user_id, user_value)];
+            if let Err(e) = embed_includes(&db_pool, &includes, &mut users).await {
+                let response = ApiResponse::error_with_data(
+                    "Database error",
+                    json!({ "error": "Failed to fetch user", "details": e.to_string() }),
+                );
+                return Err((StatusCode::INTERNAL_SERVER_ERROR, Json(response)));
+            }
+            let [(_, user_value)] = users;
             let response = ApiResponse::success_with_data("User fetched successfully", user_value);
-            Ok((StatusCode::OK, Json(response)))
+            Ok((StatusCode::OK, etag_header(version), Json(response)).into_response())
         }
         Ok(None) => {
             let response =
@@ -233,89 +893,531 @@ pub async fn show(
     }
 }
 
-pub async fn update(
-    Path(id): Path<i64>,
-    Extension(db_pool): Extension<MySqlPool>,
-    Json(payload): Json<UserUpdateRequestSchema>,
-) -> HandlerResult {
-    // Validate the incoming payload (reusable helper)
-    crate::utils::validation::validate_payload(&payload)?;
+/// Media types accepted by `PATCH /users/{id}` (advertised in `Accept-Patch`).
+const PATCH_MEDIA_TYPES: &str = "application/merge-patch+json, application/json-patch+json";
+
+/// Fields of the user document that patches may test but not change (`status` has its own
+/// endpoint).
+const READ_ONLY_FIELDS: [&str; 6] = [
+    "id",
+    "created_at",
+    "updated_at",
+    "version",
+    "status",
+    "avatar_url",
+];
 
-    // Prepare optional email (trim + lowercase if provided)
-    let email_param: Option<String> = payload.email.as_ref().map(|s| s.trim().to_lowercase());
+/// Fields a patch may set (`password` is write-only and never part of the document).
+const WRITABLE_FIELDS: [&str; 4] = ["name", "email", "password", "attributes"];
 
-    // Update user in the database (only change fields provided)
+pub(crate) fn hash_password(password: &str) -> Result<String, UserErrorResponse> {
+    // Configurable bcrypt cost (useful to lower cost in tests via BCRYPT_COST env var)
+    let bcrypt_cost: u32 = std::env::var("BCRYPT_COST")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(bcrypt::DEFAULT_COST);
+    hash(password, bcrypt_cost).map_err(|_| {
+        let response = ApiResponse::error_with_data(
+            "Hash error",
+            json!({ "error": "Failed to hash password" }),
+        );
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
+    })
+}
+
+/// Load a live user with the custom attributes `viewer` may see.
+pub(crate) async fn fetch_user(
+    db: &MySqlPool,
+    id: i64,
+    viewer: &Viewer,
+) -> Result<UserResponseSchema, UserErrorResponse> {
+    let mut user = sqlx::query_as::<_, UserResponseSchema>(&format!(
+        "SELECT {} FROM users WHERE id = ? AND deleted_at IS NULL",
+        select_list(&SHOW_FIELDS, &[])
+    ))
+    .bind(id)
+    .fetch_optional(db)
+    .await
+    .map_err(|e| {
+        let response = ApiResponse::error_with_data(
+            "Database error",
+            json!({ "error": "Failed to fetch user", "details": e.to_string() }),
+        );
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
+    })?
+    .ok_or_else(|| {
+        let response =
+            ApiResponse::error_with_data("Not Found", json!({ "error": "User not found" }));
+        (StatusCode::NOT_FOUND, Json(response))
+    })?;
+    user.attributes =
+        Some(current_attribute_schema().visible(user.attributes.as_ref(), viewer, id));
+    Ok(user)
+}
+
+/// Stored custom attributes of a live user, all of them (`None` when unset).
+async fn stored_attributes(db: &MySqlPool, id: i64) -> Result<Option<Value>, UserErrorResponse> {
+    let stored: Option<Option<Value>> =
+        sqlx::query_scalar("SELECT attributes FROM users WHERE id = ? AND deleted_at IS NULL")
+            .bind(id)
+            .fetch_optional(db)
+            .await
+            .map_err(|e| {
+                let response = ApiResponse::error_with_data(
+                    "Database error",
+                    json!({ "error": "Failed to fetch user", "details": e.to_string() }),
+                );
+                (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
+            })?;
+    Ok(stored.flatten())
+}
+
+/// Merge the payload's attributes into the stored ones (`None` keeps them unchanged).
+async fn updated_attributes(
+    db: &MySqlPool,
+    id: i64,
+    viewer: &Viewer,
+    payload: &UserUpdateRequestSchema,
+) -> Result<Option<Value>, UserErrorResponse> {
+    let Some(submitted) = &payload.attributes else {
+        return Ok(None);
+    };
+    let stored = stored_attributes(db, id).await?;
+    let schema = current_attribute_schema();
+    apply_attributes(&schema, stored.as_ref(), submitted, viewer, Some(id)).map(Some)
+}
+
+/// Replace all writable fields of a user, guarded by the version it was read at, and record
+/// the change in its history.
+///
+/// A `password` or `attributes` of `None` keeps the current value; `attributes` is the full
+/// stored object from [`AttributeSchema::apply`]. The write fails with `412` if the user
+/// changed since `version` was loaded and `409` if the email belongs to another user.
+pub(crate) async fn replace_user(
+    db: &MySqlPool,
+    id: i64,
+    version: i64,
+    payload: &UserUpdateRequestSchema,
+    attributes: Option<Value>,
+    context: ChangeContext<'_>,
+) -> Result<(), UserErrorResponse> {
+    let hashed_password = payload.password.as_deref().map(hash_password).transpose()?;
+    let attributes_search = attributes
+        .as_ref()
+        .and_then(|a| current_attribute_schema().search_text(Some(a)));
+    let db_error = |e: sqlx::Error| {
+        let response = ApiResponse::error_with_data(
+            "Database error",
+            json!({ "error": "Failed to update user", "details": e.to_string() }),
+        );
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
+    };
+    let mut tx = db.begin().await.map_err(db_error)?;
+    let before = snapshot(&mut tx, id).await.map_err(db_error)?;
     let result = sqlx::query(
         r#"
         UPDATE users
-        SET name = COALESCE(?, name),
-            email = COALESCE(?, email)
-        WHERE id = ?
+        SET name = ?,
+            email = ?,
+            password = COALESCE(?, password),
+            attributes_search = IF(? IS NULL, attributes_search, ?),
+            attributes = COALESCE(?, attributes),
+            version = version + 1
+        WHERE id = ? AND deleted_at IS NULL AND version = ?
         "#,
     )
-    .bind(payload.name.clone())
-    .bind(email_param)
+    .bind(&payload.name)
+    .bind(payload.email.trim().to_lowercase())
+    .bind(hashed_password)
+    .bind(&attributes)
+    .bind(attributes_search)
+    .bind(&attributes)
     .bind(id)
-    .execute(&db_pool)
+    .bind(version)
+    .execute(&mut *tx)
     .await;
 
     match result {
-        Ok(res) => {
-            if res.rows_affected() == 0 {
-                let response =
-                    ApiResponse::error_with_data("Not Found", json!({ "error": "User not found" }));
-                return Err((StatusCode::NOT_FOUND, Json(response)));
-            }
-            let response = ApiResponse::success_with_data(
-                "User updated successfully",
-                json!({ "user_id": id }),
-            );
-            Ok((StatusCode::OK, Json(response)))
-        }
-        Err(e) => {
+        Ok(res) if res.rows_affected() == 0 => return Err(precondition_failed()),
+        Ok(_) => {}
+        Err(sqlx::Error::Database(db_err)) if db_err.code().as_deref() == Some("1062") => {
             let response = ApiResponse::error_with_data(
-                "Database error",
-                json!({ "error": "Failed to update user", "details": e.to_string() }),
+                "Conflict",
+                json!({ "error": "Email already registered", "field": "email" }),
             );
-            Err((StatusCode::INTERNAL_SERVER_ERROR, Json(response)))
+            return Err((StatusCode::CONFLICT, Json(response)));
         }
+        Err(e) => return Err(db_error(e)),
     }
+    record_change(&mut tx, id, context, before.as_ref())
+        .await
+        .map_err(db_error)?;
+    enqueue_user_event(
+        &mut tx,
+        EVENT_USER_UPDATED,
+        id,
+        json!({ "actor_id": context.actor_id }),
+    )
+    .await
+    .map_err(db_error)?;
+    tx.commit().await.map_err(db_error)?;
+
+    if payload.password.is_some() {
+        notify_user(
+            id,
+            NOTIFY_PASSWORD_CHANGED,
+            json!({ "actor_id": context.actor_id }),
+        );
+    }
+    Ok(())
+}
+
+/// Respond with the freshly written user and its new ETag.
+pub(crate) async fn updated_user_response(
+    db: &MySqlPool,
+    authz: &Authorizer,
+    id: i64,
+) -> Result<(StatusCode, HeaderMap, Json<ApiResponse<Value>>), UserErrorResponse> {
+    let viewer = attribute_viewer(db, authz).await?;
+    let user = fetch_user(db, id, &viewer).await?;
+    let headers = etag_header(user.version);
+    let response = ApiResponse::success_with_data("User updated successfully", json!(user));
+    Ok((StatusCode::OK, headers, Json(response)))
+}
+
+/// `PUT /users/{id}`: full replacement of the user's writable fields.
+pub async fn update(
+    Path(id): Path<i64>,
+    Extension(db_pool): Extension<MySqlPool>,
+    authz: Authorizer,
+    headers: HeaderMap,
+    Json(payload): Json<UserUpdateRequestSchema>,
+) -> Result<(StatusCode, HeaderMap, Json<ApiResponse<Value>>), UserErrorResponse> {
+    // Validate the incoming payload (reusable helper)
+    crate::utils::validation::validate_payload(&payload)?;
+
+    let version = authorize_user_action(&db_pool, &authz, "users:update", id).await?;
+    check_if_match(&headers, version)?;
+
+    update_user(&db_pool, &authz, id, version, &payload).await?;
+    updated_user_response(&db_pool, &authz, id).await
+}
+
+/// Write a validated, authorized full replacement read at `version` (merging attributes the
+/// caller may see). Shared by `PUT /users/{id}` and the GraphQL `updateUser` mutation.
+pub(crate) async fn update_user(
+    db: &MySqlPool,
+    authz: &Authorizer,
+    id: i64,
+    version: i64,
+    payload: &UserUpdateRequestSchema,
+) -> Result<(), UserErrorResponse> {
+    let viewer = attribute_viewer(db, authz).await?;
+    let attributes = updated_attributes(db, id, &viewer, payload).await?;
+    let context = ChangeContext::new(ACTION_UPDATE, actor_id(authz));
+    replace_user(db, id, version, payload, attributes, context).await
+}
+
+fn patch_error(status: StatusCode, message: &str, details: Value) -> UserErrorResponse {
+    let response = ApiResponse::error_with_data(message, details);
+    (status, Json(response))
+}
+
+/// Turn a patched user document back into a validated replacement payload.
+///
+/// Read-only fields must be unchanged, unknown fields are rejected and `name` / `email`
+/// must still be present (400 with an errors map otherwise).
+fn patched_payload(
+    original: &Value,
+    patched: Value,
+) -> Result<UserUpdateRequestSchema, UserErrorResponse> {
+    let Value::Object(mut fields) = patched else {
+        return Err(patch_error(
+            StatusCode::BAD_REQUEST,
+            "Validation error",
+            json!({ "errors": { "": ["Patched document must be an object"] } }),
+        ));
+    };
+
+    let mut errors = serde_json::Map::new();
+    for field in READ_ONLY_FIELDS {
+        if fields.remove(field).as_ref() != original.get(field) {
+            errors.insert(field.to_string(), json!(["Field is read-only"]));
+        }
+    }
+    for field in fields.keys() {
+        if !WRITABLE_FIELDS.contains(&field.as_str()) {
+            errors.insert(field.clone(), json!(["Unknown field"]));
+        }
+    }
+    for field in ["name", "email"] {
+        if fields.get(field).is_none_or(Value::is_null) {
+            errors.insert(field.to_string(), json!(["Field is required"]));
+        }
+    }
+    if errors.is_empty() {
+        match serde_json::from_value::<UserUpdateRequestSchema>(Value::Object(fields)) {
+            Ok(payload) => {
+                crate::utils::validation::validate_payload(&payload)?;
+                return Ok(payload);
+            }
+            Err(e) => {
+                errors.insert("".to_string(), json!([e.to_string()]));
+            }
+        }
+    }
+    Err(patch_error(
+        StatusCode::BAD_REQUEST,
+        "Validation error",
+        json!({ "errors": Value::Object(errors) }),
+    ))
+}
+
+/// `PATCH /users/{id}`: JSON Merge Patch (RFC 7396) or JSON Patch (RFC 6902).
+///
+/// The patch applies to the user document returned by `GET /users/{id}`, plus a write-only
+/// `password`. JSON Patch `test` operations may check any field, including `version`.
+pub async fn patch(
+    Path(id): Path<i64>,
+    Extension(db_pool): Extension<MySqlPool>,
+    authz: Authorizer,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, UserErrorResponse> {
+    let media_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .map(|v| v.trim().to_ascii_lowercase())
+        .unwrap_or_default();
+    if media_type != "application/merge-patch+json" && media_type != "application/json-patch+json" {
+        // 415 advertises the supported patch formats (RFC 5789 §3.1)
+        let response = ApiResponse::error_with_data(
+            "Unsupported Media Type",
+            json!({ "error": "Unsupported patch format", "accepted": PATCH_MEDIA_TYPES }),
+        );
+        return Ok((
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            [("accept-patch", PATCH_MEDIA_TYPES)],
+            Json(response),
+        )
+            .into_response());
+    }
+
+    let version = authorize_user_action(&db_pool, &authz, "users:update", id).await?;
+    check_if_match(&headers, version)?;
+
+    let viewer = attribute_viewer(&db_pool, &authz).await?;
+    let original = json!(fetch_user(&db_pool, id, &viewer).await?);
+    let mut doc = original.clone();
+    let malformed = |e: serde_json::Error| {
+        patch_error(
+            StatusCode::BAD_REQUEST,
+            "Bad Request",
+            json!({ "error": "Malformed patch document", "details": e.to_string() }),
+        )
+    };
+    if media_type == "application/merge-patch+json" {
+        let merge: Value = serde_json::from_slice(&body).map_err(malformed)?;
+        json_patch::merge(&mut doc, &merge);
+    } else {
+        let ops: json_patch::Patch = serde_json::from_slice(&body).map_err(malformed)?;
+        // Failed `test` operations and invalid paths conflict with the current state
+        json_patch::patch(&mut doc, &ops).map_err(|e| {
+            patch_error(
+                StatusCode::CONFLICT,
+                "Conflict",
+                json!({ "error": "Patch could not be applied", "details": e.to_string() }),
+            )
+        })?;
+    }
+
+    let mut payload = patched_payload(&original, doc)?;
+    // Untouched attributes are kept as stored; removing the whole object clears them
+    payload.attributes = match payload.attributes.take() {
+        Some(attrs) if original.get("attributes") == Some(&Value::Object(attrs.clone())) => None,
+        attrs => Some(attrs.unwrap_or_default()),
+    };
+    let attributes = updated_attributes(&db_pool, id, &viewer, &payload).await?;
+    let context = ChangeContext::new(ACTION_UPDATE, actor_id(&authz));
+    replace_user(&db_pool, id, version, &payload, attributes, context).await?;
+    Ok(updated_user_response(&db_pool, &authz, id)
+        .await?
+        .into_response())
 }
 
 pub async fn destroy(
     Path(id): Path<i64>,
     Extension(db_pool): Extension<MySqlPool>,
+    authz: Authorizer,
+    headers: HeaderMap,
 ) -> HandlerResult {
-    // Delete user from the database
-    let result = sqlx::query(
+    let version = authorize_user_action(&db_pool, &authz, "users:delete", id).await?;
+    check_if_match(&headers, version)?;
+    delete_user(&db_pool, &authz, id, version).await?;
+
+    let response =
+        ApiResponse::success_with_data("User deleted successfully", json!({ "user_id": id }));
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// Soft-delete an authorized user read at `version` and close its sessions. Shared by
+/// `DELETE /users/{id}` and the GraphQL `deleteUser` mutation.
+pub(crate) async fn delete_user(
+    db_pool: &MySqlPool,
+    authz: &Authorizer,
+    id: i64,
+    version: i64,
+) -> Result<(), UserErrorResponse> {
+    let db_error = |e: sqlx::Error| {
+        let response = ApiResponse::error_with_data(
+            "Database error",
+            json!({ "error": "Failed to delete user", "details": e.to_string() }),
+        );
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
+    };
+
+    // Soft delete; the row is hard-deleted by the purge task after the retention period
+    let mut tx = db_pool.begin().await.map_err(db_error)?;
+    let before = snapshot(&mut tx, id).await.map_err(db_error)?;
+    let res = sqlx::query(
         r#"
-        DELETE FROM users
-        WHERE id = ?
+        UPDATE users
+        SET deleted_at = NOW(), version = version + 1
+        WHERE id = ? AND deleted_at IS NULL AND version = ?
         "#,
     )
     .bind(id)
-    .execute(&db_pool)
-    .await;
+    .bind(version)
+    .execute(&mut *tx)
+    .await
+    .map_err(db_error)?;
+    if res.rows_affected() == 0 {
+        return Err(precondition_failed());
+    }
+    let context = ChangeContext::new(ACTION_DELETE, actor_id(authz));
+    record_change(&mut tx, id, context, before.as_ref())
+        .await
+        .map_err(db_error)?;
+    enqueue_user_event(
+        &mut tx,
+        EVENT_USER_DELETED,
+        id,
+        json!({ "actor_id": context.actor_id }),
+    )
+    .await
+    .map_err(db_error)?;
+    tx.commit().await.map_err(db_error)?;
+    revoke_sessions(id, "Account deleted");
+    Ok(())
+}
+
+pub async fn restore(
+    Path(id): Path<i64>,
+    Extension(db_pool): Extension<MySqlPool>,
+    authz: Authorizer,
+) -> HandlerResult {
+    let db_error = |msg: &str, e: sqlx::Error| {
+        let response = ApiResponse::error_with_data(
+            "Database error",
+            json!({ "error": msg, "details": e.to_string() }),
+        );
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
+    };
 
+    type Deletion = (
+        Option<chrono::DateTime<chrono::Utc>>,
+        Option<chrono::DateTime<chrono::Utc>>,
+    );
+    let deletion: Option<Deletion> =
+        sqlx::query_as("SELECT deleted_at, erased_at FROM users WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&db_pool)
+            .await
+            .map_err(|e| db_error("Failed to fetch user", e))?;
+    match deletion {
+        None => {
+            let response =
+                ApiResponse::error_with_data("Not Found", json!({ "error": "User not found" }));
+            return Err((StatusCode::NOT_FOUND, Json(response)));
+        }
+        Some((None, _)) => {
+            let response =
+                ApiResponse::error_with_data("Conflict", json!({ "error": "User is not deleted" }));
+            return Err((StatusCode::CONFLICT, Json(response)));
+        }
+        // Personal data is gone; there is nothing meaningful to bring back
+        Some((Some(_), Some(_))) => {
+            let response =
+                ApiResponse::error_with_data("Conflict", json!({ "error": "User was erased" }));
+            return Err((StatusCode::CONFLICT, Json(response)));
+        }
+        Some((Some(_), None)) => {}
+    }
+
+    // The email may have been reused by another live user in the meantime (unique index)
+    let mut tx = db_pool
+        .begin()
+        .await
+        .map_err(|e| db_error("Failed to restore user", e))?;
+    let before = snapshot(&mut tx, id)
+        .await
+        .map_err(|e| db_error("Failed to restore user", e))?;
+    let result =
+        sqlx::query("UPDATE users SET deleted_at = NULL, version = version + 1 WHERE id = ? AND deleted_at IS NOT NULL")
+            .bind(id)
+            .execute(&mut *tx)
+            .await;
     match result {
-        Ok(res) => {
-            if res.rows_affected() == 0 {
-                let response =
-                    ApiResponse::error_with_data("Not Found", json!({ "error": "User not found" }));
-                return Err((StatusCode::NOT_FOUND, Json(response)));
-            }
-            let response = ApiResponse::success_with_data(
-                "User deleted successfully",
-                json!({ "user_id": id }),
-            );
-            Ok((StatusCode::OK, Json(response)))
+        // Restored concurrently; nothing to record
+        Ok(res) if res.rows_affected() == 0 => {}
+        Ok(_) => {
+            let context = ChangeContext::new(ACTION_RESTORE, actor_id(&authz));
+            record_change(&mut tx, id, context, before.as_ref())
+                .await
+                .map_err(|e| db_error("Failed to restore user", e))?;
+            // Subscribers that removed the user on `user.deleted` learn it is back
+            enqueue_user_event(
+                &mut tx,
+                EVENT_USER_UPDATED,
+                id,
+                json!({ "actor_id": context.actor_id }),
+            )
+            .await
+            .map_err(|e| db_error("Failed to restore user", e))?;
         }
-        Err(e) => {
+        Err(sqlx::Error::Database(db_err)) if db_err.code().as_deref() == Some("1062") => {
             let response = ApiResponse::error_with_data(
-                "Database error",
-                json!({ "error": "Failed to delete user", "details": e.to_string() }),
+                "Conflict",
+                json!({ "error": "Email already registered by another user", "field": "email" }),
             );
-            Err((StatusCode::INTERNAL_SERVER_ERROR, Json(response)))
+            return Err((StatusCode::CONFLICT, Json(response)));
         }
+        Err(e) => return Err(db_error("Failed to restore user", e)),
     }
+    tx.commit()
+        .await
+        .map_err(|e| db_error("Failed to restore user", e))?;
+    tracing::info!("user restored: user_id={}", id);
+
+    let mut user = sqlx::query_as::<_, UserResponseSchema>(&format!(
+        "SELECT {} FROM users WHERE id = ?",
+        select_list(&SHOW_FIELDS, &[])
+    ))
+    .bind(id)
+    .fetch_one(&db_pool)
+    .await
+    .map_err(|e| db_error("Failed to fetch user", e))?;
+    let viewer = attribute_viewer(&db_pool, &authz).await?;
+    user.attributes =
+        Some(current_attribute_schema().visible(user.attributes.as_ref(), &viewer, id));
+    let user_value = serde_json::to_value(user).map_err(|e| {
+        let response = ApiResponse::error_with_data(
+            "Serialization error",
+            json!({ "error": "Failed to serialize user", "details": e.to_string() }),
+        );
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
+    })?;
+    let response = ApiResponse::success_with_data("User restored successfully", user_value);
+    Ok((StatusCode::OK, Json(response)))
 }
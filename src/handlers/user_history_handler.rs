@@ -0,0 +1,185 @@
+use axum::extract::{Path, Query};
+use axum::http::HeaderMap;
+use axum::{Extension, Json, http::StatusCode};
+
+use crate::utils::handler::HandlerResult;
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+use sqlx::MySqlPool;
+
+// Import schemas and change history helpers
+use crate::schemas::user_revision_schema::UserRevisionResponseSchema;
+use crate::schemas::user_schema::UserUpdateRequestSchema;
+use crate::utils::user_attributes::current_attribute_schema;
+use crate::utils::user_revisions::{
+    ACTION_REVERT, ChangeContext, REVISION_COLUMNS, UserSnapshot, can_see_field, revert_attributes,
+    values_at_revision, visible_changes,
+};
+
+// Import user handler helpers (authorization, conditional writes)
+use crate::handlers::user_handler::{
+    actor_id, attribute_viewer, authorize_user_action, check_if_match, replace_user,
+    updated_user_response,
+};
+use crate::middlewares::policy_guard::Authorizer;
+use crate::utils::response::ApiResponse;
+
+type UserErrorResponse = (StatusCode, Json<ApiResponse<Value>>);
+
+#[derive(Deserialize)]
+pub struct UserHistoryParams {
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+fn db_error(message: &str, e: sqlx::Error) -> UserErrorResponse {
+    let response = ApiResponse::error_with_data(
+        "Database error",
+        json!({ "error": message, "details": e.to_string() }),
+    );
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
+}
+
+/// `GET /users/{id}/history` — recorded changes of a user, newest first.
+pub async fn index(
+    Path(id): Path<i64>,
+    Extension(db): Extension<MySqlPool>,
+    authz: Authorizer,
+    Query(params): Query<UserHistoryParams>,
+) -> HandlerResult {
+    authorize_user_action(&db, &authz, "users:history", id).await?;
+
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = params.per_page.unwrap_or(50).clamp(1, 100);
+    let offset = (page - 1) as i64 * per_page as i64;
+
+    let revisions = sqlx::query_as::<_, UserRevisionResponseSchema>(&format!(
+        "SELECT {} FROM user_revisions WHERE user_id = ? ORDER BY id DESC LIMIT ? OFFSET ?",
+        REVISION_COLUMNS
+    ))
+    .bind(id)
+    .bind(per_page as i64)
+    .bind(offset)
+    .fetch_all(&db)
+    .await
+    .map_err(|e| db_error("Failed to fetch user history", e))?;
+    let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM user_revisions WHERE user_id = ?")
+        .bind(id)
+        .fetch_one(&db)
+        .await
+        .map_err(|e| db_error("Failed to fetch user history", e))?;
+
+    // Custom attributes hidden from the caller are left out of the diffs
+    let schema = current_attribute_schema();
+    let viewer = attribute_viewer(&db, &authz).await?;
+    let revisions: Vec<UserRevisionResponseSchema> = revisions
+        .into_iter()
+        .map(|mut revision| {
+            revision.changes.0 = visible_changes(&revision.changes.0, &schema, &viewer, id);
+            revision
+        })
+        .collect();
+
+    let response = ApiResponse::success_with_data(
+        "User history fetched successfully",
+        json!({
+            "revisions": revisions,
+            "meta": { "page": page, "per_page": per_page, "total": total },
+        }),
+    );
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// `POST /users/{id}/history/{revision}/revert` — bring name, email and custom attributes back
+/// to their values right after `revision`. The revert is recorded as a new revision.
+pub async fn revert(
+    Path((id, revision_id)): Path<(i64, i64)>,
+    Extension(db): Extension<MySqlPool>,
+    authz: Authorizer,
+    headers: HeaderMap,
+) -> Result<(StatusCode, HeaderMap, Json<ApiResponse<Value>>), UserErrorResponse> {
+    let version = authorize_user_action(&db, &authz, "users:revert", id).await?;
+    check_if_match(&headers, version)?;
+
+    let exists: Option<i64> =
+        sqlx::query_scalar("SELECT id FROM user_revisions WHERE id = ? AND user_id = ?")
+            .bind(revision_id)
+            .bind(id)
+            .fetch_optional(&db)
+            .await
+            .map_err(|e| db_error("Failed to fetch revision", e))?;
+    if exists.is_none() {
+        let response =
+            ApiResponse::error_with_data("Not Found", json!({ "error": "Revision not found" }));
+        return Err((StatusCode::NOT_FOUND, Json(response)));
+    }
+
+    let later: Vec<sqlx::types::Json<Value>> = sqlx::query_scalar(
+        "SELECT changes FROM user_revisions WHERE user_id = ? AND id > ? ORDER BY id",
+    )
+    .bind(id)
+    .bind(revision_id)
+    .fetch_all(&db)
+    .await
+    .map_err(|e| db_error("Failed to fetch user history", e))?;
+    let later: Vec<Value> = later.into_iter().map(|changes| changes.0).collect();
+    let values = values_at_revision(&later);
+
+    let current = sqlx::query_as::<_, UserSnapshot>(
+        "SELECT name, email, attributes, deleted_at, version FROM users WHERE id = ?",
+    )
+    .bind(id)
+    .fetch_one(&db)
+    .await
+    .map_err(|e| db_error("Failed to fetch user", e))?;
+
+    // Attributes hidden from the caller keep their current value
+    let schema = current_attribute_schema();
+    let viewer = attribute_viewer(&db, &authz).await?;
+    let keep = |key: &str| can_see_field(&schema, &viewer, id, key);
+    let reverted = revert_attributes(current.attributes.as_ref(), &values, keep);
+    // The old values must still pass the current attribute schema, like a PUT: attributes
+    // removed from the schema or failing its rules since are rejected with 422
+    let submitted: Map<String, Value> = match reverted {
+        Value::Object(reverted) => reverted
+            .into_iter()
+            .filter(|(name, _)| {
+                let key = format!("attributes.{}", name);
+                (values.contains_key(&key) && keep(&key))
+                    || schema
+                        .get(name)
+                        .is_some_and(|d| viewer.can_access(d.visibility, Some(id)))
+            })
+            .collect(),
+        _ => Map::new(),
+    };
+    let attributes = schema
+        .apply(current.attributes.as_ref(), &submitted, &viewer, Some(id))
+        .map_err(|errors| {
+            let response = ApiResponse::error_with_data(
+                "Validation error",
+                json!({ "errors": Value::Object(errors) }),
+            );
+            (StatusCode::UNPROCESSABLE_ENTITY, Json(response))
+        })?;
+    let text = |field: &str, current: String| {
+        values
+            .get(field)
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or(current)
+    };
+    let payload = UserUpdateRequestSchema {
+        name: text("name", current.name),
+        email: text("email", current.email),
+        password: None,
+        attributes: None,
+    };
+    let context = ChangeContext {
+        action: ACTION_REVERT,
+        actor_id: actor_id(&authz),
+        reverted_to: Some(revision_id),
+    };
+    replace_user(&db, id, version, &payload, Some(attributes), context).await?;
+    updated_user_response(&db, &authz, id).await
+}
@@ -0,0 +1,154 @@
+use axum::extract::Query;
+use axum::{Extension, Json, http::StatusCode};
+
+use crate::utils::handler::HandlerResult;
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+use sqlx::{MySql, MySqlPool, QueryBuilder};
+
+// Import Models User
+use crate::models::user::User;
+
+// Import policy guard, attribute viewer and response helpers
+use crate::handlers::user_handler::attribute_viewer;
+use crate::middlewares::policy_guard::Authorizer;
+use crate::utils::response::ApiResponse;
+use crate::utils::user_attributes::current_attribute_schema;
+use crate::utils::user_fields::{LIST_FIELDS, select_list};
+use crate::utils::user_search::{SearchMode, SearchQuery, highlight};
+
+#[derive(Deserialize)]
+pub struct UserSearchParams {
+    pub q: Option<String>,
+    /// `natural` (default) or `boolean`
+    pub mode: Option<String>,
+    /// Retry with shortened words when nothing matches (natural mode; default true)
+    pub fuzzy: Option<bool>,
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+#[derive(sqlx::FromRow)]
+struct SearchRow {
+    #[sqlx(flatten)]
+    user: User,
+    score: f64,
+}
+
+fn validation_error(field: &str, message: &str) -> (StatusCode, Json<ApiResponse<Value>>) {
+    let response = ApiResponse::error_with_data(
+        "Validation error",
+        json!({ "errors": { field: [message] } }),
+    );
+    (StatusCode::BAD_REQUEST, Json(response))
+}
+
+async fn run_search(
+    db: &MySqlPool,
+    query: &SearchQuery,
+    per_page: u32,
+    offset: i64,
+) -> Result<(Vec<SearchRow>, i64), sqlx::Error> {
+    let mut qb = QueryBuilder::<MySql>::new(format!("SELECT {}, ", select_list(&LIST_FIELDS, &[])));
+    query.push_score(&mut qb);
+    qb.push(" AS score FROM users WHERE deleted_at IS NULL AND ");
+    query.push_match(&mut qb);
+    qb.push(" ORDER BY score DESC, id LIMIT ")
+        .push_bind(per_page as i64)
+        .push(" OFFSET ")
+        .push_bind(offset);
+    let rows = qb.build_query_as::<SearchRow>().fetch_all(db).await?;
+
+    let mut qb =
+        QueryBuilder::<MySql>::new("SELECT COUNT(*) FROM users WHERE deleted_at IS NULL AND ");
+    query.push_match(&mut qb);
+    let total: i64 = qb.build_query_scalar().fetch_one(db).await?;
+    Ok((rows, total))
+}
+
+/// `GET /users/search` — full-text search over name, email and public custom attributes,
+/// ranked by relevance, with highlighted matches.
+pub async fn search(
+    Extension(db): Extension<MySqlPool>,
+    authz: Authorizer,
+    Query(params): Query<UserSearchParams>,
+) -> HandlerResult {
+    authz.authorize("users:list", &json!({}))?;
+
+    let mode = match params.mode.as_deref() {
+        None => SearchMode::Natural,
+        Some(raw) => SearchMode::parse(raw)
+            .ok_or_else(|| validation_error("mode", "Must be one of: natural, boolean"))?,
+    };
+    let query = SearchQuery::parse(params.q.as_deref().unwrap_or(""), mode)
+        .map_err(|message| validation_error("q", &message))?;
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = params.per_page.unwrap_or(20).clamp(1, 100);
+    let offset = (page - 1) as i64 * per_page as i64;
+
+    let db_error = |e: sqlx::Error| {
+        let response = ApiResponse::error_with_data(
+            "Database error",
+            json!({ "error": "Failed to search users", "details": e.to_string() }),
+        );
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
+    };
+    let (mut rows, mut total) = run_search(&db, &query, per_page, offset)
+        .await
+        .map_err(db_error)?;
+    let mut applied = query;
+    let mut fuzzy_used = false;
+    if total == 0
+        && params.fuzzy.unwrap_or(true)
+        && let Some(fuzzy) = applied.fuzzy()
+    {
+        (rows, total) = run_search(&db, &fuzzy, per_page, offset)
+            .await
+            .map_err(db_error)?;
+        applied = fuzzy;
+        fuzzy_used = true;
+    }
+
+    let schema = current_attribute_schema();
+    let viewer = attribute_viewer(&db, &authz).await?;
+    let users: Vec<Value> = rows
+        .into_iter()
+        .map(|SearchRow { mut user, score }| {
+            let attributes = schema.visible(user.attributes.as_ref(), &viewer, user.id);
+            let mut highlights = Map::new();
+            for (field, text) in [("name", &user.name), ("email", &user.email)] {
+                if let Some(h) = highlight(text, &applied.terms) {
+                    highlights.insert(field.to_string(), json!(h));
+                }
+            }
+            if let Value::Object(visible) = &attributes {
+                for (name, value) in visible {
+                    if let Some(h) = value.as_str().and_then(|v| highlight(v, &applied.terms)) {
+                        highlights.insert(format!("attributes.{}", name), json!(h));
+                    }
+                }
+            }
+            user.attributes = Some(attributes);
+            let mut value = json!(user);
+            if let Value::Object(obj) = &mut value {
+                obj.insert("score".to_string(), json!(score));
+                obj.insert("highlights".to_string(), Value::Object(highlights));
+            }
+            value
+        })
+        .collect();
+
+    let meta = json!({
+        "q": params.q.as_deref().unwrap_or("").trim(),
+        "mode": mode,
+        "fuzzy": fuzzy_used,
+        "page": page,
+        "per_page": per_page,
+        "total": total,
+    });
+    let response = ApiResponse::success_with_data(
+        "Users fetched successfully",
+        json!({ "users": users, "meta": meta }),
+    );
+    Ok((StatusCode::OK, Json(response)))
+}
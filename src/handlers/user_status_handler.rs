@@ -0,0 +1,182 @@
+use axum::extract::Path;
+use axum::{Extension, Json, http::StatusCode};
+
+use crate::utils::handler::HandlerResult;
+use chrono::Utc;
+use serde_json::{Value, json};
+use sqlx::MySqlPool;
+
+// Import schemas for status changes
+use crate::schemas::user_schema::{UserStatusChangeSchema, UserStatusResponseSchema};
+
+// Import util response API, JWT claims and status helpers
+use crate::utils::jwt::Claims;
+use crate::utils::notifications::revoke_sessions;
+use crate::utils::response::ApiResponse;
+use crate::utils::user_status::{EFFECTIVE_STATUS_SQL, UserStatus};
+
+type ErrorResponse = (StatusCode, Json<ApiResponse<Value>>);
+
+// Shared mapper for unexpected database errors
+fn db_error(context: &str, e: sqlx::Error) -> ErrorResponse {
+    let response = ApiResponse::error_with_data(
+        "Database error",
+        json!({ "error": context, "details": e.to_string() }),
+    );
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
+}
+
+fn not_found() -> ErrorResponse {
+    let response = ApiResponse::error_with_data("Not Found", json!({ "error": "User not found" }));
+    (StatusCode::NOT_FOUND, Json(response))
+}
+
+async fn fetch_status(db: &MySqlPool, id: i64) -> Result<UserStatusResponseSchema, ErrorResponse> {
+    sqlx::query_as::<_, UserStatusResponseSchema>(&format!(
+        r#"
+        SELECT id AS user_id, {} AS status, status_reason AS reason, status_until AS until,
+               status_changed_at AS changed_at, status_changed_by AS changed_by
+        FROM users
+        WHERE id = ? AND deleted_at IS NULL
+        "#,
+        EFFECTIVE_STATUS_SQL
+    ))
+    .bind(id)
+    .fetch_optional(db)
+    .await
+    .map_err(|e| db_error("Failed to fetch user status", e))?
+    .ok_or_else(not_found)
+}
+
+// Show the current status of a user and its last change
+pub async fn show(Path(id): Path<i64>, Extension(db): Extension<MySqlPool>) -> HandlerResult {
+    let status = fetch_status(&db, id).await?;
+    let response =
+        ApiResponse::success_with_data("User status fetched successfully", json!(status));
+    Ok((StatusCode::OK, Json(response)))
+}
+
+// Suspend a user, indefinitely or until a given date
+pub async fn suspend(
+    Path(id): Path<i64>,
+    Extension(db): Extension<MySqlPool>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<UserStatusChangeSchema>,
+) -> HandlerResult {
+    change_status(&db, &claims, id, UserStatus::Suspended, payload).await
+}
+
+// Ban a user until an admin reactivates them
+pub async fn ban(
+    Path(id): Path<i64>,
+    Extension(db): Extension<MySqlPool>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<UserStatusChangeSchema>,
+) -> HandlerResult {
+    change_status(&db, &claims, id, UserStatus::Banned, payload).await
+}
+
+// Lift a suspension or ban, or approve a pending account
+pub async fn reactivate(
+    Path(id): Path<i64>,
+    Extension(db): Extension<MySqlPool>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<UserStatusChangeSchema>,
+) -> HandlerResult {
+    change_status(&db, &claims, id, UserStatus::Active, payload).await
+}
+
+async fn change_status(
+    db: &MySqlPool,
+    claims: &Claims,
+    id: i64,
+    next: UserStatus,
+    payload: UserStatusChangeSchema,
+) -> HandlerResult {
+    // Validate the incoming payload (reusable helper)
+    crate::utils::validation::validate_payload(&payload)?;
+    let until_error = match payload.until {
+        Some(_) if next != UserStatus::Suspended => Some("Only a suspension can have an end date"),
+        Some(until) if until <= Utc::now() => Some("Must be in the future"),
+        _ => None,
+    };
+    if let Some(message) = until_error {
+        let response = ApiResponse::error_with_data(
+            "Validation error",
+            json!({ "errors": { "until": [message] } }),
+        );
+        return Err((StatusCode::BAD_REQUEST, Json(response)));
+    }
+
+    // An admin locking themselves out would also lose the ability to undo it
+    if id == claims.sub {
+        let response = ApiResponse::error_with_data(
+            "Forbidden",
+            json!({ "error": "You cannot change your own status" }),
+        );
+        return Err((StatusCode::FORBIDDEN, Json(response)));
+    }
+
+    let row: Option<(String, i64)> = sqlx::query_as(&format!(
+        "SELECT {} AS status, version FROM users WHERE id = ? AND deleted_at IS NULL",
+        EFFECTIVE_STATUS_SQL
+    ))
+    .bind(id)
+    .fetch_optional(db)
+    .await
+    .map_err(|e| db_error("Failed to fetch user", e))?;
+    let (current, version) = row.ok_or_else(not_found)?;
+    let current = UserStatus::parse(&current).unwrap_or(UserStatus::Suspended);
+    if !current.can_transition_to(next) {
+        let response = ApiResponse::error_with_data(
+            "Conflict",
+            json!({
+                "error": format!("Cannot change status from {} to {}", current.as_str(), next.as_str()),
+                "status": current.as_str(),
+            }),
+        );
+        return Err((StatusCode::CONFLICT, Json(response)));
+    }
+
+    // Guarded by version so a concurrent edit or status change is not silently overwritten
+    let res = sqlx::query(
+        r#"
+        UPDATE users
+        SET status = ?, status_reason = ?, status_until = ?, status_changed_at = NOW(),
+            status_changed_by = ?, version = version + 1
+        WHERE id = ? AND deleted_at IS NULL AND version = ?
+        "#,
+    )
+    .bind(next.as_str())
+    .bind(payload.reason.trim())
+    .bind(payload.until)
+    .bind(claims.sub)
+    .bind(id)
+    .bind(version)
+    .execute(db)
+    .await
+    .map_err(|e| db_error("Failed to update user status", e))?;
+    if res.rows_affected() == 0 {
+        let response = ApiResponse::error_with_data(
+            "Precondition Failed",
+            json!({ "error": "User was modified concurrently, retry the request" }),
+        );
+        return Err((StatusCode::PRECONDITION_FAILED, Json(response)));
+    }
+
+    tracing::info!(
+        "user status changed: user_id={} from={} to={} changed_by={}",
+        id,
+        current.as_str(),
+        next.as_str(),
+        claims.sub
+    );
+    // Open WebSocket connections of a suspended or banned user are closed right away
+    if let Some(reason) = next.login_error() {
+        revoke_sessions(id, reason);
+    }
+
+    let status = fetch_status(db, id).await?;
+    let response = ApiResponse::success_with_data("User status updated", json!(status));
+    Ok((StatusCode::OK, Json(response)))
+}
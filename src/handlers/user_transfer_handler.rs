@@ -0,0 +1,500 @@
+use std::collections::HashSet;
+
+use axum::body::{Body, Bytes};
+use axum::extract::Query;
+use axum::http::{HeaderMap, header};
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json, http::StatusCode};
+use chrono::{DateTime, SecondsFormat, Utc};
+use futures_util::StreamExt;
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+use sqlx::{MySql, MySqlPool, QueryBuilder};
+
+use crate::handlers::user_handler::hash_password;
+use crate::middlewares::policy_guard::Authorizer;
+use crate::models::user::ROLE_USER;
+use crate::schemas::user_schema::UserStoreRequestSchema;
+use crate::utils::response::ApiResponse;
+use crate::utils::user_transfer::{RecordSplitter, TransferFormat, csv_line, parse_csv_record};
+use crate::utils::validation::validation_errors;
+
+// Type error response alias
+type TransferErrorResponse = (StatusCode, Json<ApiResponse<Value>>);
+
+/// Columns written by the export (CSV header and NDJSON keys).
+const EXPORT_COLUMNS: [&str; 7] = [
+    "id",
+    "name",
+    "email",
+    "role",
+    "active",
+    "created_at",
+    "updated_at",
+];
+
+/// Columns an import must provide; other CSV columns are ignored.
+const IMPORT_COLUMNS: [&str; 3] = ["name", "email", "password"];
+
+/// Valid rows validated / inserted together (passwords are hashed concurrently per batch).
+const IMPORT_BATCH_SIZE: usize = 50;
+
+/// Row errors listed in the report; further failures are only counted.
+const MAX_REPORTED_ERRORS: usize = 1000;
+
+fn transfer_error(status: StatusCode, message: &str, details: Value) -> TransferErrorResponse {
+    (status, Json(ApiResponse::error_with_data(message, details)))
+}
+
+#[derive(Deserialize)]
+pub struct ExportParams {
+    /// `csv` (default) or `ndjson`
+    pub format: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct ExportRow {
+    id: i64,
+    name: String,
+    email: String,
+    role: String,
+    active: bool,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl ExportRow {
+    fn render(&self, format: TransferFormat) -> Bytes {
+        let created_at = self.created_at.to_rfc3339_opts(SecondsFormat::Secs, true);
+        let updated_at = self.updated_at.to_rfc3339_opts(SecondsFormat::Secs, true);
+        match format {
+            TransferFormat::Csv => Bytes::from(csv_line(&[
+                &self.id.to_string(),
+                &self.name,
+                &self.email,
+                &self.role,
+                if self.active { "true" } else { "false" },
+                &created_at,
+                &updated_at,
+            ])),
+            TransferFormat::Ndjson => {
+                let line = json!({
+                    "id": self.id,
+                    "name": self.name,
+                    "email": self.email,
+                    "role": self.role,
+                    "active": self.active,
+                    "created_at": created_at,
+                    "updated_at": updated_at,
+                });
+                Bytes::from(format!("{}\n", line))
+            }
+        }
+    }
+}
+
+// GET /users/export?format=csv|ndjson
+pub async fn export(
+    Extension(db): Extension<MySqlPool>,
+    Query(params): Query<ExportParams>,
+) -> Result<Response, TransferErrorResponse> {
+    let format = match params.format.as_deref() {
+        None => TransferFormat::Csv,
+        Some(raw) => TransferFormat::parse(raw).ok_or_else(|| {
+            transfer_error(
+                StatusCode::BAD_REQUEST,
+                "Validation error",
+                json!({ "errors": { "format": ["Must be one of: csv, ndjson"] } }),
+            )
+        })?,
+    };
+
+    // Rows are read from the result set one at a time and written straight to the body
+    let body = async_stream::stream! {
+        if format == TransferFormat::Csv {
+            yield Ok::<Bytes, sqlx::Error>(Bytes::from(csv_line(&EXPORT_COLUMNS)));
+        }
+        let mut rows = sqlx::query_as::<_, ExportRow>(
+            r#"
+            SELECT id, name, email, role, active, created_at, updated_at
+            FROM users
+            WHERE deleted_at IS NULL
+            ORDER BY id
+            "#,
+        )
+        .fetch(&db);
+        let mut exported = 0u64;
+        while let Some(row) = rows.next().await {
+            match row {
+                Ok(row) => {
+                    exported += 1;
+                    yield Ok(row.render(format));
+                }
+                Err(e) => {
+                    // Headers are already sent; aborting the body tells the client it is incomplete
+                    tracing::error!("users export: aborted after {} rows: {}", exported, e);
+                    yield Err(e);
+                    break;
+                }
+            }
+        }
+        tracing::info!("users export: format={} rows={}", format.as_str(), exported);
+    };
+
+    let filename = format!("attachment; filename=\"users.{}\"", format.as_str());
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, filename),
+        ],
+        Body::from_stream(body),
+    )
+        .into_response())
+}
+
+#[derive(Deserialize)]
+pub struct ImportParams {
+    /// `csv` or `ndjson`; defaults to the request `Content-Type`
+    pub format: Option<String>,
+    /// Validate every row and report errors without writing anything
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// A validated row waiting for the batch duplicate check / insert.
+struct PendingRow {
+    row: usize,
+    user: UserStoreRequestSchema,
+    email: String,
+}
+
+/// Incremental import state: rows are validated as they arrive and inserted per batch.
+struct Importer<'a> {
+    db: &'a MySqlPool,
+    authz: &'a Authorizer,
+    format: TransferFormat,
+    dry_run: bool,
+    max_rows: usize,
+    /// CSV column positions of `IMPORT_COLUMNS`, read from the header record
+    columns: Option<[usize; 3]>,
+    seen_emails: HashSet<String>,
+    pending: Vec<PendingRow>,
+    rows: usize,
+    valid: usize,
+    imported: usize,
+    failed: usize,
+    errors: Vec<Value>,
+}
+
+impl<'a> Importer<'a> {
+    fn fail(&mut self, row: usize, errors: Map<String, Value>) {
+        self.failed += 1;
+        if self.errors.len() < MAX_REPORTED_ERRORS {
+            self.errors
+                .push(json!({ "row": row, "errors": Value::Object(errors) }));
+        }
+    }
+
+    fn fail_with(&mut self, row: usize, field: &str, message: &str) {
+        let mut errors = Map::new();
+        errors.insert(field.to_string(), json!([message]));
+        self.fail(row, errors);
+    }
+
+    /// Read the CSV header and locate the required columns.
+    fn header(&mut self, record: &[u8]) -> Result<(), TransferErrorResponse> {
+        let names: Vec<String> = parse_csv_record(record)
+            .map_err(|e| {
+                transfer_error(
+                    StatusCode::BAD_REQUEST,
+                    "Invalid CSV header",
+                    json!({ "error": e }),
+                )
+            })?
+            .iter()
+            .map(|n| n.trim_start_matches('\u{feff}').trim().to_lowercase())
+            .collect();
+        let mut columns = [0usize; 3];
+        let mut missing = Vec::new();
+        for (slot, wanted) in columns.iter_mut().zip(IMPORT_COLUMNS) {
+            match names.iter().position(|n| n == wanted) {
+                Some(i) => *slot = i,
+                None => missing.push(wanted),
+            }
+        }
+        if !missing.is_empty() {
+            return Err(transfer_error(
+                StatusCode::BAD_REQUEST,
+                "Invalid CSV header",
+                json!({ "error": "Missing required columns", "missing": missing }),
+            ));
+        }
+        self.columns = Some(columns);
+        Ok(())
+    }
+
+    /// Turn one record into the fields of `UserStoreRequestSchema`.
+    fn fields(&self, record: &[u8]) -> Result<Value, String> {
+        match (self.format, self.columns) {
+            (TransferFormat::Csv, Some(columns)) => {
+                let cells = parse_csv_record(record)?;
+                let mut fields = Map::new();
+                for (name, i) in IMPORT_COLUMNS.iter().zip(columns) {
+                    if let Some(cell) = cells.get(i) {
+                        fields.insert(name.to_string(), json!(cell));
+                    }
+                }
+                Ok(Value::Object(fields))
+            }
+            _ => {
+                let value: Value = serde_json::from_slice(record).map_err(|e| e.to_string())?;
+                if !value.is_object() {
+                    return Err("Each line must be a JSON object".to_string());
+                }
+                Ok(value)
+            }
+        }
+    }
+
+    async fn record(&mut self, record: Vec<u8>) -> Result<(), TransferErrorResponse> {
+        if record.iter().all(u8::is_ascii_whitespace) {
+            return Ok(());
+        }
+        if self.format == TransferFormat::Csv && self.columns.is_none() {
+            return self.header(&record);
+        }
+
+        self.rows += 1;
+        let row = self.rows;
+        if row > self.max_rows {
+            return Err(transfer_error(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "Too many rows",
+                json!({ "error": format!("Imports are limited to {} rows", self.max_rows) }),
+            ));
+        }
+
+        let user = match self.fields(&record).and_then(|v| {
+            serde_json::from_value::<UserStoreRequestSchema>(v).map_err(|e| e.to_string())
+        }) {
+            Ok(user) => user,
+            Err(e) => {
+                self.fail_with(row, "record", &e);
+                return Ok(());
+            }
+        };
+        if let Some(errors) = validation_errors(&user) {
+            self.fail(row, errors);
+            return Ok(());
+        }
+        if user.attributes.is_some() {
+            self.fail_with(row, "attributes", "Custom attributes cannot be imported");
+            return Ok(());
+        }
+        let email = user.email.trim().to_lowercase();
+        if !self.seen_emails.insert(email.clone()) {
+            self.fail_with(row, "email", "Duplicate email in this import");
+            return Ok(());
+        }
+        if let Err((_, Json(response))) = self.authz.authorize(
+            "users:create",
+            &json!({ "email": email, "role": ROLE_USER }),
+        ) {
+            self.fail_with(row, "record", &response.message);
+            return Ok(());
+        }
+
+        self.pending.push(PendingRow { row, user, email });
+        if self.pending.len() >= IMPORT_BATCH_SIZE {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Check the pending batch against existing users and insert it (unless dry-run).
+    async fn flush(&mut self) -> Result<(), TransferErrorResponse> {
+        let batch = std::mem::take(&mut self.pending);
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let db_error = |e: sqlx::Error| {
+            transfer_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error",
+                json!({ "error": "Failed to import users", "details": e.to_string() }),
+            )
+        };
+
+        let mut qb = QueryBuilder::<MySql>::new(
+            "SELECT email FROM users WHERE deleted_at IS NULL AND email IN (",
+        );
+        let mut list = qb.separated(", ");
+        for p in &batch {
+            list.push_bind(p.email.clone());
+        }
+        qb.push(")");
+        let existing: HashSet<String> = qb
+            .build_query_scalar::<String>()
+            .fetch_all(self.db)
+            .await
+            .map_err(db_error)?
+            .into_iter()
+            .collect();
+
+        let mut accepted = Vec::with_capacity(batch.len());
+        for p in batch {
+            if existing.contains(&p.email) {
+                self.fail_with(p.row, "email", "Email already registered");
+            } else {
+                accepted.push(p);
+            }
+        }
+        self.valid += accepted.len();
+        if self.dry_run {
+            return Ok(());
+        }
+
+        // Hash the batch concurrently on the blocking pool, then insert row by row
+        let jobs: Vec<_> = accepted
+            .iter()
+            .map(|p| {
+                let password = p.user.password.clone();
+                tokio::task::spawn_blocking(move || hash_password(&password))
+            })
+            .collect();
+        for (p, job) in accepted.into_iter().zip(jobs) {
+            let hashed = match job.await {
+                Ok(Ok(h)) => h,
+                _ => {
+                    self.valid -= 1;
+                    self.fail_with(p.row, "password", "Failed to hash password");
+                    continue;
+                }
+            };
+            let res = sqlx::query("INSERT INTO users (name, email, password) VALUES (?, ?, ?)")
+                .bind(&p.user.name)
+                .bind(&p.email)
+                .bind(hashed)
+                .execute(self.db)
+                .await;
+            match res {
+                Ok(_) => self.imported += 1,
+                // Registered concurrently since the batch check
+                Err(sqlx::Error::Database(db_err)) if db_err.code().as_deref() == Some("1062") => {
+                    self.valid -= 1;
+                    self.fail_with(p.row, "email", "Email already registered");
+                }
+                Err(e) => return Err(db_error(e)),
+            }
+        }
+        Ok(())
+    }
+}
+
+// POST /users/import?format=csv|ndjson&dry_run=true
+pub async fn import(
+    Extension(db): Extension<MySqlPool>,
+    authz: Authorizer,
+    headers: HeaderMap,
+    Query(params): Query<ImportParams>,
+    body: Body,
+) -> Result<(StatusCode, Json<ApiResponse<Value>>), TransferErrorResponse> {
+    let format = match params.format.as_deref() {
+        Some(raw) => TransferFormat::parse(raw),
+        None => headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(TransferFormat::from_content_type),
+    }
+    .ok_or_else(|| {
+        transfer_error(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "Unsupported Media Type",
+            json!({ "error": "Send text/csv or application/x-ndjson, or set format=csv|ndjson" }),
+        )
+    })?;
+
+    let mut importer = Importer {
+        db: &db,
+        authz: &authz,
+        format,
+        dry_run: params.dry_run,
+        max_rows: std::env::var("USER_IMPORT_MAX_ROWS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(10_000),
+        columns: None,
+        seen_emails: HashSet::new(),
+        pending: Vec::new(),
+        rows: 0,
+        valid: 0,
+        imported: 0,
+        failed: 0,
+        errors: Vec::new(),
+    };
+
+    // The body is consumed chunk by chunk; only the current record and batch are held
+    let mut splitter = RecordSplitter::new(format);
+    let mut stream = body.into_data_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| {
+            transfer_error(
+                StatusCode::BAD_REQUEST,
+                "Bad Request",
+                json!({ "error": "Failed to read request body", "details": e.to_string() }),
+            )
+        })?;
+        let records = splitter.push(&chunk).map_err(|e| {
+            transfer_error(
+                StatusCode::BAD_REQUEST,
+                "Bad Request",
+                json!({ "error": e }),
+            )
+        })?;
+        for record in records {
+            importer.record(record).await?;
+        }
+    }
+    if let Some(record) = splitter.finish() {
+        importer.record(record).await?;
+    }
+    importer.flush().await?;
+
+    if format == TransferFormat::Csv && importer.columns.is_none() {
+        return Err(transfer_error(
+            StatusCode::BAD_REQUEST,
+            "Invalid CSV header",
+            json!({ "error": "The file is empty; a header row with name,email,password is required" }),
+        ));
+    }
+
+    tracing::info!(
+        "users import: format={} dry_run={} rows={} valid={} imported={} failed={}",
+        format.as_str(),
+        importer.dry_run,
+        importer.rows,
+        importer.valid,
+        importer.imported,
+        importer.failed
+    );
+
+    let message = if importer.dry_run {
+        "Dry run completed"
+    } else {
+        "Import completed"
+    };
+    let data = json!({
+        "format": format.as_str(),
+        "dry_run": importer.dry_run,
+        "rows": importer.rows,
+        "valid": importer.valid,
+        "imported": importer.imported,
+        "failed": importer.failed,
+        "errors": importer.errors,
+        "errors_truncated": importer.failed > importer.errors.len(),
+    });
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success_with_data(message, data)),
+    ))
+}
@@ -0,0 +1,316 @@
+use axum::extract::{Path, Query};
+use axum::{Extension, Json, http::StatusCode};
+
+use crate::utils::handler::HandlerResult;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use sqlx::MySqlPool;
+use sqlx::types::Json as SqlJson;
+
+// Import schemas for webhooks
+use crate::schemas::webhook_schema::{
+    WebhookDeliveryResponseSchema, WebhookSubscriptionRequestSchema,
+    WebhookSubscriptionResponseSchema,
+};
+
+// Import util response API, JWT claims and webhook helpers
+use crate::utils::jwt::Claims;
+use crate::utils::response::ApiResponse;
+use crate::utils::webhooks::{
+    DELIVERY_COLUMNS, STATUS_DEAD, STATUS_PENDING, SUBSCRIPTION_COLUMNS, generate_secret,
+};
+
+/// Delivery statuses accepted by the `status` filter.
+const DELIVERY_STATUSES: [&str; 4] = ["pending", "delivering", "delivered", "dead"];
+
+// Shared mapper for unexpected database errors
+fn db_error(context: &str, e: sqlx::Error) -> (StatusCode, Json<ApiResponse<Value>>) {
+    let response = ApiResponse::error_with_data(
+        "Database error",
+        json!({ "error": context, "details": e.to_string() }),
+    );
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
+}
+
+fn not_found(error: &str) -> (StatusCode, Json<ApiResponse<Value>>) {
+    let response = ApiResponse::error_with_data("Not Found", json!({ "error": error }));
+    (StatusCode::NOT_FOUND, Json(response))
+}
+
+async fn fetch_subscription(
+    db: &MySqlPool,
+    id: i64,
+) -> Result<WebhookSubscriptionResponseSchema, (StatusCode, Json<ApiResponse<Value>>)> {
+    sqlx::query_as::<_, WebhookSubscriptionResponseSchema>(&format!(
+        "SELECT {} FROM webhook_subscriptions WHERE id = ?",
+        SUBSCRIPTION_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(db)
+    .await
+    .map_err(|e| db_error("Failed to fetch webhook", e))?
+    .ok_or_else(|| not_found("Webhook not found"))
+}
+
+// List webhook subscriptions (secrets are never listed)
+pub async fn index(Extension(db): Extension<MySqlPool>) -> HandlerResult {
+    let webhooks = sqlx::query_as::<_, WebhookSubscriptionResponseSchema>(&format!(
+        "SELECT {} FROM webhook_subscriptions ORDER BY id",
+        SUBSCRIPTION_COLUMNS
+    ))
+    .fetch_all(&db)
+    .await
+    .map_err(|e| db_error("Failed to fetch webhooks", e))?;
+
+    let response = ApiResponse::success_with_data(
+        "Webhooks fetched successfully",
+        json!({ "webhooks": webhooks }),
+    );
+    Ok((StatusCode::OK, Json(response)))
+}
+
+// Register a subscriber and return its signing secret once
+pub async fn store(
+    Extension(db): Extension<MySqlPool>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<WebhookSubscriptionRequestSchema>,
+) -> HandlerResult {
+    // Validate the incoming payload (reusable helper)
+    crate::utils::validation::validate_payload(&payload)?;
+
+    let secret = payload.secret.clone().unwrap_or_else(generate_secret);
+    let res = sqlx::query(
+        r#"
+        INSERT INTO webhook_subscriptions (url, secret, events, description, active, created_by)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(&payload.url)
+    .bind(&secret)
+    .bind(SqlJson(&payload.events))
+    .bind(&payload.description)
+    .bind(payload.active.unwrap_or(true))
+    .bind(claims.sub)
+    .execute(&db)
+    .await
+    .map_err(|e| db_error("Failed to create webhook", e))?;
+
+    let webhook = fetch_subscription(&db, res.last_insert_id() as i64).await?;
+    tracing::info!(
+        "webhook created: id={} url={} created_by={}",
+        webhook.id,
+        webhook.url,
+        claims.sub
+    );
+
+    let response = ApiResponse::success_with_data(
+        "Webhook created",
+        json!({ "webhook": webhook, "secret": secret }),
+    );
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+pub async fn show(Path(id): Path<i64>, Extension(db): Extension<MySqlPool>) -> HandlerResult {
+    let webhook = fetch_subscription(&db, id).await?;
+    let response = ApiResponse::success_with_data(
+        "Webhook fetched successfully",
+        json!({ "webhook": webhook }),
+    );
+    Ok((StatusCode::OK, Json(response)))
+}
+
+// Replace a subscription; a new `secret` rotates it (and is returned once)
+pub async fn update(
+    Path(id): Path<i64>,
+    Extension(db): Extension<MySqlPool>,
+    Json(payload): Json<WebhookSubscriptionRequestSchema>,
+) -> HandlerResult {
+    // Validate the incoming payload (reusable helper)
+    crate::utils::validation::validate_payload(&payload)?;
+
+    sqlx::query(
+        r#"
+        UPDATE webhook_subscriptions
+        SET url = ?, events = ?, description = ?, active = ?, secret = COALESCE(?, secret)
+        WHERE id = ?
+        "#,
+    )
+    .bind(&payload.url)
+    .bind(SqlJson(&payload.events))
+    .bind(&payload.description)
+    .bind(payload.active.unwrap_or(true))
+    .bind(&payload.secret)
+    .bind(id)
+    .execute(&db)
+    .await
+    .map_err(|e| db_error("Failed to update webhook", e))?;
+    // Unchanged rows report 0 affected rows, so check existence separately
+    let webhook = fetch_subscription(&db, id).await?;
+
+    let mut data = json!({ "webhook": webhook });
+    if let Some(secret) = &payload.secret {
+        data["secret"] = json!(secret);
+    }
+    let response = ApiResponse::success_with_data("Webhook updated", data);
+    Ok((StatusCode::OK, Json(response)))
+}
+
+// Remove a subscription and its delivery log
+pub async fn destroy(Path(id): Path<i64>, Extension(db): Extension<MySqlPool>) -> HandlerResult {
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| db_error("Failed to delete webhook", e))?;
+    sqlx::query("DELETE FROM webhook_deliveries WHERE subscription_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| db_error("Failed to delete webhook", e))?;
+    let res = sqlx::query("DELETE FROM webhook_subscriptions WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| db_error("Failed to delete webhook", e))?;
+    if res.rows_affected() == 0 {
+        return Err(not_found("Webhook not found"));
+    }
+    tx.commit()
+        .await
+        .map_err(|e| db_error("Failed to delete webhook", e))?;
+
+    let response = ApiResponse::success_with_data("Webhook deleted", json!({ "webhook_id": id }));
+    Ok((StatusCode::OK, Json(response)))
+}
+
+#[derive(Deserialize)]
+pub struct DeliveryListParams {
+    /// `pending`, `delivering`, `delivered` or `dead`
+    pub status: Option<String>,
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+// Delivery log of a subscription, newest first
+pub async fn deliveries(
+    Path(id): Path<i64>,
+    Extension(db): Extension<MySqlPool>,
+    Query(params): Query<DeliveryListParams>,
+) -> HandlerResult {
+    fetch_subscription(&db, id).await?;
+    if let Some(status) = &params.status
+        && !DELIVERY_STATUSES.contains(&status.as_str())
+    {
+        let response = ApiResponse::error_with_data(
+            "Validation error",
+            json!({ "errors": { "status": ["Must be one of: pending, delivering, delivered, dead"] } }),
+        );
+        return Err((StatusCode::BAD_REQUEST, Json(response)));
+    }
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = params.per_page.unwrap_or(50).clamp(1, 100);
+    let offset = (page - 1) as i64 * per_page as i64;
+
+    let deliveries = sqlx::query_as::<_, WebhookDeliveryResponseSchema>(&format!(
+        r#"
+        SELECT {} FROM webhook_deliveries d JOIN webhook_events e ON e.id = d.event_id
+        WHERE d.subscription_id = ? AND (? IS NULL OR d.status = ?)
+        ORDER BY d.id DESC
+        LIMIT ? OFFSET ?
+        "#,
+        DELIVERY_COLUMNS
+    ))
+    .bind(id)
+    .bind(&params.status)
+    .bind(&params.status)
+    .bind(per_page as i64)
+    .bind(offset)
+    .fetch_all(&db)
+    .await
+    .map_err(|e| db_error("Failed to fetch deliveries", e))?;
+    let total: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM webhook_deliveries WHERE subscription_id = ? AND (? IS NULL OR status = ?)",
+    )
+    .bind(id)
+    .bind(&params.status)
+    .bind(&params.status)
+    .fetch_one(&db)
+    .await
+    .map_err(|e| db_error("Failed to fetch deliveries", e))?;
+
+    let response = ApiResponse::success_with_data(
+        "Deliveries fetched successfully",
+        json!({
+            "deliveries": deliveries,
+            "meta": { "page": page, "per_page": per_page, "total": total },
+        }),
+    );
+    Ok((StatusCode::OK, Json(response)))
+}
+
+// Send one delivery again (any finished delivery, including successful ones)
+pub async fn replay(
+    Path(delivery_id): Path<i64>,
+    Extension(db): Extension<MySqlPool>,
+) -> HandlerResult {
+    let res = sqlx::query(
+        r#"
+        UPDATE webhook_deliveries
+        SET status = ?, attempts = 0, next_attempt_at = NOW(), locked_at = NULL
+        WHERE id = ? AND status <> 'delivering'
+        "#,
+    )
+    .bind(STATUS_PENDING)
+    .bind(delivery_id)
+    .execute(&db)
+    .await
+    .map_err(|e| db_error("Failed to replay delivery", e))?;
+
+    let delivery = sqlx::query_as::<_, WebhookDeliveryResponseSchema>(&format!(
+        "SELECT {} FROM webhook_deliveries d JOIN webhook_events e ON e.id = d.event_id \
+         WHERE d.id = ?",
+        DELIVERY_COLUMNS
+    ))
+    .bind(delivery_id)
+    .fetch_optional(&db)
+    .await
+    .map_err(|e| db_error("Failed to fetch delivery", e))?
+    .ok_or_else(|| not_found("Delivery not found"))?;
+    if res.rows_affected() == 0 {
+        let response = ApiResponse::error_with_data(
+            "Conflict",
+            json!({ "error": "Delivery is being sent right now", "delivery": delivery }),
+        );
+        return Err((StatusCode::CONFLICT, Json(response)));
+    }
+
+    let response =
+        ApiResponse::success_with_data("Delivery queued", json!({ "delivery": delivery }));
+    Ok((StatusCode::OK, Json(response)))
+}
+
+// Queue every dead-lettered delivery of a subscription again
+pub async fn replay_dead(
+    Path(id): Path<i64>,
+    Extension(db): Extension<MySqlPool>,
+) -> HandlerResult {
+    fetch_subscription(&db, id).await?;
+    let res = sqlx::query(
+        r#"
+        UPDATE webhook_deliveries
+        SET status = ?, attempts = 0, next_attempt_at = NOW()
+        WHERE subscription_id = ? AND status = ?
+        "#,
+    )
+    .bind(STATUS_PENDING)
+    .bind(id)
+    .bind(STATUS_DEAD)
+    .execute(&db)
+    .await
+    .map_err(|e| db_error("Failed to replay deliveries", e))?;
+
+    let response = ApiResponse::success_with_data(
+        "Deliveries queued",
+        json!({ "replayed": res.rows_affected() }),
+    );
+    Ok((StatusCode::OK, Json(response)))
+}
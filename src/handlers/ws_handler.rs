@@ -0,0 +1,253 @@
+use axum::extract::Query;
+use axum::extract::ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade};
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json, http::StatusCode};
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use sqlx::MySqlPool;
+use std::time::Duration;
+use tokio::time::Instant;
+
+// Import JWT, notification registry and response helpers
+use crate::utils::jwt::{Claims, verify_jwt_token};
+use crate::utils::notifications::{
+    CLOSE_REVOKED, CLOSE_UNAUTHORIZED, NOTIFY_SESSION_REVOKED, Outgoing, message, register,
+    unregister,
+};
+use crate::utils::response::ApiResponse;
+use crate::utils::user_status::account_state;
+
+/// How often an open connection re-checks that the account may still sign in (catches
+/// revocations made through another instance).
+const STATUS_RECHECK: Duration = Duration::from_secs(30);
+
+fn env_u64(name: &str, default: u64) -> u64 {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+/// Time between server pings (`WS_PING_INTERVAL_SECS`, default 30). A client silent for two
+/// intervals is disconnected.
+fn ping_interval() -> Duration {
+    Duration::from_secs(env_u64("WS_PING_INTERVAL_SECS", 30).max(1))
+}
+
+/// Time a client has to send its `auth` message (`WS_AUTH_TIMEOUT_SECS`, default 10).
+fn auth_timeout() -> Duration {
+    Duration::from_secs(env_u64("WS_AUTH_TIMEOUT_SECS", 10).max(1))
+}
+
+#[derive(Deserialize)]
+pub struct WsParams {
+    /// JWT; when omitted the first message must be `{"type":"auth","token":"..."}`
+    pub token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ClientMessage {
+    #[serde(rename = "type")]
+    kind: String,
+    token: Option<String>,
+}
+
+/// Why a token cannot open a connection: close code (or HTTP status) and reason.
+struct AuthFailure {
+    code: u16,
+    reason: &'static str,
+}
+
+impl AuthFailure {
+    fn status(&self) -> (StatusCode, &'static str) {
+        match self.code {
+            CLOSE_UNAUTHORIZED => (StatusCode::UNAUTHORIZED, "Unauthorized"),
+            CLOSE_REVOKED => (StatusCode::FORBIDDEN, "Forbidden"),
+            _ => (StatusCode::INTERNAL_SERVER_ERROR, "Server error"),
+        }
+    }
+}
+
+/// Verify a token and the account status, as `auth_middleware` does for HTTP requests.
+async fn authenticate(db: &MySqlPool, token: &str) -> Result<Claims, AuthFailure> {
+    let claims = verify_jwt_token(token).await.map_err(|_| AuthFailure {
+        code: CLOSE_UNAUTHORIZED,
+        reason: "Invalid or expired token",
+    })?;
+    match account_state(db, claims.sub).await {
+        Ok(Some(status)) => match status.login_error() {
+            None => Ok(claims),
+            Some(reason) => Err(AuthFailure {
+                code: CLOSE_REVOKED,
+                reason,
+            }),
+        },
+        Ok(None) => Err(AuthFailure {
+            code: CLOSE_UNAUTHORIZED,
+            reason: "Account no longer exists",
+        }),
+        Err(e) => {
+            tracing::error!("ws: failed to load account status: {}", e);
+            Err(AuthFailure {
+                code: 1011,
+                reason: "Failed to load account status",
+            })
+        }
+    }
+}
+
+fn text(value: Value) -> Message {
+    Message::Text(value.to_string().into())
+}
+
+fn close(code: u16, reason: &str) -> Message {
+    Message::Close(Some(CloseFrame {
+        code,
+        reason: reason.into(),
+    }))
+}
+
+/// `GET /ws` — WebSocket channel for notifications to the signed-in user. The JWT comes in
+/// the `token` query parameter (checked before the upgrade) or in the first message.
+pub async fn ws_handler(
+    ws: WebSocketUpgrade,
+    Extension(db): Extension<MySqlPool>,
+    Query(params): Query<WsParams>,
+) -> Response {
+    let claims = match params.token {
+        Some(token) => match authenticate(&db, &token).await {
+            Ok(claims) => Some(claims),
+            Err(failure) => {
+                let (status, message) = failure.status();
+                let response =
+                    ApiResponse::error_with_data(message, json!({ "error": failure.reason }));
+                return (status, Json(response)).into_response();
+            }
+        },
+        None => None,
+    };
+    ws.on_upgrade(move |socket| handle_socket(socket, db, claims))
+}
+
+/// Wait for the `auth` message; closes the socket and returns `None` when it does not come
+/// or its token is rejected.
+async fn authenticate_first_message(socket: &mut WebSocket, db: &MySqlPool) -> Option<Claims> {
+    let token = match tokio::time::timeout(auth_timeout(), socket.recv()).await {
+        Ok(Some(Ok(Message::Text(raw)))) => serde_json::from_str::<ClientMessage>(&raw)
+            .ok()
+            .filter(|m| m.kind == "auth")
+            .and_then(|m| m.token),
+        _ => None,
+    };
+    let result = match token {
+        Some(token) => authenticate(db, &token).await,
+        None => Err(AuthFailure {
+            code: CLOSE_UNAUTHORIZED,
+            reason: "Expected an auth message with a token",
+        }),
+    };
+    match result {
+        Ok(claims) => Some(claims),
+        Err(failure) => {
+            let _ = socket.send(close(failure.code, failure.reason)).await;
+            None
+        }
+    }
+}
+
+async fn handle_socket(mut socket: WebSocket, db: MySqlPool, claims: Option<Claims>) {
+    let claims = match claims {
+        Some(claims) => claims,
+        None => match authenticate_first_message(&mut socket, &db).await {
+            Some(claims) => claims,
+            None => return,
+        },
+    };
+    let user_id = claims.sub;
+    let (connection_id, mut queue) = register(user_id);
+    tracing::debug!("ws: user_id={} connected ({})", user_id, connection_id);
+
+    let (mut sender, mut receiver) = socket.split();
+    let ready = message(
+        "ready",
+        json!({ "user_id": user_id, "expires_at": claims.exp }),
+    );
+    if sender.send(text(ready)).await.is_err() {
+        unregister(user_id, connection_id);
+        return;
+    }
+
+    // The connection ends when the token does
+    let remaining = (claims.exp as i64 - chrono::Utc::now().timestamp()).max(0) as u64;
+    let expiry = tokio::time::sleep(Duration::from_secs(remaining));
+    tokio::pin!(expiry);
+    let ping_every = ping_interval();
+    let mut ping = tokio::time::interval_at(Instant::now() + ping_every, ping_every);
+    let mut status_check =
+        tokio::time::interval_at(Instant::now() + STATUS_RECHECK, STATUS_RECHECK);
+    let mut last_seen = Instant::now();
+
+    loop {
+        tokio::select! {
+            _ = &mut expiry => {
+                let _ = sender.send(close(CLOSE_UNAUTHORIZED, "Token expired")).await;
+                break;
+            }
+            _ = ping.tick() => {
+                if last_seen.elapsed() > ping_every * 2 {
+                    tracing::debug!("ws: user_id={} missed heartbeats", user_id);
+                    break;
+                }
+                if sender.send(Message::Ping(Default::default())).await.is_err() {
+                    break;
+                }
+            }
+            _ = status_check.tick() => {
+                if let Ok(status) = account_state(&db, user_id).await {
+                    let reason = match status {
+                        Some(status) => status.login_error(),
+                        None => Some("Account no longer exists"),
+                    };
+                    if let Some(reason) = reason {
+                        let revoked = message(NOTIFY_SESSION_REVOKED, json!({ "reason": reason }));
+                        let _ = sender.send(text(revoked)).await;
+                        let _ = sender.send(close(CLOSE_REVOKED, reason)).await;
+                        break;
+                    }
+                }
+            }
+            outgoing = queue.recv() => match outgoing {
+                Some(Outgoing::Message(value)) => {
+                    if sender.send(text(value)).await.is_err() {
+                        break;
+                    }
+                }
+                Some(Outgoing::Close { code, reason }) => {
+                    let _ = sender.send(close(code, &reason)).await;
+                    break;
+                }
+                None => break,
+            },
+            incoming = receiver.next() => {
+                last_seen = Instant::now();
+                match incoming {
+                    Some(Ok(Message::Text(raw))) => {
+                        // Application-level ping for clients that cannot see protocol pings
+                        let kind = serde_json::from_str::<ClientMessage>(&raw).ok().map(|m| m.kind);
+                        if kind.as_deref() == Some("ping")
+                            && sender.send(text(json!({ "type": "pong" }))).await.is_err()
+                        {
+                            break;
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => {}
+                }
+            }
+        }
+    }
+
+    unregister(user_id, connection_id);
+    tracing::debug!("ws: user_id={} disconnected ({})", user_id, connection_id);
+}
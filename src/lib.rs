@@ -1,9 +1,12 @@
 pub mod config;
+pub mod grpc;
 pub mod handlers;
 pub mod middlewares;
 pub mod models;
 pub mod routes;
 pub mod schemas;
+pub mod storage;
+pub mod tasks;
 pub mod utils;
 
 pub mod app;
@@ -3,6 +3,7 @@ use std::net::SocketAddr;
 
 use backend_api_jwt::config;
 use backend_api_jwt::create_app;
+use backend_api_jwt::grpc::server as grpc;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
@@ -19,11 +20,62 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     // Initialize tracing for structured logs
     tracing_subscriber::fmt::init();
 
+    // Load permission policies (POLICY_FILE); fail fast on invalid policy files
+    config::policy::load_policies()?;
+
+    // Load federated login providers (OIDC_PROVIDERS_FILE); fail fast on invalid files
+    config::oidc::load_providers()?;
+
+    // Load the custom user attribute schema (USER_ATTRIBUTES_FILE); fail fast on invalid files
+    config::user_attributes::load_user_attributes()?;
+
+    // Configure blob storage for uploads (BLOB_STORE); fail fast on incomplete S3 settings
+    backend_api_jwt::storage::blob_store()?;
+
     // Establish database connection (and run migrations)
     let db_pool = config::database::establish_connection().await?;
 
+    // Hard-delete users whose soft-delete retention period has passed
+    backend_api_jwt::tasks::user_purge::spawn(db_pool.clone());
+
+    // Run data export / erasure requests and remove expired export archives
+    backend_api_jwt::tasks::user_data_requests::spawn(db_pool.clone());
+
+    // Remove stored Idempotency-Key responses past their TTL
+    backend_api_jwt::tasks::idempotency_purge::spawn(db_pool.clone());
+
+    // Rebuild the full-text search text of custom attributes for the configured schema
+    backend_api_jwt::tasks::search_reindex::spawn(db_pool.clone());
+
+    // Deliver outbox events to webhook subscribers and purge old events
+    backend_api_jwt::tasks::webhook_delivery::spawn(db_pool.clone());
+
     // Create the application router using library helper (CORS will be configured there based on env)
-    let app = create_app(db_pool.clone());
+    let mut app = create_app(db_pool.clone());
+
+    // gRPC services (GRPC_ENABLED): on their own port with GRPC_PORT, otherwise multiplexed
+    // with the REST API on the HTTP listener (told apart by their service paths)
+    if grpc::grpc_enabled() {
+        let grpc_app = grpc::grpc_router(db_pool.clone());
+        match grpc::grpc_port() {
+            Some(grpc_port) => {
+                let host = std::env::var("APP_HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
+                let grpc_addr: SocketAddr = format!("{}:{}", host, grpc_port)
+                    .parse()
+                    .expect("APP_HOST:GRPC_PORT must form a valid socket address");
+                let grpc_listener = tokio::net::TcpListener::bind(grpc_addr)
+                    .await
+                    .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?;
+                tracing::info!("gRPC listening on http://{}", grpc_addr);
+                tokio::spawn(async move {
+                    if let Err(e) = axum::serve(grpc_listener, grpc_app.into_make_service()).await {
+                        tracing::error!("gRPC server stopped: {}", e);
+                    }
+                });
+            }
+            None => app = app.merge(grpc_app),
+        }
+    }
 
     // PORT from environment variable or default to 3000
     let port: u16 = std::env::var("APP_PORT")
@@ -0,0 +1,64 @@
+use axum::{Json, extract::Request, http::StatusCode, middleware::Next, response::Response};
+use sqlx::MySqlPool;
+
+use crate::models::user::ROLE_ADMIN;
+use crate::utils::jwt::Claims;
+use crate::utils::response::ApiResponse;
+
+// Type error response alias
+type AdminErrorResponse = (StatusCode, Json<ApiResponse<serde_json::Value>>);
+
+/// Whether `user_id` is a live (not soft-deleted) admin.
+pub async fn is_admin(db: &MySqlPool, user_id: i64) -> Result<bool, sqlx::Error> {
+    let role: Option<String> =
+        sqlx::query_scalar("SELECT role FROM users WHERE id = ? AND deleted_at IS NULL")
+            .bind(user_id)
+            .fetch_optional(db)
+            .await?;
+    Ok(role.as_deref() == Some(ROLE_ADMIN))
+}
+
+// Admin middleware: must run after `auth_middleware` so `Claims` are available.
+// The role is read from the database on every request so demotions take effect immediately.
+pub async fn admin_middleware(req: Request, next: Next) -> Result<Response, AdminErrorResponse> {
+    let claims = match req.extensions().get::<Claims>() {
+        Some(c) => c.clone(),
+        None => {
+            let response = ApiResponse::error_with_data(
+                "Unauthorized",
+                serde_json::json!({ "error": "Missing authentication" }),
+            );
+            return Err((StatusCode::UNAUTHORIZED, Json(response)));
+        }
+    };
+
+    let db = match req.extensions().get::<MySqlPool>() {
+        Some(p) => p.clone(),
+        None => {
+            let response = ApiResponse::error_with_data(
+                "Server error",
+                serde_json::json!({ "error": "Database pool not configured" }),
+            );
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, Json(response)));
+        }
+    };
+
+    let admin = is_admin(&db, claims.sub).await.map_err(|e| {
+        let response = ApiResponse::error_with_data(
+            "Database error",
+            serde_json::json!({ "error": "Failed to load user role", "details": e.to_string() }),
+        );
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
+    })?;
+
+    if !admin {
+        tracing::warn!("admin_middleware: forbidden for user_id={}", claims.sub);
+        let response = ApiResponse::error_with_data(
+            "Forbidden",
+            serde_json::json!({ "error": "Admin privileges required" }),
+        );
+        return Err((StatusCode::FORBIDDEN, Json(response)));
+    }
+
+    Ok(next.run(req).await)
+}
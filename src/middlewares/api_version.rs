@@ -0,0 +1,245 @@
+use axum::{
+    Router,
+    body::{Body, to_bytes},
+    extract::{Request, State},
+    http::{HeaderValue, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use once_cell::sync::Lazy;
+use serde_json::{Map, Value, json};
+use tower::ServiceExt;
+
+/// Media type selecting the v2 API on unversioned paths (and returned by v2 responses).
+pub const V2_MEDIA_TYPE: &str = "application/vnd.backend.v2+json";
+/// Media type selecting the v1 API on unversioned paths.
+pub const V1_MEDIA_TYPE: &str = "application/vnd.backend.v1+json";
+
+/// Default `API_UNVERSIONED_DEPRECATED_AT`: the release that introduced `/v1`.
+const DEFAULT_DEPRECATED_AT: &str = "2026-10-18";
+/// Default `API_UNVERSIONED_SUNSET`: six months after the deprecation.
+const DEFAULT_SUNSET: &str = "2027-04-18";
+
+/// API version requested through the `Accept` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiVersion {
+    V1,
+    V2,
+}
+
+/// Version named by a vendor media type in `Accept`, ignoring parameters and quality values.
+pub fn accepted_version(accept: &str) -> Option<ApiVersion> {
+    accept
+        .split(',')
+        .filter_map(|range| range.split(';').next())
+        .find_map(|media| match media.trim().to_ascii_lowercase().as_str() {
+            V2_MEDIA_TYPE => Some(ApiVersion::V2),
+            V1_MEDIA_TYPE => Some(ApiVersion::V1),
+            _ => None,
+        })
+}
+
+/// A date (`2027-04-18`, midnight UTC) or RFC 3339 timestamp.
+fn parse_instant(raw: &str) -> Option<DateTime<Utc>> {
+    let raw = raw.trim();
+    DateTime::parse_from_rfc3339(raw)
+        .map(|t| t.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(raw, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+                .map(|t| t.and_utc())
+        })
+}
+
+fn env_instant(name: &str, default: &str) -> DateTime<Utc> {
+    let parsed = std::env::var(name).ok().and_then(|raw| {
+        let t = parse_instant(&raw);
+        if t.is_none() {
+            tracing::warn!("{}: invalid date {:?}, using {}", name, raw, default);
+        }
+        t
+    });
+    parsed.unwrap_or_else(|| parse_instant(default).expect("valid default date"))
+}
+
+/// `Deprecation` value (RFC 9745): a structured field date, `@<unix seconds>`.
+pub fn deprecation_value(at: DateTime<Utc>) -> String {
+    format!("@{}", at.timestamp())
+}
+
+/// `Sunset` value (RFC 8594): an HTTP-date.
+pub fn sunset_value(at: DateTime<Utc>) -> String {
+    at.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+/// `Deprecation` and `Sunset` headers of the unversioned aliases, read once.
+static LEGACY_HEADERS: Lazy<(HeaderValue, HeaderValue)> = Lazy::new(|| {
+    let deprecated_at = env_instant("API_UNVERSIONED_DEPRECATED_AT", DEFAULT_DEPRECATED_AT);
+    let sunset = env_instant("API_UNVERSIONED_SUNSET", DEFAULT_SUNSET);
+    (
+        HeaderValue::from_str(&deprecation_value(deprecated_at)).expect("ascii header"),
+        HeaderValue::from_str(&sunset_value(sunset)).expect("ascii header"),
+    )
+});
+
+/// Unversioned aliases of the v1 routes.
+///
+/// Requests asking for `application/vnd.backend.v2+json` are handed to the v2 router (given
+/// as state, with routes at the same unprefixed paths). Requests without a version in the
+/// path or `Accept` header are served by v1 and marked deprecated: `Deprecation`, `Sunset`
+/// and a `Link` to the `/v1` successor.
+pub async fn unversioned_alias(State(v2): State<Router>, req: Request, next: Next) -> Response {
+    let requested = req
+        .headers()
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .and_then(accepted_version);
+    let successor = format!(
+        "</v1{}>; rel=\"successor-version\"",
+        req.uri().path_and_query().map_or("", |p| p.as_str())
+    );
+
+    let mut res = match requested {
+        Some(ApiVersion::V2) => match v2.oneshot(req).await {
+            Ok(res) => res,
+            Err(never) => match never {},
+        },
+        Some(ApiVersion::V1) => next.run(req).await,
+        None => {
+            let mut res = next.run(req).await;
+            let (deprecation, sunset) = LEGACY_HEADERS.clone();
+            let headers = res.headers_mut();
+            headers.insert("deprecation", deprecation);
+            headers.insert("sunset", sunset);
+            if let Ok(link) = HeaderValue::from_str(&successor) {
+                headers.append(header::LINK, link);
+            }
+            res
+        }
+    };
+    res.headers_mut()
+        .append(header::VARY, HeaderValue::from_static("Accept"));
+    res
+}
+
+/// Rewrite an `ApiResponse` envelope into its v2 form: the `data` itself on success, and an
+/// RFC 9457 problem document (with the error data as extension members) on failure.
+/// `None` when `body` is not an envelope.
+pub fn v2_body(status: StatusCode, body: &[u8]) -> Option<(Value, &'static str)> {
+    let Ok(Value::Object(mut envelope)) = serde_json::from_slice::<Value>(body) else {
+        return None;
+    };
+    let success = envelope.get("success")?.as_bool()?;
+    let message = envelope.remove("message")?;
+    let data = envelope.remove("data").unwrap_or(Value::Null);
+    if success {
+        return Some((data, V2_MEDIA_TYPE));
+    }
+
+    let mut problem = Map::new();
+    problem.insert("type".to_string(), json!("about:blank"));
+    problem.insert(
+        "title".to_string(),
+        json!(status.canonical_reason().unwrap_or("Error")),
+    );
+    problem.insert("status".to_string(), json!(status.as_u16()));
+    problem.insert("detail".to_string(), message);
+    match data {
+        Value::Object(members) => {
+            for (key, value) in members {
+                problem.entry(key).or_insert(value);
+            }
+        }
+        Value::Null => {}
+        other => {
+            problem.insert("data".to_string(), other);
+        }
+    }
+    Some((Value::Object(problem), "application/problem+json"))
+}
+
+/// Response shapes of the v2 API (see [`v2_body`]). Non-JSON responses (files, streams) and
+/// JSON bodies that are not envelopes pass through unchanged.
+pub async fn v2_responses(req: Request, next: Next) -> Response {
+    let res = next.run(req).await;
+    let is_json = res
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    if !is_json {
+        return res;
+    }
+
+    let (mut parts, body) = res.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::error!("v2: failed to read response body: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let Some((value, content_type)) = v2_body(parts.status, &bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts
+        .headers
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    Response::from_parts(parts, Body::from(value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accepted_version() {
+        assert_eq!(accepted_version(V2_MEDIA_TYPE), Some(ApiVersion::V2));
+        assert_eq!(
+            accepted_version("text/html, Application/Vnd.Backend.V1+JSON;q=0.9"),
+            Some(ApiVersion::V1)
+        );
+        assert_eq!(accepted_version("application/json, */*"), None);
+        assert_eq!(accepted_version(""), None);
+    }
+
+    #[test]
+    fn test_header_values() {
+        let at = parse_instant("2027-04-18").unwrap();
+        assert_eq!(sunset_value(at), "Sun, 18 Apr 2027 00:00:00 GMT");
+        assert_eq!(deprecation_value(at), "@1808006400");
+        assert_eq!(parse_instant("2027-04-18T12:00:00+02:00").unwrap(), {
+            parse_instant("2027-04-18").unwrap() + chrono::Duration::hours(10)
+        });
+        assert!(parse_instant("next year").is_none());
+    }
+
+    #[test]
+    fn test_v2_body() {
+        let ok = br#"{"success":true,"message":"User found","data":{"id":7}}"#;
+        let (value, content_type) = v2_body(StatusCode::OK, ok).unwrap();
+        assert_eq!(value, json!({ "id": 7 }));
+        assert_eq!(content_type, V2_MEDIA_TYPE);
+
+        let err = br#"{"success":false,"message":"Validation error","data":{"errors":{"email":["taken"]},"status":1}}"#;
+        let (value, content_type) = v2_body(StatusCode::UNPROCESSABLE_ENTITY, err).unwrap();
+        assert_eq!(content_type, "application/problem+json");
+        assert_eq!(
+            value,
+            json!({
+                "type": "about:blank",
+                "title": "Unprocessable Entity",
+                "status": 422,
+                "detail": "Validation error",
+                "errors": { "email": ["taken"] },
+            })
+        );
+
+        assert!(v2_body(StatusCode::OK, br#"{"id":7}"#).is_none());
+        assert!(v2_body(StatusCode::OK, b"[1,2]").is_none());
+    }
+}
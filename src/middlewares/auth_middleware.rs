@@ -1,7 +1,9 @@
 use axum::{Json, extract::Request, http::StatusCode, middleware::Next, response::Response};
+use sqlx::MySqlPool;
 
 use crate::utils::jwt::verify_jwt_token;
 use crate::utils::response::ApiResponse;
+use crate::utils::user_status::account_state;
 
 // Type error response alias
 type AuthErrorResponse = (StatusCode, Json<ApiResponse<serde_json::Value>>);
@@ -34,19 +36,57 @@ pub async fn auth_middleware(mut req: Request, next: Next) -> Result<Response, A
     let token = auth_header.trim_start_matches("Bearer ").trim();
 
     // Verify the JWT token
-    match verify_jwt_token(token).await {
-        Ok(claims) => {
-            // You can attach claims to request extensions if needed
-            req.extensions_mut().insert(claims);
-            // Proceed to the next middleware/handler
-            Ok(next.run(req).await)
-        }
+    let claims = match verify_jwt_token(token).await {
+        Ok(claims) => claims,
         Err(e) => {
             let response = ApiResponse::error_with_data(
                 "Unauthorized",
                 serde_json::json!({ "error": "Invalid or expired token", "details": e.to_string() }),
             );
-            Err((StatusCode::UNAUTHORIZED, Json(response)))
+            return Err((StatusCode::UNAUTHORIZED, Json(response)));
         }
+    };
+
+    // The account status is read on every request so suspensions and bans revoke
+    // tokens that were issued before them
+    let db = match req.extensions().get::<MySqlPool>() {
+        Some(p) => p.clone(),
+        None => {
+            let response = ApiResponse::error_with_data(
+                "Server error",
+                serde_json::json!({ "error": "Database pool not configured" }),
+            );
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, Json(response)));
+        }
+    };
+    let status = account_state(&db, claims.sub).await.map_err(|e| {
+        let response = ApiResponse::error_with_data(
+            "Database error",
+            serde_json::json!({ "error": "Failed to load account status", "details": e.to_string() }),
+        );
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
+    })?;
+    // Deleted (or erased) accounts keep no access through tokens issued earlier
+    let Some(status) = status else {
+        let response = ApiResponse::error_with_data(
+            "Unauthorized",
+            serde_json::json!({ "error": "Account no longer exists" }),
+        );
+        return Err((StatusCode::UNAUTHORIZED, Json(response)));
+    };
+    if let Some(error) = status.login_error() {
+        tracing::warn!(
+            "auth_middleware: rejected user_id={}: {}",
+            claims.sub,
+            error
+        );
+        let response =
+            ApiResponse::error_with_data("Forbidden", serde_json::json!({ "error": error }));
+        return Err((StatusCode::FORBIDDEN, Json(response)));
     }
+
+    // You can attach claims to request extensions if needed
+    req.extensions_mut().insert(claims);
+    // Proceed to the next middleware/handler
+    Ok(next.run(req).await)
 }
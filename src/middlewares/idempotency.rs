@@ -0,0 +1,396 @@
+use axum::body::{Body, Bytes, to_bytes};
+use axum::http::{HeaderMap, HeaderName, HeaderValue, Method, header};
+use axum::{
+    Json,
+    extract::Request,
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+use sqlx::MySqlPool;
+use sqlx::types::Json as SqlJson;
+use std::net::IpAddr;
+
+use crate::middlewares::proxy::ClientIp;
+use crate::utils::response::ApiResponse;
+use crate::utils::token::hash_token;
+
+// Type error response alias
+type IdempotencyErrorResponse = (StatusCode, Json<ApiResponse<serde_json::Value>>);
+
+/// Request header carrying the client-chosen key.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+/// Set to `true` on responses replayed from storage.
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// An in-flight request older than this is assumed lost (e.g. the instance stopped) and a retry
+/// may take over its key.
+const LOCK_TIMEOUT_SECS: i64 = 60;
+
+/// Response headers that are not stored: recomputed on replay or tied to the connection.
+const SKIPPED_HEADERS: [&str; 4] = ["content-length", "transfer-encoding", "connection", "date"];
+
+/// Hours a key and its response are kept (`IDEMPOTENCY_TTL_HOURS`, default 24).
+fn ttl_hours() -> u32 {
+    std::env::var("IDEMPOTENCY_TTL_HOURS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(24)
+}
+
+/// Largest request body accepted with a key (`IDEMPOTENCY_MAX_BODY_BYTES`, default 1 MiB).
+fn max_body_bytes() -> usize {
+    std::env::var("IDEMPOTENCY_MAX_BODY_BYTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(1024 * 1024)
+}
+
+fn error(status: StatusCode, message: &str, error: &str) -> IdempotencyErrorResponse {
+    let response = ApiResponse::error_with_data(message, serde_json::json!({ "error": error }));
+    (status, Json(response))
+}
+
+fn db_error(e: sqlx::Error) -> IdempotencyErrorResponse {
+    let response = ApiResponse::error_with_data(
+        "Database error",
+        serde_json::json!({ "error": "Failed to process Idempotency-Key", "details": e.to_string() }),
+    );
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
+}
+
+/// Keys are opaque: 1 to 255 visible ASCII characters (a UUID is recommended).
+pub fn valid_key(key: &str) -> bool {
+    !key.is_empty() && key.len() <= 255 && key.bytes().all(|b| b.is_ascii_graphic())
+}
+
+/// SHA-256 fingerprint of a request: method, path with query string and body.
+pub fn request_fingerprint(method: &Method, path_and_query: &str, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_str().as_bytes());
+    hasher.update(b" ");
+    hasher.update(path_and_query.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(body);
+    hex::encode(hasher.finalize())
+}
+
+/// Who a key belongs to: the credentials sent with the request, or the client IP for
+/// anonymous requests (e.g. `POST /register`). Only a digest is stored.
+pub fn caller_scope(headers: &HeaderMap, client_ip: Option<IpAddr>) -> String {
+    match headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+    {
+        Some(auth) => hash_token(&format!("auth:{}", auth.trim())),
+        None => hash_token(&format!(
+            "ip:{}",
+            client_ip.map(|ip| ip.to_string()).unwrap_or_default()
+        )),
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct StoredKey {
+    request_hash: String,
+    response_status: Option<i16>,
+    response_headers: Option<SqlJson<Vec<(String, String)>>>,
+    response_body: Option<Vec<u8>>,
+    stale: bool,
+}
+
+/// Honor `Idempotency-Key` on `POST` and `PATCH` requests.
+///
+/// Routes opt in by layering this on their method router (`POST /register`, `POST /users`).
+/// It buffers the request and stores the whole response, so it is not meant for streaming or
+/// multipart uploads, nor for routes whose responses carry credentials (`/login`,
+/// `/invitations/accept`, the OIDC callback): those would sit in `idempotency_keys`.
+///
+/// The first request with a key runs normally and its response (status, headers, body) is
+/// stored; retries with the same key and request get the stored response with
+/// `Idempotent-Replayed: true`. Reusing a key for a different request returns `422`, and a retry
+/// while the first request is still running returns `409`. `5xx` responses are not stored, so
+/// the key can be retried. Requests without the header are passed through untouched.
+pub async fn idempotency_middleware(
+    req: Request,
+    next: Next,
+) -> Result<Response, IdempotencyErrorResponse> {
+    if !matches!(*req.method(), Method::POST | Method::PATCH) {
+        return Ok(next.run(req).await);
+    }
+    let Some(raw_key) = req.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(next.run(req).await);
+    };
+    let key = raw_key
+        .to_str()
+        .ok()
+        .map(str::trim)
+        .filter(|k| valid_key(k))
+        .ok_or_else(|| {
+            error(
+                StatusCode::BAD_REQUEST,
+                "Bad Request",
+                "Idempotency-Key must be 1 to 255 visible ASCII characters",
+            )
+        })?
+        .to_string();
+    let db = req
+        .extensions()
+        .get::<MySqlPool>()
+        .cloned()
+        .ok_or_else(|| {
+            error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Server error",
+                "Database pool not configured",
+            )
+        })?;
+    let scope = caller_scope(
+        req.headers(),
+        req.extensions().get::<ClientIp>().map(|c| c.0),
+    );
+
+    let (parts, body) = req.into_parts();
+    let body = to_bytes(body, max_body_bytes()).await.map_err(|_| {
+        error(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "Payload Too Large",
+            "Request body is too large to be used with an Idempotency-Key",
+        )
+    })?;
+    let path = parts
+        .uri
+        .path_and_query()
+        .map(|p| p.as_str())
+        .unwrap_or("/");
+    let fingerprint = request_fingerprint(&parts.method, path, &body);
+
+    if let Some(replay) = claim_key(&db, &scope, &key, &fingerprint).await? {
+        return Ok(replay);
+    }
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+    let (parts, body) = response.into_parts();
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(e) => {
+            release_key(&db, &scope, &key).await;
+            tracing::error!("idempotency: failed to read response body: {}", e);
+            return Err(error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Server error",
+                "Failed to read response",
+            ));
+        }
+    };
+
+    if parts.status.is_server_error() {
+        // Nothing reliable to replay; let the client retry for real
+        release_key(&db, &scope, &key).await;
+    } else if let Err(e) =
+        store_response(&db, &scope, &key, parts.status, &parts.headers, &body).await
+    {
+        tracing::error!("idempotency: failed to store response: {}", e);
+        release_key(&db, &scope, &key).await;
+    }
+    Ok(Response::from_parts(parts, Body::from(body)))
+}
+
+/// Reserve the key for this request (`None`), or answer from a previous request with it.
+async fn claim_key(
+    db: &MySqlPool,
+    scope: &str,
+    key: &str,
+    fingerprint: &str,
+) -> Result<Option<Response>, IdempotencyErrorResponse> {
+    // A key whose TTL has passed may be used for a new request
+    sqlx::query(
+        "DELETE FROM idempotency_keys WHERE scope = ? AND idempotency_key = ? AND expires_at <= NOW()",
+    )
+    .bind(scope)
+    .bind(key)
+    .execute(db)
+    .await
+    .map_err(db_error)?;
+
+    let inserted = sqlx::query(
+        r#"
+        INSERT INTO idempotency_keys (scope, idempotency_key, request_hash, locked_at, expires_at)
+        VALUES (?, ?, ?, NOW(), NOW() + INTERVAL ? HOUR)
+        "#,
+    )
+    .bind(scope)
+    .bind(key)
+    .bind(fingerprint)
+    .bind(ttl_hours())
+    .execute(db)
+    .await;
+    match inserted {
+        Ok(_) => return Ok(None),
+        Err(sqlx::Error::Database(db_err)) if db_err.code().as_deref() == Some("1062") => {}
+        Err(e) => return Err(db_error(e)),
+    }
+
+    let in_progress = || {
+        error(
+            StatusCode::CONFLICT,
+            "Conflict",
+            "A request with this Idempotency-Key is already in progress",
+        )
+    };
+    let stored = sqlx::query_as::<_, StoredKey>(
+        r#"
+        SELECT request_hash, response_status, response_headers, response_body,
+               IFNULL(locked_at < NOW() - INTERVAL ? SECOND, 0) AS stale
+        FROM idempotency_keys
+        WHERE scope = ? AND idempotency_key = ?
+        "#,
+    )
+    .bind(LOCK_TIMEOUT_SECS)
+    .bind(scope)
+    .bind(key)
+    .fetch_optional(db)
+    .await
+    .map_err(db_error)?
+    // Released by a failed first attempt in the meantime
+    .ok_or_else(in_progress)?;
+
+    if stored.request_hash != fingerprint {
+        return Err(error(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "Unprocessable Entity",
+            "Idempotency-Key was already used with a different request",
+        ));
+    }
+    if let Some(status) = stored.response_status {
+        return Ok(Some(replay(
+            status,
+            stored.response_headers.map(|h| h.0).unwrap_or_default(),
+            stored.response_body.unwrap_or_default(),
+        )));
+    }
+    if stored.stale {
+        let res = sqlx::query(
+            r#"
+            UPDATE idempotency_keys SET locked_at = NOW()
+            WHERE scope = ? AND idempotency_key = ? AND response_status IS NULL
+              AND locked_at < NOW() - INTERVAL ? SECOND
+            "#,
+        )
+        .bind(scope)
+        .bind(key)
+        .bind(LOCK_TIMEOUT_SECS)
+        .execute(db)
+        .await
+        .map_err(db_error)?;
+        if res.rows_affected() == 1 {
+            return Ok(None);
+        }
+    }
+    Err(in_progress())
+}
+
+fn replay(status: i16, headers: Vec<(String, String)>, body: Vec<u8>) -> Response {
+    let status = u16::try_from(status)
+        .ok()
+        .and_then(|s| StatusCode::from_u16(s).ok())
+        .unwrap_or(StatusCode::OK);
+    let mut response_headers = HeaderMap::new();
+    for (name, value) in headers {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(&value),
+        ) {
+            response_headers.append(name, value);
+        }
+    }
+    response_headers.insert(
+        HeaderName::from_static(IDEMPOTENT_REPLAYED_HEADER),
+        HeaderValue::from_static("true"),
+    );
+    (status, response_headers, Bytes::from(body)).into_response()
+}
+
+async fn store_response(
+    db: &MySqlPool,
+    scope: &str,
+    key: &str,
+    status: StatusCode,
+    headers: &HeaderMap,
+    body: &[u8],
+) -> Result<(), sqlx::Error> {
+    let headers: Vec<(String, String)> = headers
+        .iter()
+        .filter(|(name, _)| !SKIPPED_HEADERS.contains(&name.as_str()))
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect();
+    sqlx::query(
+        r#"
+        UPDATE idempotency_keys
+        SET response_status = ?, response_headers = ?, response_body = ?, locked_at = NULL
+        WHERE scope = ? AND idempotency_key = ?
+        "#,
+    )
+    .bind(status.as_u16() as i16)
+    .bind(SqlJson(headers))
+    .bind(body)
+    .bind(scope)
+    .bind(key)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Forget an in-flight key so the request can be retried.
+async fn release_key(db: &MySqlPool, scope: &str, key: &str) {
+    let res = sqlx::query(
+        "DELETE FROM idempotency_keys WHERE scope = ? AND idempotency_key = ? AND response_status IS NULL",
+    )
+    .bind(scope)
+    .bind(key)
+    .execute(db)
+    .await;
+    if let Err(e) = res {
+        tracing::error!("idempotency: failed to release key: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_and_fingerprints() {
+        assert!(valid_key("3f9c1d2e-6b1a-4c55-9d0e-1f2a3b4c5d6e"));
+        assert!(!valid_key(""));
+        assert!(!valid_key("has space"));
+        assert!(!valid_key(&"k".repeat(256)));
+
+        let a = request_fingerprint(&Method::POST, "/users", b"{\"name\":\"a\"}");
+        assert_eq!(
+            a,
+            request_fingerprint(&Method::POST, "/users", b"{\"name\":\"a\"}")
+        );
+        assert_ne!(
+            a,
+            request_fingerprint(&Method::POST, "/users", b"{\"name\":\"b\"}")
+        );
+        assert_ne!(
+            a,
+            request_fingerprint(&Method::POST, "/register", b"{\"name\":\"a\"}")
+        );
+
+        let mut headers = HeaderMap::new();
+        let anonymous = caller_scope(&headers, Some("10.0.0.1".parse().unwrap()));
+        assert_ne!(
+            anonymous,
+            caller_scope(&headers, Some("10.0.0.2".parse().unwrap()))
+        );
+        headers.insert(header::AUTHORIZATION, HeaderValue::from_static("Bearer a"));
+        assert_ne!(
+            anonymous,
+            caller_scope(&headers, Some("10.0.0.1".parse().unwrap()))
+        );
+    }
+}
@@ -1,3 +1,8 @@
+pub mod admin_middleware;
+pub mod api_version;
 pub mod auth_middleware;
+pub mod idempotency;
+pub mod policy_guard;
 pub mod proxy;
 pub mod rate_limiter;
+pub mod scim_auth;
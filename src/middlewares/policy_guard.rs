@@ -0,0 +1,122 @@
+use axum::{Json, extract::FromRequestParts, http::StatusCode, http::request::Parts};
+use serde_json::{Map, Value, json};
+use sqlx::MySqlPool;
+use sqlx::types::Json as SqlJson;
+
+use crate::utils::jwt::Claims;
+use crate::utils::policy::{Decision, current_policies};
+use crate::utils::response::ApiResponse;
+
+// Type error response alias
+type PolicyErrorResponse = (StatusCode, Json<ApiResponse<Value>>);
+
+/// Attribute map of a user for policy evaluation: the custom attributes (e.g. `region`),
+/// overlaid with the built-in `fields` so those cannot be shadowed.
+pub fn user_attributes(custom: Option<&Value>, fields: Value) -> Value {
+    let mut attrs: Map<String, Value> = custom
+        .and_then(Value::as_object)
+        .cloned()
+        .unwrap_or_default();
+    if let Value::Object(fields) = fields {
+        attrs.extend(fields);
+    }
+    Value::Object(attrs)
+}
+
+/// Load the principal attribute map for policy evaluation.
+///
+/// Like `admin_middleware`, the role and custom attributes are read from the database on
+/// every request, so demotions and attribute changes apply to tokens issued earlier. Extra
+/// `attrs` carried by the token (e.g. OIDC `attribute_claims`) come first and are overridden
+/// by stored attributes; `id` and `role` always reflect the token subject. A subject that no
+/// longer exists gets no role.
+pub async fn load_principal(db: &MySqlPool, claims: &Claims) -> Result<Value, sqlx::Error> {
+    let row: Option<(String, Option<SqlJson<Value>>)> =
+        sqlx::query_as("SELECT role, attributes FROM users WHERE id = ? AND deleted_at IS NULL")
+            .bind(claims.sub)
+            .fetch_optional(db)
+            .await?;
+    let (role, attributes) = match row {
+        Some((role, attributes)) => (json!(role), attributes.map(|a| a.0)),
+        None => (Value::Null, None),
+    };
+    let mut principal = claims.attrs.clone();
+    if let Some(Value::Object(custom)) = attributes {
+        principal.extend(custom);
+    }
+    principal.insert("id".to_string(), json!(claims.sub));
+    if !role.is_null() {
+        principal.insert("role".to_string(), role);
+    }
+    Ok(Value::Object(principal))
+}
+
+/// Extractor/guard that evaluates the active policy set for the authenticated caller.
+///
+/// Requires `auth_middleware` to have inserted `Claims` (and the database pool extension).
+/// Handlers call [`Authorizer::authorize`] once they know the action and resource attributes.
+pub struct Authorizer {
+    pub principal: Value,
+}
+
+impl<S> FromRequestParts<S> for Authorizer
+where
+    S: Send + Sync,
+{
+    type Rejection = PolicyErrorResponse;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Some(claims) = parts.extensions.get::<Claims>() else {
+            let response = ApiResponse::error_with_data(
+                "Unauthorized",
+                json!({ "error": "Missing authentication" }),
+            );
+            return Err((StatusCode::UNAUTHORIZED, Json(response)));
+        };
+        let Some(db) = parts.extensions.get::<MySqlPool>() else {
+            let response = ApiResponse::error_with_data(
+                "Server error",
+                json!({ "error": "Database pool not configured" }),
+            );
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, Json(response)));
+        };
+        Self::load(db, claims).await
+    }
+}
+
+impl Authorizer {
+    /// Guard for the subject of `claims`, with its principal loaded from the database.
+    pub async fn load(db: &MySqlPool, claims: &Claims) -> Result<Self, PolicyErrorResponse> {
+        let principal = load_principal(db, claims).await.map_err(|e| {
+            let response = ApiResponse::error_with_data(
+                "Database error",
+                json!({ "error": "Failed to load permissions", "details": e.to_string() }),
+            );
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
+        })?;
+        Ok(Self { principal })
+    }
+
+    /// Evaluate `action` on `resource`; returns 403 with the decision reason when denied.
+    pub fn authorize(
+        &self,
+        action: &str,
+        resource: &Value,
+    ) -> Result<Decision, PolicyErrorResponse> {
+        let decision = current_policies().evaluate(&self.principal, action, resource);
+        if decision.allowed {
+            return Ok(decision);
+        }
+        tracing::warn!(
+            "policy: denied action={} principal={} reason={}",
+            action,
+            self.principal,
+            decision.reason
+        );
+        let response = ApiResponse::error_with_data(
+            "Forbidden",
+            json!({ "error": "Permission denied", "action": action, "reason": decision.reason }),
+        );
+        Err((StatusCode::FORBIDDEN, Json(response)))
+    }
+}
@@ -0,0 +1,40 @@
+use axum::{extract::Request, http::StatusCode, middleware::Next, response::Response};
+
+use crate::utils::scim::ScimError;
+use crate::utils::token::hash_token;
+
+/// Authenticate SCIM provisioning clients with the dedicated bearer token (`SCIM_TOKEN`).
+///
+/// User JWTs are not accepted here. When `SCIM_TOKEN` is unset, SCIM is disabled and every
+/// request is rejected.
+pub async fn scim_auth_middleware(req: Request, next: Next) -> Result<Response, ScimError> {
+    let expected = match std::env::var("SCIM_TOKEN") {
+        Ok(t) if !t.trim().is_empty() => t,
+        _ => {
+            return Err(ScimError::new(
+                StatusCode::UNAUTHORIZED,
+                None,
+                "SCIM provisioning is not configured",
+            ));
+        }
+    };
+
+    let provided = req
+        .headers()
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .map(str::trim)
+        .unwrap_or("");
+
+    // Compare digests so the comparison time does not depend on the secret's contents
+    if provided.is_empty() || hash_token(provided) != hash_token(expected.trim()) {
+        tracing::warn!("scim: rejected request with invalid bearer token");
+        return Err(ScimError::new(
+            StatusCode::UNAUTHORIZED,
+            None,
+            "Invalid or missing SCIM bearer token",
+        ));
+    }
+    Ok(next.run(req).await)
+}
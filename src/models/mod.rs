@@ -1 +1,2 @@
+pub mod invitation;
 pub mod user;
@@ -1,11 +1,42 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
+/// Default role assigned to new accounts.
+pub const ROLE_USER: &str = "user";
+/// Role allowed to manage invitations and other admin-only resources.
+pub const ROLE_ADMIN: &str = "admin";
+/// All roles accepted by the API.
+pub const ROLES: [&str; 2] = [ROLE_USER, ROLE_ADMIN];
+
+/// Row of a `GET /users` listing.
+///
+/// Only `id` is always selected; sparse fieldsets (`?fields=`) may leave out the other
+/// columns, which then decode to defaults and are dropped from the response.
 #[derive(sqlx::FromRow, Debug, Serialize, Deserialize)]
 pub struct User {
     pub id: i64,
+    #[sqlx(default)]
     pub name: String,
+    #[sqlx(default)]
     pub email: String,
+    /// `active`, `suspended`, `banned` or `pending` (an expired suspension reads as `active`)
+    #[sqlx(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[sqlx(default)]
     pub created_at: DateTime<Utc>,
+    #[sqlx(default)]
     pub updated_at: DateTime<Utc>,
+    /// Set when the user is soft-deleted; only present in admin listings with `include_deleted`
+    #[sqlx(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// `GET /users/{id}/avatar` with a cache-busting token, or `null` without a profile picture
+    #[sqlx(default)]
+    pub avatar_url: Option<String>,
+    /// Custom attributes the caller may see
+    #[sqlx(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attributes: Option<Value>,
 }
@@ -0,0 +1,16 @@
+use axum::{Router, middleware, routing::post};
+
+// Import admin handlers
+use crate::handlers::policy_handler::evaluate;
+
+// Import middlewares for authentication and admin authorization
+use crate::middlewares::admin_middleware::admin_middleware;
+use crate::middlewares::auth_middleware::auth_middleware;
+
+pub fn admin_routes() -> Router {
+    Router::new()
+        .route("/admin/policy/evaluate", post(evaluate))
+        // auth runs first (outermost), then the admin check
+        .layer(middleware::from_fn(admin_middleware))
+        .layer(middleware::from_fn(auth_middleware))
+}
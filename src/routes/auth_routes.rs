@@ -1,13 +1,28 @@
-use axum::{Router, routing::post};
+use axum::{
+    Router, middleware,
+    routing::{get, post},
+};
 
 // Import the register handler
 use crate::handlers::register_handler::register_handler;
 // Import the login handler
 use crate::handlers::login_handler::login_handler;
+// Import the federated (OpenID Connect) login handlers
+use crate::handlers::oidc_handler;
+// Import the Idempotency-Key middleware (retried registrations are replayed)
+use crate::middlewares::idempotency::idempotency_middleware;
 
 // Function to create auth routes
 pub fn auth_routes() -> Router {
     Router::new()
-        .route("/register", post(register_handler))
+        .route(
+            "/register",
+            post(register_handler).layer(middleware::from_fn(idempotency_middleware)),
+        )
         .route("/login", post(login_handler))
+        .route("/auth/oidc/{provider}/start", get(oidc_handler::start))
+        .route(
+            "/auth/oidc/{provider}/callback",
+            get(oidc_handler::callback),
+        )
 }
@@ -0,0 +1,24 @@
+use axum::{
+    Router, middleware,
+    routing::{get, post},
+};
+
+// Import GraphQL handlers
+use crate::handlers::graphql_handler::{graphiql, graphiql_enabled, graphql_handler};
+
+// Import middleware for authentication
+use crate::middlewares::auth_middleware::auth_middleware;
+
+pub fn graphql_routes() -> Router {
+    // Queries and mutations authenticate with the same bearer token as the REST routes
+    let mut router = Router::new().route(
+        "/graphql",
+        post(graphql_handler).layer(middleware::from_fn(auth_middleware)),
+    );
+
+    // The IDE page itself is public; it only exists when GRAPHQL_GRAPHIQL=true
+    if graphiql_enabled() {
+        router = router.route("/graphql", get(graphiql));
+    }
+    router
+}
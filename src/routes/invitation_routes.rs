@@ -0,0 +1,25 @@
+use axum::{
+    Router, middleware,
+    routing::{delete, get, post},
+};
+
+// Import invitation handlers
+use crate::handlers::invitation_handler::{accept, destroy, index, store};
+
+// Import middlewares for authentication and admin authorization
+use crate::middlewares::admin_middleware::admin_middleware;
+use crate::middlewares::auth_middleware::auth_middleware;
+
+pub fn invitation_routes() -> Router {
+    // Admin-only management routes (auth runs first, then the admin check)
+    let admin = Router::new()
+        .route("/invitations", get(index).post(store))
+        .route("/invitations/{id}", delete(destroy))
+        .layer(middleware::from_fn(admin_middleware))
+        .layer(middleware::from_fn(auth_middleware));
+
+    // Public acceptance route (the invitation token is the credential)
+    Router::new()
+        .route("/invitations/accept", post(accept))
+        .merge(admin)
+}
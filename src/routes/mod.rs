@@ -1,2 +1,9 @@
+pub mod admin_routes;
 pub mod auth_routes;
+pub mod graphql_routes;
+pub mod invitation_routes;
+pub mod scim_routes;
 pub mod user_routes;
+pub mod versioned_routes;
+pub mod webhook_routes;
+pub mod ws_routes;
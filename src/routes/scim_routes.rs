@@ -0,0 +1,27 @@
+use axum::{Router, middleware, routing::get};
+
+// Import SCIM 2.0 handlers
+use crate::handlers::scim_handler::{
+    create_user, delete_user, get_user, list_users, patch_user, replace_user,
+    service_provider_config,
+};
+
+// Import middleware for the dedicated SCIM bearer token
+use crate::middlewares::scim_auth::scim_auth_middleware;
+
+pub fn scim_routes() -> Router {
+    Router::new()
+        .route("/scim/v2/Users", get(list_users).post(create_user))
+        .route(
+            "/scim/v2/Users/{id}",
+            get(get_user)
+                .put(replace_user)
+                .patch(patch_user)
+                .delete(delete_user),
+        )
+        .route(
+            "/scim/v2/ServiceProviderConfig",
+            get(service_provider_config),
+        )
+        .layer(middleware::from_fn(scim_auth_middleware))
+}
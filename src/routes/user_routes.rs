@@ -1,19 +1,93 @@
 use axum::{
-    Router, middleware,
-    routing::{get, post},
+    Router,
+    extract::DefaultBodyLimit,
+    middleware,
+    routing::{get, post, put},
 };
 
 // Import user-related handlers
-use crate::handlers::user_handler::{destroy, index, show, store, update};
+use crate::handlers::user_avatar_handler::{
+    destroy_avatar, max_upload_bytes, show_avatar, upload_avatar,
+};
+use crate::handlers::user_bulk_handler::bulk;
+use crate::handlers::user_data_handler;
+use crate::handlers::user_events_handler::stream_user_events;
+use crate::handlers::user_handler::{destroy, index, patch, restore, show, store, update};
+use crate::handlers::user_history_handler;
+use crate::handlers::user_search_handler;
+use crate::handlers::user_status_handler;
+use crate::handlers::user_transfer_handler::{export, import};
 
-// Import middleware for authentication
+// Import middlewares for authentication, admin authorization and idempotent retries
+use crate::middlewares::admin_middleware::admin_middleware;
 use crate::middlewares::auth_middleware::auth_middleware;
+use crate::middlewares::idempotency::idempotency_middleware;
 
 pub fn user_routes() -> Router {
+    // Admin-only routes (auth runs first, then the admin check)
+    let admin = Router::new()
+        .route("/users/export", get(export))
+        .route("/users/import", post(import))
+        .route("/users/{id}/restore", post(restore))
+        .route("/users/{id}/status", get(user_status_handler::show))
+        .route("/users/{id}/suspend", post(user_status_handler::suspend))
+        .route("/users/{id}/ban", post(user_status_handler::ban))
+        .route(
+            "/users/{id}/reactivate",
+            post(user_status_handler::reactivate),
+        )
+        .route("/users/{id}/export", post(user_data_handler::export_user))
+        .route(
+            "/users/{id}/erase",
+            post(user_data_handler::erase_user).delete(user_data_handler::cancel_erase_user),
+        )
+        .route(
+            "/users/{id}/data-requests",
+            get(user_data_handler::index_user),
+        )
+        .layer(middleware::from_fn(admin_middleware))
+        .layer(middleware::from_fn(auth_middleware));
+
     Router::new()
         .route("/users", get(index))
-        .route("/users", post(store))
-        .route("/users/{id}", get(show).put(update).delete(destroy))
+        // Retried creations carrying an Idempotency-Key are replayed instead of run twice
+        .route(
+            "/users",
+            post(store).layer(middleware::from_fn(idempotency_middleware)),
+        )
+        .route("/users/bulk", post(bulk))
+        .route("/users/search", get(user_search_handler::search))
+        .route(
+            "/users/{id}",
+            get(show).put(update).patch(patch).delete(destroy),
+        )
+        .route("/users/{id}/history", get(user_history_handler::index))
+        .route(
+            "/users/{id}/history/{revision}/revert",
+            post(user_history_handler::revert),
+        )
+        .route(
+            "/users/{id}/avatar",
+            put(upload_avatar)
+                .get(show_avatar)
+                .delete(destroy_avatar)
+                // Leave room for the multipart framing; the handler enforces the exact limit
+                .layer(DefaultBodyLimit::max(max_upload_bytes() + 64 * 1024)),
+        )
+        // Data subject requests (GDPR) for the caller's own account
+        .route("/me/export", post(user_data_handler::export_me))
+        .route(
+            "/me/erase",
+            post(user_data_handler::erase_me).delete(user_data_handler::cancel_erase_me),
+        )
+        .route("/me/data-requests", get(user_data_handler::index_me))
+        .route(
+            "/data-requests/{id}/download",
+            get(user_data_handler::download),
+        )
+        // Live stream of user changes (Server-Sent Events)
+        .route("/events/users", get(stream_user_events))
         // Apply authentication middleware to user routes
         .layer(middleware::from_fn(auth_middleware))
+        .merge(admin)
 }
@@ -0,0 +1,31 @@
+use axum::{Router, middleware};
+
+// Import the route groups that make up the versioned REST API
+use crate::routes::auth_routes::auth_routes;
+use crate::routes::user_routes::user_routes;
+
+// Import the version negotiation middlewares
+use crate::middlewares::api_version::{unversioned_alias, v2_responses};
+
+// The current API (auth and users)
+pub fn v1_routes() -> Router {
+    Router::new().merge(auth_routes()).merge(user_routes())
+}
+
+// The v2 API: the v1 handlers with v2 response shapes. Routes whose schemas change in v2
+// get their own handlers here, without touching v1.
+pub fn v2_routes() -> Router {
+    v1_routes().layer(middleware::from_fn(v2_responses))
+}
+
+// `/v1` and `/v2`, plus the unversioned paths as deprecated v1 aliases that also
+// honour `Accept: application/vnd.backend.v2+json`
+pub fn versioned_routes() -> Router {
+    Router::new()
+        .nest("/v1", v1_routes())
+        .nest("/v2", v2_routes())
+        .merge(v1_routes().layer(middleware::from_fn_with_state(
+            v2_routes(),
+            unversioned_alias,
+        )))
+}
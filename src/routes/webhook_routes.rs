@@ -0,0 +1,25 @@
+use axum::{
+    Router, middleware,
+    routing::{get, post},
+};
+
+// Import webhook handlers
+use crate::handlers::webhook_handler::{
+    deliveries, destroy, index, replay, replay_dead, show, store, update,
+};
+
+// Import middlewares for authentication and admin authorization
+use crate::middlewares::admin_middleware::admin_middleware;
+use crate::middlewares::auth_middleware::auth_middleware;
+
+pub fn webhook_routes() -> Router {
+    // Admin-only management routes (auth runs first, then the admin check)
+    Router::new()
+        .route("/webhooks", get(index).post(store))
+        .route("/webhooks/{id}", get(show).put(update).delete(destroy))
+        .route("/webhooks/{id}/deliveries", get(deliveries))
+        .route("/webhooks/{id}/replay", post(replay_dead))
+        .route("/webhooks/deliveries/{id}/replay", post(replay))
+        .layer(middleware::from_fn(admin_middleware))
+        .layer(middleware::from_fn(auth_middleware))
+}
@@ -0,0 +1,9 @@
+use axum::{Router, routing::get};
+
+// Import the WebSocket handler
+use crate::handlers::ws_handler::ws_handler;
+
+pub fn ws_routes() -> Router {
+    // Not behind auth_middleware: the JWT comes in the query string or the first message
+    Router::new().route("/ws", get(ws_handler))
+}
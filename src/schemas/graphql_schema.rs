@@ -0,0 +1,144 @@
+use async_graphql::{Enum, InputObject, Json, SimpleObject};
+use chrono::{DateTime, Utc};
+use serde_json::{Map, Value};
+
+use crate::models::user::User;
+use crate::schemas::user_schema::{UserResponseSchema, UserStoreRequestSchema};
+use crate::utils::user_status::UserStatus;
+
+/// Account status, as a GraphQL enum (`ACTIVE`, `SUSPENDED`, ...).
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+#[graphql(name = "UserStatus")]
+pub enum GraphqlUserStatus {
+    Active,
+    Suspended,
+    Banned,
+    Pending,
+}
+
+impl GraphqlUserStatus {
+    fn parse(raw: Option<&str>) -> Option<Self> {
+        Some(match UserStatus::parse(raw?)? {
+            UserStatus::Active => Self::Active,
+            UserStatus::Suspended => Self::Suspended,
+            UserStatus::Banned => Self::Banned,
+            UserStatus::Pending => Self::Pending,
+        })
+    }
+}
+
+impl From<GraphqlUserStatus> for UserStatus {
+    fn from(status: GraphqlUserStatus) -> Self {
+        match status {
+            GraphqlUserStatus::Active => Self::Active,
+            GraphqlUserStatus::Suspended => Self::Suspended,
+            GraphqlUserStatus::Banned => Self::Banned,
+            GraphqlUserStatus::Pending => Self::Pending,
+        }
+    }
+}
+
+/// A user, with the custom attributes the caller may see.
+#[derive(SimpleObject, Debug)]
+#[graphql(name = "User")]
+pub struct GraphqlUser {
+    pub id: i64,
+    pub name: String,
+    pub email: String,
+    /// Effective status (an expired suspension reads as `ACTIVE`)
+    pub status: Option<GraphqlUserStatus>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// Incremented on every write; pass it to `updateUser`/`deleteUser` to detect lost updates
+    pub version: i64,
+    /// Set on soft-deleted users, which only admins list (`includeDeleted`)
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// `GET /users/{id}/avatar` with a cache-busting token, or `null` without a profile picture
+    pub avatar_url: Option<String>,
+    /// Custom attributes the caller may see
+    pub attributes: Json<Value>,
+}
+
+impl GraphqlUser {
+    /// A row of a listing (which does not carry the version itself).
+    pub fn from_list_row(user: User, version: i64) -> Self {
+        Self {
+            id: user.id,
+            name: user.name,
+            email: user.email,
+            status: GraphqlUserStatus::parse(user.status.as_deref()),
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+            version,
+            deleted_at: user.deleted_at,
+            avatar_url: user.avatar_url,
+            attributes: Json(user.attributes.unwrap_or_default()),
+        }
+    }
+}
+
+impl From<UserResponseSchema> for GraphqlUser {
+    fn from(user: UserResponseSchema) -> Self {
+        Self {
+            id: user.id,
+            name: user.name,
+            email: user.email,
+            status: GraphqlUserStatus::parse(user.status.as_deref()),
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+            version: user.version,
+            deleted_at: None,
+            avatar_url: user.avatar_url,
+            attributes: Json(user.attributes.unwrap_or_default()),
+        }
+    }
+}
+
+/// Filters of the `users` connection, with the semantics of the `GET /users` parameters.
+#[derive(InputObject, Debug, Default)]
+pub struct UserFilterInput {
+    /// Case-insensitive substring match over name and email
+    pub q: Option<String>,
+    /// Exact email match
+    pub email: Option<String>,
+    /// Any of these statuses
+    pub status: Option<Vec<GraphqlUserStatus>>,
+    /// Created at or after this time
+    pub created_after: Option<DateTime<Utc>>,
+    /// Created before this time
+    pub created_before: Option<DateTime<Utc>>,
+    /// Also return soft-deleted users (admin only)
+    #[graphql(default)]
+    pub include_deleted: bool,
+}
+
+/// Input of `createUser`; validated like `POST /users`.
+#[derive(InputObject, Debug)]
+pub struct CreateUserInput {
+    pub name: String,
+    pub email: String,
+    pub password: String,
+    /// Custom attributes, checked against the attribute schema
+    pub attributes: Option<Json<Map<String, Value>>>,
+}
+
+impl From<CreateUserInput> for UserStoreRequestSchema {
+    fn from(input: CreateUserInput) -> Self {
+        Self {
+            name: input.name,
+            email: input.email,
+            password: input.password,
+            attributes: input.attributes.map(|a| a.0),
+        }
+    }
+}
+
+/// Input of `updateUser`: omitted fields keep their current values.
+#[derive(InputObject, Debug)]
+pub struct UpdateUserInput {
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub password: Option<String>,
+    /// Replaces the custom attributes the caller may see (`null` values remove one)
+    pub attributes: Option<Json<Map<String, Value>>>,
+}
@@ -0,0 +1,50 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use validator::{Validate, ValidationError};
+
+use crate::models::user::ROLES;
+
+fn validate_role(role: &str) -> Result<(), ValidationError> {
+    if ROLES.contains(&role) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("role").with_message("Role must be one of: user, admin".into()))
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct InvitationStoreRequestSchema {
+    #[validate(email(message = "Invalid email format"))]
+    pub email: String,
+
+    #[validate(custom(function = "validate_role"))]
+    pub role: Option<String>,
+
+    #[validate(range(min = 1, max = 720, message = "Expiry must be between 1 and 720 hours"))]
+    pub expires_in_hours: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct InvitationAcceptRequestSchema {
+    #[validate(length(min = 1, message = "Token cannot be empty"))]
+    pub token: String,
+
+    // Only required when no account exists yet for the invited email
+    #[validate(length(min = 1, message = "Name cannot be empty"))]
+    pub name: Option<String>,
+
+    // Password of the new account, or the current password of an existing account when the
+    // request is not signed in to it
+    #[validate(length(min = 6, message = "Password must be at least 6 characters long"))]
+    pub password: Option<String>,
+}
+
+#[derive(sqlx::FromRow, Debug, Serialize, Deserialize)]
+pub struct InvitationResponseSchema {
+    pub id: i64,
+    pub email: String,
+    pub role: String,
+    pub invited_by: i64,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
@@ -1,8 +1,14 @@
-pub mod login_schema;
-pub mod register_schema;
-pub mod user_schema;
-
 // // Re-export common schemas for easier imports
-// pub use crate::schemas::register_schema::{RegisterSchema, RegisterResponseSchema};
 // pub use crate::schemas::login_schema::{LoginSchema, LoginResponseSchema, UserLoginResponseSchema};
+// pub use crate::schemas::register_schema::{RegisterSchema, RegisterResponseSchema};
 // pub use crate::schemas::user_schema::{UserStoreRequestSchema, UserUpdateRequestSchema};
+pub mod graphql_schema;
+pub mod invitation_schema;
+pub mod login_schema;
+pub mod policy_schema;
+pub mod register_schema;
+pub mod scim_schema;
+pub mod user_data_schema;
+pub mod user_revision_schema;
+pub mod user_schema;
+pub mod webhook_schema;
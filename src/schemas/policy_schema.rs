@@ -0,0 +1,15 @@
+use serde::Deserialize;
+use serde_json::Value;
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct PolicyEvaluateRequestSchema {
+    // Principal attributes to simulate; defaults to the caller's own principal
+    pub principal: Option<Value>,
+
+    #[validate(length(min = 1, message = "Action cannot be empty"))]
+    pub action: String,
+
+    // Resource attributes; defaults to an empty object
+    pub resource: Option<Value>,
+}
@@ -23,4 +23,6 @@ pub struct RegisterResponseSchema {
     pub email: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// `pending` when self-registration requires admin approval
+    pub status: String,
 }
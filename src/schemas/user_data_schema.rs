@@ -0,0 +1,22 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// A data export or erasure request, as shown to the user and admins.
+#[derive(sqlx::FromRow, Debug, Serialize)]
+pub struct UserDataRequestResponseSchema {
+    pub id: i64,
+    pub user_id: i64,
+    /// `export` or `erase`
+    pub kind: String,
+    /// `pending`, `processing`, `completed`, `failed`, `cancelled` or `expired`
+    pub status: String,
+    pub requested_by: i64,
+    /// When an erasure will run (end of the grace period)
+    pub scheduled_for: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Download link of a completed export, until `expires_at`
+    pub download_url: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
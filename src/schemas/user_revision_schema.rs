@@ -0,0 +1,22 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::types::Json;
+
+/// One recorded change of a user, as returned by `GET /users/{id}/history`.
+#[derive(sqlx::FromRow, Debug, Serialize)]
+pub struct UserRevisionResponseSchema {
+    pub id: i64,
+    pub user_id: i64,
+    /// Who made the change (`null` when unknown)
+    pub actor_id: Option<i64>,
+    /// `create`, `update`, `delete`, `restore` or `revert`
+    pub action: String,
+    /// `{ "<field>": { "from": ..., "to": ... } }`; custom attributes are keyed `attributes.<name>`
+    pub changes: Json<Value>,
+    /// The user's version after the change
+    pub version: i64,
+    /// For `revert`, the revision whose state was restored
+    pub reverted_to: Option<i64>,
+    pub created_at: DateTime<Utc>,
+}
@@ -1,5 +1,6 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use validator::Validate;
 
 #[derive(Debug, Deserialize, Validate)]
@@ -12,25 +13,124 @@ pub struct UserStoreRequestSchema {
 
     #[validate(length(min = 6, message = "Password must be at least 6 characters long"))]
     pub password: String,
+
+    /// Custom attributes, checked against the attribute schema
+    #[serde(default)]
+    pub attributes: Option<Map<String, Value>>,
 }
 
+/// User representation returned by the API.
+///
+/// Columns other than `id` and `version` may be left out of the `SELECT` by sparse
+/// fieldsets (`?fields=`); they then decode to defaults and are dropped from the response.
 #[derive(sqlx::FromRow, Debug, Serialize, Deserialize)]
 pub struct UserResponseSchema {
     pub id: i64,
+    #[sqlx(default)]
     pub name: String,
+    #[sqlx(default)]
     pub email: String,
+    /// `active`, `suspended`, `banned` or `pending` (an expired suspension reads as `active`)
+    #[sqlx(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[sqlx(default)]
     pub created_at: DateTime<Utc>,
+    #[sqlx(default)]
     pub updated_at: DateTime<Utc>,
+    /// Incremented on every write; exposed as the `ETag` header
+    pub version: i64,
+    /// `GET /users/{id}/avatar` with a cache-busting token, or `null` without a profile picture
+    #[sqlx(default)]
+    pub avatar_url: Option<String>,
+    /// Custom attributes the caller may see; left out where they are not selected
+    #[sqlx(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attributes: Option<Value>,
 }
 
+/// Full representation for `PUT /users/{id}` (and the result of a `PATCH`).
+///
+/// `password` and `attributes` are optional: when omitted the current values are kept.
 #[derive(Debug, Serialize, Deserialize, Validate)]
 pub struct UserUpdateRequestSchema {
     #[validate(length(min = 1, message = "Name cannot be empty"))]
-    pub name: Option<String>,
+    pub name: String,
 
     #[validate(email(message = "Invalid email format"))]
-    pub email: Option<String>,
+    pub email: String,
 
     #[validate(length(min = 6, message = "Password must be at least 6 characters long"))]
     pub password: Option<String>,
+
+    /// Replaces the custom attributes the caller may see (`null` values remove one)
+    #[serde(default)]
+    pub attributes: Option<Map<String, Value>>,
+}
+
+/// Execution mode for `POST /users/bulk`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkMode {
+    /// All operations succeed or none is applied
+    #[default]
+    Atomic,
+    /// Each operation is applied independently
+    BestEffort,
+}
+
+/// Body of the admin status endpoints (`suspend`, `ban`, `reactivate`).
+#[derive(Debug, Deserialize, Validate)]
+pub struct UserStatusChangeSchema {
+    #[validate(length(
+        min = 1,
+        max = 255,
+        message = "Reason must be 1 to 255 characters long"
+    ))]
+    pub reason: String,
+
+    /// End of a suspension (RFC 3339); omit for an indefinite one
+    #[serde(default)]
+    pub until: Option<DateTime<Utc>>,
+}
+
+/// Current status of an account and its last change, as shown to admins.
+#[derive(sqlx::FromRow, Debug, Serialize)]
+pub struct UserStatusResponseSchema {
+    pub user_id: i64,
+    pub status: String,
+    pub reason: Option<String>,
+    pub until: Option<DateTime<Utc>>,
+    pub changed_at: Option<DateTime<Utc>>,
+    /// Admin who made the last change
+    pub changed_by: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UserBulkRequestSchema {
+    #[serde(default)]
+    pub mode: BulkMode,
+    /// Parsed per item so a malformed operation only fails its own result
+    pub operations: Vec<serde_json::Value>,
+}
+
+/// One item of a bulk request, tagged by `op`.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum UserBulkOperation {
+    Create {
+        data: UserStoreRequestSchema,
+    },
+    /// Full replacement, like `PUT /users/{id}`; `version` works like `If-Match`
+    Update {
+        id: i64,
+        data: UserUpdateRequestSchema,
+        #[serde(default)]
+        version: Option<i64>,
+    },
+    Delete {
+        id: i64,
+        #[serde(default)]
+        version: Option<i64>,
+    },
 }
@@ -0,0 +1,85 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use validator::{Validate, ValidationError};
+
+use crate::utils::webhooks::{valid_pattern, valid_url};
+
+fn validate_url(url: &str) -> Result<(), ValidationError> {
+    if valid_url(url) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("url").with_message("Must be an absolute http(s) URL".into()))
+    }
+}
+
+fn validate_events(events: &[String]) -> Result<(), ValidationError> {
+    if events.iter().all(|e| valid_pattern(e)) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("events").with_message(
+            "Must be event types (user.created, user.updated, user.deleted, user.logged_in), user.* or *"
+                .into(),
+        ))
+    }
+}
+
+/// Body of `POST /webhooks` and `PUT /webhooks/{id}`.
+#[derive(Debug, Deserialize, Validate)]
+pub struct WebhookSubscriptionRequestSchema {
+    #[validate(
+        length(max = 2048, message = "URL must be at most 2048 characters"),
+        custom(function = "validate_url")
+    )]
+    pub url: String,
+
+    #[validate(
+        length(min = 1, message = "At least one event type is required"),
+        custom(function = "validate_events")
+    )]
+    pub events: Vec<String>,
+
+    #[validate(length(max = 255, message = "Description must be at most 255 characters"))]
+    pub description: Option<String>,
+
+    /// Defaults to `true`
+    pub active: Option<bool>,
+
+    /// Signing secret; generated when omitted on create and kept when omitted on update
+    #[validate(length(
+        min = 16,
+        max = 255,
+        message = "Secret must be between 16 and 255 characters"
+    ))]
+    pub secret: Option<String>,
+}
+
+#[derive(sqlx::FromRow, Debug, Serialize)]
+pub struct WebhookSubscriptionResponseSchema {
+    pub id: i64,
+    pub url: String,
+    pub events: Json<Vec<String>>,
+    pub description: Option<String>,
+    pub active: bool,
+    pub created_by: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(sqlx::FromRow, Debug, Serialize)]
+pub struct WebhookDeliveryResponseSchema {
+    pub id: i64,
+    pub event_id: i64,
+    pub event_type: String,
+    pub subscription_id: i64,
+    /// `pending`, `delivering`, `delivered` or `dead`
+    pub status: String,
+    pub attempts: i32,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    /// HTTP status of the last attempt (`null` if the request itself failed)
+    pub last_status_code: Option<i16>,
+    pub last_error: Option<String>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
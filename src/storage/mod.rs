@@ -0,0 +1,84 @@
+use axum::body::Bytes;
+use futures_util::future::BoxFuture;
+use once_cell::sync::OnceCell;
+use std::error::Error;
+use std::sync::Arc;
+
+pub mod local;
+pub mod s3;
+
+pub use local::LocalBlobStore;
+pub use s3::{S3BlobStore, S3Config};
+
+pub type BlobError = Box<dyn Error + Send + Sync>;
+
+/// Binary object storage (avatars and other uploads), addressed by `/`-separated keys.
+pub trait BlobStore: Send + Sync {
+    /// Store `data` under `key`, replacing any existing object.
+    fn put<'a>(
+        &'a self,
+        key: &'a str,
+        content_type: &'a str,
+        data: Bytes,
+    ) -> BoxFuture<'a, Result<(), BlobError>>;
+
+    /// Read an object (`None` if it does not exist).
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<Bytes>, BlobError>>;
+
+    /// Remove an object; removing a missing object is not an error.
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), BlobError>>;
+}
+
+/// Reject keys that could escape the store root or produce odd object names.
+pub(crate) fn check_key(key: &str) -> Result<(), BlobError> {
+    let valid = !key.is_empty()
+        && key.split('/').all(|segment| {
+            !segment.is_empty()
+                && segment != "."
+                && segment != ".."
+                && segment
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.'))
+        });
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("invalid blob key: {:?}", key).into())
+    }
+}
+
+/// Build the store selected by `BLOB_STORE` (`local`, the default, or `s3`).
+pub fn from_env() -> Result<Arc<dyn BlobStore>, BlobError> {
+    match std::env::var("BLOB_STORE")
+        .unwrap_or_else(|_| "local".to_string())
+        .to_ascii_lowercase()
+        .as_str()
+    {
+        "local" => {
+            let root = std::env::var("BLOB_LOCAL_DIR").unwrap_or_else(|_| "storage".to_string());
+            Ok(Arc::new(LocalBlobStore::new(root)))
+        }
+        "s3" => Ok(Arc::new(S3BlobStore::new(S3Config::from_env()?)?)),
+        other => Err(format!("unknown BLOB_STORE {:?} (expected local or s3)", other).into()),
+    }
+}
+
+static BLOB_STORE: OnceCell<Arc<dyn BlobStore>> = OnceCell::new();
+
+/// The process-wide store, created from the environment on first use.
+pub fn blob_store() -> Result<Arc<dyn BlobStore>, BlobError> {
+    BLOB_STORE.get_or_try_init(from_env).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_key() {
+        assert!(check_key("avatars/1/abc/64.png").is_ok());
+        for bad in ["", "/a", "a//b", "a/../b", "..", "a/b c", "a\\b"] {
+            assert!(check_key(bad).is_err(), "{:?}", bad);
+        }
+    }
+}
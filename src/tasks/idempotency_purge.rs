@@ -0,0 +1,37 @@
+use sqlx::MySqlPool;
+use std::time::Duration;
+
+/// Delete `Idempotency-Key` records past their TTL; returns how many were removed.
+pub async fn purge_expired_keys(db: &MySqlPool) -> Result<u64, sqlx::Error> {
+    let res = sqlx::query("DELETE FROM idempotency_keys WHERE expires_at <= NOW()")
+        .execute(db)
+        .await?;
+    Ok(res.rows_affected())
+}
+
+/// Start the periodic purge of expired idempotency keys.
+///
+/// `IDEMPOTENCY_PURGE_INTERVAL_SECS` (default 3600, `0` disables the task) is the time between
+/// runs. Expired keys are also replaced on reuse, so the task only bounds the table size.
+pub fn spawn(db: MySqlPool) {
+    let interval_secs: u64 = std::env::var("IDEMPOTENCY_PURGE_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(3600);
+    if interval_secs == 0 {
+        tracing::info!("idempotency purge: disabled (IDEMPOTENCY_PURGE_INTERVAL_SECS=0)");
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(interval_secs));
+        loop {
+            ticker.tick().await;
+            match purge_expired_keys(&db).await {
+                Ok(0) => {}
+                Ok(n) => tracing::info!("idempotency purge: removed {} expired keys", n),
+                Err(e) => tracing::error!("idempotency purge: failed: {}", e),
+            }
+        }
+    });
+}
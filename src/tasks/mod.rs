@@ -0,0 +1,5 @@
+pub mod idempotency_purge;
+pub mod search_reindex;
+pub mod user_data_requests;
+pub mod user_purge;
+pub mod webhook_delivery;
@@ -0,0 +1,75 @@
+use sqlx::MySqlPool;
+use sqlx::types::Json;
+
+use crate::utils::user_attributes::current_attribute_schema;
+
+/// Users read per batch.
+const BATCH_SIZE: i64 = 500;
+
+/// Recompute `attributes_search` for every user with custom attributes, so the search index
+/// follows changes to `USER_ATTRIBUTES_FILE`: an attribute made public becomes searchable, one
+/// made hidden stops being searchable.
+///
+/// Rows are only written when their text differs; `updated_at` and `version` are left alone and
+/// a row changed concurrently is skipped (its writer already stored the new text).
+///
+/// Returns the number of users updated.
+pub async fn reindex_search_text(db: &MySqlPool) -> Result<u64, sqlx::Error> {
+    let schema = current_attribute_schema();
+    let mut updated = 0u64;
+    let mut after = 0i64;
+    loop {
+        let rows: Vec<(i64, i64, Json<serde_json::Value>, Option<String>)> = sqlx::query_as(
+            r#"
+            SELECT id, version, attributes, attributes_search FROM users
+            WHERE id > ? AND attributes IS NOT NULL
+            ORDER BY id
+            LIMIT ?
+            "#,
+        )
+        .bind(after)
+        .bind(BATCH_SIZE)
+        .fetch_all(db)
+        .await?;
+
+        for (id, version, attributes, stored) in &rows {
+            let text = schema.search_text(Some(&attributes.0));
+            if &text == stored {
+                continue;
+            }
+            updated += sqlx::query(
+                "UPDATE users SET attributes_search = ?, updated_at = updated_at WHERE id = ? AND version = ?",
+            )
+            .bind(text)
+            .bind(id)
+            .bind(version)
+            .execute(db)
+            .await?
+            .rows_affected();
+        }
+
+        match rows.last() {
+            Some((id, ..)) if (rows.len() as i64) == BATCH_SIZE => after = *id,
+            _ => return Ok(updated),
+        }
+    }
+}
+
+/// Run the reindex once in the background at startup (`SEARCH_REINDEX_ON_START=false` skips it).
+pub fn spawn(db: MySqlPool) {
+    let enabled = std::env::var("SEARCH_REINDEX_ON_START")
+        .map(|v| v != "false" && v != "0")
+        .unwrap_or(true);
+    if !enabled {
+        tracing::info!("search reindex: disabled (SEARCH_REINDEX_ON_START=false)");
+        return;
+    }
+
+    tokio::spawn(async move {
+        match reindex_search_text(&db).await {
+            Ok(0) => {}
+            Ok(n) => tracing::info!("search reindex: updated search text of {} users", n),
+            Err(e) => tracing::error!("search reindex: failed: {}", e),
+        }
+    });
+}
@@ -0,0 +1,228 @@
+use axum::body::Bytes;
+use sqlx::MySqlPool;
+use std::time::Duration;
+
+use crate::storage::{BlobError, blob_store};
+use crate::utils::notifications::revoke_sessions;
+use crate::utils::user_data::{
+    KIND_ERASE, KIND_EXPORT, archive_key, collect_user_data, erase_user, export_ttl_hours,
+};
+
+/// Requests handled per kind and run.
+const BATCH_SIZE: i64 = 100;
+
+/// A request left in `processing` this long (e.g. by a crashed instance) is picked up again.
+const STALE_MINUTES: i64 = 60;
+
+/// Mark a request as `processing` so only one worker handles it; returns its user id if the
+/// claim succeeded.
+async fn claim(db: &MySqlPool, request_id: i64) -> Result<Option<i64>, sqlx::Error> {
+    let res = sqlx::query(
+        r#"
+        UPDATE user_data_requests
+        SET status = 'processing', started_at = NOW()
+        WHERE id = ?
+          AND (status = 'pending'
+               OR (status = 'processing' AND started_at < NOW() - INTERVAL ? MINUTE))
+        "#,
+    )
+    .bind(request_id)
+    .bind(STALE_MINUTES)
+    .execute(db)
+    .await?;
+    if res.rows_affected() == 0 {
+        return Ok(None);
+    }
+    sqlx::query_scalar("SELECT user_id FROM user_data_requests WHERE id = ?")
+        .bind(request_id)
+        .fetch_optional(db)
+        .await
+}
+
+async fn mark_failed(db: &MySqlPool, request_id: i64, error: &str) -> Result<(), sqlx::Error> {
+    let error: String = error.chars().take(255).collect();
+    sqlx::query(
+        "UPDATE user_data_requests SET status = 'failed', error = ?, completed_at = NOW() \
+         WHERE id = ?",
+    )
+    .bind(error)
+    .bind(request_id)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Build and store the archive of an export request.
+///
+/// Does nothing if another worker already claimed the request. Failures are recorded on the
+/// request; only errors while recording them are returned.
+pub async fn run_export(db: &MySqlPool, request_id: i64) -> Result<(), BlobError> {
+    let Some(user_id) = claim(db, request_id).await? else {
+        return Ok(());
+    };
+    match write_archive(db, request_id, user_id).await {
+        Ok(Some(key)) => {
+            sqlx::query(
+                r#"
+                UPDATE user_data_requests
+                SET status = 'completed', archive_key = ?, completed_at = NOW(),
+                    expires_at = NOW() + INTERVAL ? HOUR
+                WHERE id = ?
+                "#,
+            )
+            .bind(key)
+            .bind(export_ttl_hours())
+            .bind(request_id)
+            .execute(db)
+            .await?;
+            tracing::info!(
+                "user data export completed: request_id={} user_id={}",
+                request_id,
+                user_id
+            );
+        }
+        Ok(None) => mark_failed(db, request_id, "User not found").await?,
+        Err(e) => {
+            tracing::error!("user data export failed: request_id={}: {}", request_id, e);
+            mark_failed(db, request_id, &format!("Export failed: {}", e)).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn write_archive(
+    db: &MySqlPool,
+    request_id: i64,
+    user_id: i64,
+) -> Result<Option<String>, BlobError> {
+    let Some(data) = collect_user_data(db, user_id).await? else {
+        return Ok(None);
+    };
+    let key = archive_key(user_id, request_id);
+    blob_store()?
+        .put(
+            &key,
+            "application/json",
+            Bytes::from(serde_json::to_vec_pretty(&data)?),
+        )
+        .await?;
+    Ok(Some(key))
+}
+
+/// Erase the user of a request whose grace period has ended.
+async fn run_erasure(db: &MySqlPool, request_id: i64) -> Result<(), BlobError> {
+    let Some(user_id) = claim(db, request_id).await? else {
+        return Ok(());
+    };
+    match erase_user(db, user_id).await {
+        Ok(erased) => {
+            sqlx::query(
+                "UPDATE user_data_requests SET status = 'completed', completed_at = NOW() \
+                 WHERE id = ?",
+            )
+            .bind(request_id)
+            .execute(db)
+            .await?;
+            if erased {
+                tracing::info!("user erased: request_id={} user_id={}", request_id, user_id);
+                revoke_sessions(user_id, "Account erased");
+            }
+        }
+        Err(e) => {
+            tracing::error!("user erasure failed: request_id={}: {}", request_id, e);
+            mark_failed(db, request_id, &format!("Erasure failed: {}", e)).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Remove export archives past their expiry; returns how many were removed.
+async fn expire_archives(db: &MySqlPool) -> Result<u64, BlobError> {
+    let rows: Vec<(i64, String)> = sqlx::query_as(
+        r#"
+        SELECT id, archive_key FROM user_data_requests
+        WHERE kind = ? AND archive_key IS NOT NULL AND expires_at <= NOW()
+        ORDER BY id
+        LIMIT ?
+        "#,
+    )
+    .bind(KIND_EXPORT)
+    .bind(BATCH_SIZE)
+    .fetch_all(db)
+    .await?;
+    if rows.is_empty() {
+        return Ok(0);
+    }
+    let store = blob_store()?;
+    for (id, key) in &rows {
+        store.delete(key).await?;
+        sqlx::query(
+            "UPDATE user_data_requests SET status = 'expired', archive_key = NULL WHERE id = ?",
+        )
+        .bind(id)
+        .execute(db)
+        .await?;
+    }
+    Ok(rows.len() as u64)
+}
+
+/// Handle outstanding work: pending (or stale) exports, erasures whose grace period has ended
+/// and expired archives.
+///
+/// Returns the number of requests handled.
+pub async fn process_data_requests(db: &MySqlPool) -> Result<u64, BlobError> {
+    let mut handled = 0u64;
+    for kind in [KIND_EXPORT, KIND_ERASE] {
+        let ids: Vec<i64> = sqlx::query_scalar(
+            r#"
+            SELECT id FROM user_data_requests
+            WHERE kind = ?
+              AND ((status = 'pending' AND (scheduled_for IS NULL OR scheduled_for <= NOW()))
+                   OR (status = 'processing' AND started_at < NOW() - INTERVAL ? MINUTE))
+            ORDER BY id
+            LIMIT ?
+            "#,
+        )
+        .bind(kind)
+        .bind(STALE_MINUTES)
+        .bind(BATCH_SIZE)
+        .fetch_all(db)
+        .await?;
+        for id in ids {
+            if kind == KIND_EXPORT {
+                run_export(db, id).await?;
+            } else {
+                run_erasure(db, id).await?;
+            }
+            handled += 1;
+        }
+    }
+    Ok(handled + expire_archives(db).await?)
+}
+
+/// Start the periodic data request worker.
+///
+/// `USER_DATA_REQUESTS_INTERVAL_SECS` (default 60, `0` disables the task) is the time between
+/// runs. Exports also start right away when requested; the worker retries them after a restart.
+pub fn spawn(db: MySqlPool) {
+    let interval_secs: u64 = std::env::var("USER_DATA_REQUESTS_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(60);
+    if interval_secs == 0 {
+        tracing::info!("user data requests: disabled (USER_DATA_REQUESTS_INTERVAL_SECS=0)");
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(interval_secs));
+        loop {
+            ticker.tick().await;
+            match process_data_requests(&db).await {
+                Ok(0) => {}
+                Ok(n) => tracing::info!("user data requests: handled {}", n),
+                Err(e) => tracing::error!("user data requests: failed: {}", e),
+            }
+        }
+    });
+}
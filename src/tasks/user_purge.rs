@@ -0,0 +1,118 @@
+use sqlx::{MySql, MySqlPool, QueryBuilder};
+use std::time::Duration;
+
+use crate::storage::blob_store;
+use crate::utils::avatar::delete_blobs;
+
+/// Rows hard-deleted per transaction, to keep lock times short.
+const BATCH_SIZE: i64 = 500;
+
+/// Hard-delete users soft-deleted at least `retention_days` ago, with their linked identities,
+/// change history and avatar files. Erased users are kept as anonymized placeholders.
+///
+/// Returns the number of users removed.
+pub async fn purge_deleted_users(db: &MySqlPool, retention_days: u32) -> Result<u64, sqlx::Error> {
+    let mut purged = 0u64;
+    loop {
+        let mut tx = db.begin().await?;
+        let rows: Vec<(i64, Option<String>, Option<String>)> = sqlx::query_as(
+            r#"
+            SELECT id, avatar_version, avatar_content_type FROM users
+            WHERE deleted_at IS NOT NULL AND deleted_at <= NOW() - INTERVAL ? DAY
+              AND erased_at IS NULL
+            ORDER BY id
+            LIMIT ?
+            FOR UPDATE
+            "#,
+        )
+        .bind(retention_days)
+        .bind(BATCH_SIZE)
+        .fetch_all(&mut *tx)
+        .await?;
+        if rows.is_empty() {
+            tx.rollback().await?;
+            return Ok(purged);
+        }
+
+        for table_column in [
+            "user_identities WHERE user_id",
+            "user_revisions WHERE user_id",
+            "users WHERE id",
+        ] {
+            let mut qb = QueryBuilder::<MySql>::new(format!("DELETE FROM {} IN (", table_column));
+            let mut list = qb.separated(", ");
+            for (id, _, _) in &rows {
+                list.push_bind(*id);
+            }
+            qb.push(")");
+            qb.build().execute(&mut *tx).await?;
+        }
+        tx.commit().await?;
+        remove_avatars(&rows).await;
+
+        purged += rows.len() as u64;
+        if (rows.len() as i64) < BATCH_SIZE {
+            return Ok(purged);
+        }
+    }
+}
+
+/// Delete the avatar files of purged users; failures are logged and leave orphaned files.
+async fn remove_avatars(rows: &[(i64, Option<String>, Option<String>)]) {
+    let avatars: Vec<(i64, &String, &String)> = rows
+        .iter()
+        .filter_map(|(id, token, content_type)| {
+            Some((*id, token.as_ref()?, content_type.as_ref()?))
+        })
+        .collect();
+    if avatars.is_empty() {
+        return;
+    }
+    let store = match blob_store() {
+        Ok(store) => store,
+        Err(e) => {
+            tracing::warn!("user purge: avatar storage unavailable: {}", e);
+            return;
+        }
+    };
+    for (id, token, content_type) in avatars {
+        if let Err(e) = delete_blobs(store.as_ref(), id, token, content_type).await {
+            tracing::warn!("user purge: failed to remove avatar of user {}: {}", id, e);
+        }
+    }
+}
+
+/// Start the periodic purge task.
+///
+/// `USER_PURGE_RETENTION_DAYS` (default 30) is how long soft-deleted users can still be restored;
+/// `USER_PURGE_INTERVAL_SECS` (default 3600, `0` disables the task) is the time between runs.
+pub fn spawn(db: MySqlPool) {
+    let retention_days: u32 = std::env::var("USER_PURGE_RETENTION_DAYS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(30);
+    let interval_secs: u64 = std::env::var("USER_PURGE_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(3600);
+    if interval_secs == 0 {
+        tracing::info!("user purge: disabled (USER_PURGE_INTERVAL_SECS=0)");
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(interval_secs));
+        loop {
+            ticker.tick().await;
+            match purge_deleted_users(&db, retention_days).await {
+                Ok(0) => {}
+                Ok(n) => tracing::info!(
+                    "user purge: removed {} users deleted more than {} days ago",
+                    n,
+                    retention_days
+                ),
+                Err(e) => tracing::error!("user purge: failed: {}", e),
+            }
+        }
+    });
+}
@@ -0,0 +1,345 @@
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use sqlx::types::Json;
+use sqlx::{MySql, MySqlPool, QueryBuilder};
+use std::time::Duration;
+
+use crate::utils::webhooks::{
+    STATUS_DEAD, STATUS_DELIVERED, STATUS_PENDING, event_body, max_attempts, request_timeout,
+    retry_delay, sign, subscribes_to,
+};
+
+/// Events fanned out and deliveries attempted per batch.
+const BATCH_SIZE: i64 = 100;
+
+/// Deliveries sent at the same time.
+const CONCURRENCY: usize = 8;
+
+/// A delivery left in `delivering` this long (e.g. by a crashed instance) is picked up again.
+const STALE_MINUTES: i64 = 5;
+
+/// Condition for deliveries that are due (on `webhook_deliveries d`).
+const DUE_SQL: &str = "((d.status = 'pending' AND d.next_attempt_at <= NOW()) \
+     OR (d.status = 'delivering' AND d.locked_at < NOW() - INTERVAL ? MINUTE))";
+
+// Redirects are not followed: a receiver must answer at the registered URL
+static HTTP_CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .timeout(request_timeout())
+        .redirect(reqwest::redirect::Policy::none())
+        .user_agent(concat!(
+            "backend-api-jwt-webhooks/",
+            env!("CARGO_PKG_VERSION")
+        ))
+        .build()
+        .expect("failed to build webhook HTTP client")
+});
+
+/// Turn outbox events into one delivery per matching active subscription.
+///
+/// Returns the number of events dispatched.
+pub async fn dispatch_events(db: &MySqlPool) -> Result<u64, sqlx::Error> {
+    let mut dispatched = 0u64;
+    loop {
+        let mut tx = db.begin().await?;
+        let events: Vec<(i64, String)> = sqlx::query_as(
+            "SELECT id, event_type FROM webhook_events WHERE dispatched_at IS NULL \
+             ORDER BY id LIMIT ? FOR UPDATE SKIP LOCKED",
+        )
+        .bind(BATCH_SIZE)
+        .fetch_all(&mut *tx)
+        .await?;
+        if events.is_empty() {
+            tx.rollback().await?;
+            return Ok(dispatched);
+        }
+        let subscriptions: Vec<(i64, Json<Vec<String>>)> =
+            sqlx::query_as("SELECT id, events FROM webhook_subscriptions WHERE active = TRUE")
+                .fetch_all(&mut *tx)
+                .await?;
+
+        for (event_id, event_type) in &events {
+            for (subscription_id, patterns) in &subscriptions {
+                if subscribes_to(patterns, event_type) {
+                    sqlx::query(
+                        "INSERT IGNORE INTO webhook_deliveries (event_id, subscription_id) VALUES (?, ?)",
+                    )
+                    .bind(event_id)
+                    .bind(subscription_id)
+                    .execute(&mut *tx)
+                    .await?;
+                }
+            }
+        }
+        let mut qb = QueryBuilder::<MySql>::new(
+            "UPDATE webhook_events SET dispatched_at = NOW() WHERE id IN (",
+        );
+        let mut list = qb.separated(", ");
+        for (id, _) in &events {
+            list.push_bind(*id);
+        }
+        qb.push(")");
+        qb.build().execute(&mut *tx).await?;
+        tx.commit().await?;
+
+        dispatched += events.len() as u64;
+        if (events.len() as i64) < BATCH_SIZE {
+            return Ok(dispatched);
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct DueDelivery {
+    id: i64,
+    attempts: i32,
+    event_id: i64,
+    event_type: String,
+    payload: Json<Value>,
+    event_created_at: DateTime<Utc>,
+    url: String,
+    secret: String,
+}
+
+/// Mark a delivery as `delivering` so only one worker sends it.
+async fn claim(db: &MySqlPool, delivery_id: i64) -> Result<bool, sqlx::Error> {
+    let res = sqlx::query(&format!(
+        "UPDATE webhook_deliveries d SET d.status = 'delivering', d.locked_at = NOW() \
+         WHERE d.id = ? AND {}",
+        DUE_SQL
+    ))
+    .bind(delivery_id)
+    .bind(STALE_MINUTES)
+    .execute(db)
+    .await?;
+    Ok(res.rows_affected() == 1)
+}
+
+/// POST one event; `Ok` with the status code on a response, `Err` when the request failed.
+async fn send(delivery: &DueDelivery) -> Result<u16, String> {
+    let body = event_body(
+        delivery.event_id,
+        &delivery.event_type,
+        delivery.event_created_at,
+        &delivery.payload,
+    );
+    let body = serde_json::to_vec(&body).map_err(|e| e.to_string())?;
+    let timestamp = Utc::now().timestamp();
+    let res = HTTP_CLIENT
+        .post(&delivery.url)
+        .header("content-type", "application/json")
+        .header("webhook-id", delivery.event_id.to_string())
+        .header("webhook-event", &delivery.event_type)
+        .header("webhook-timestamp", timestamp.to_string())
+        .header(
+            "webhook-signature",
+            sign(&delivery.secret, timestamp, &body),
+        )
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(res.status().as_u16())
+}
+
+/// Send a claimed delivery and record the outcome: `delivered` on a 2xx response, otherwise
+/// retried with exponential backoff until `WEBHOOK_MAX_ATTEMPTS`, then `dead`.
+async fn deliver(db: &MySqlPool, delivery: DueDelivery) -> Result<(), sqlx::Error> {
+    let outcome = send(&delivery).await;
+    if let Ok(code) = outcome
+        && (200..300).contains(&code)
+    {
+        sqlx::query(
+            "UPDATE webhook_deliveries SET status = ?, attempts = attempts + 1, \
+             last_status_code = ?, last_error = NULL, delivered_at = NOW(), locked_at = NULL \
+             WHERE id = ?",
+        )
+        .bind(STATUS_DELIVERED)
+        .bind(code)
+        .bind(delivery.id)
+        .execute(db)
+        .await?;
+        return Ok(());
+    }
+
+    let (code, error) = match outcome {
+        Ok(code) => (Some(code), format!("Receiver responded with HTTP {}", code)),
+        Err(e) => (None, e),
+    };
+    let error: String = error.chars().take(255).collect();
+    let attempts = delivery.attempts as u32 + 1;
+    let status = if attempts >= max_attempts() {
+        tracing::warn!(
+            "webhooks: delivery {} of event {} to {} dead after {} attempts: {}",
+            delivery.id,
+            delivery.event_id,
+            delivery.url,
+            attempts,
+            error
+        );
+        STATUS_DEAD
+    } else {
+        STATUS_PENDING
+    };
+    sqlx::query(
+        "UPDATE webhook_deliveries SET status = ?, attempts = ?, last_status_code = ?, \
+         last_error = ?, next_attempt_at = NOW() + INTERVAL ? SECOND, locked_at = NULL \
+         WHERE id = ?",
+    )
+    .bind(status)
+    .bind(attempts)
+    .bind(code)
+    .bind(error)
+    .bind(retry_delay(attempts).as_secs())
+    .bind(delivery.id)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Send due deliveries of active subscriptions. Returns the number of attempts made.
+pub async fn deliver_due(db: &MySqlPool) -> Result<u64, sqlx::Error> {
+    let due: Vec<i64> = sqlx::query_scalar(&format!(
+        "SELECT d.id FROM webhook_deliveries d \
+         JOIN webhook_subscriptions s ON s.id = d.subscription_id AND s.active = TRUE \
+         WHERE {} ORDER BY d.next_attempt_at, d.id LIMIT ?",
+        DUE_SQL
+    ))
+    .bind(STALE_MINUTES)
+    .bind(BATCH_SIZE)
+    .fetch_all(db)
+    .await?;
+
+    let mut claimed = Vec::new();
+    for id in due {
+        if !claim(db, id).await? {
+            continue;
+        }
+        let delivery = sqlx::query_as::<_, DueDelivery>(
+            r#"
+            SELECT d.id, d.attempts, e.id AS event_id, e.event_type, e.payload,
+                   e.created_at AS event_created_at, s.url, s.secret
+            FROM webhook_deliveries d
+            JOIN webhook_events e ON e.id = d.event_id
+            JOIN webhook_subscriptions s ON s.id = d.subscription_id
+            WHERE d.id = ?
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(db)
+        .await?;
+        claimed.extend(delivery);
+    }
+
+    let attempted = claimed.len() as u64;
+    let results: Vec<Result<(), sqlx::Error>> = futures_util::stream::iter(claimed)
+        .map(|delivery| deliver(db, delivery))
+        .buffer_unordered(CONCURRENCY)
+        .collect()
+        .await;
+    results.into_iter().collect::<Result<(), _>>()?;
+    Ok(attempted)
+}
+
+/// Remove dispatched events older than `retention_days` whose deliveries are all finished,
+/// with their deliveries. Returns the number of events removed.
+pub async fn purge_old_events(db: &MySqlPool, retention_days: u32) -> Result<u64, sqlx::Error> {
+    let mut purged = 0u64;
+    loop {
+        let ids: Vec<i64> = sqlx::query_scalar(
+            r#"
+            SELECT e.id FROM webhook_events e
+            WHERE e.dispatched_at IS NOT NULL AND e.created_at < NOW() - INTERVAL ? DAY
+              AND NOT EXISTS (SELECT 1 FROM webhook_deliveries d
+                              WHERE d.event_id = e.id AND d.status IN ('pending', 'delivering'))
+            ORDER BY e.id
+            LIMIT ?
+            "#,
+        )
+        .bind(retention_days)
+        .bind(BATCH_SIZE * 10)
+        .fetch_all(db)
+        .await?;
+        if ids.is_empty() {
+            return Ok(purged);
+        }
+
+        let mut tx = db.begin().await?;
+        for table_column in [
+            "webhook_deliveries WHERE event_id",
+            "webhook_events WHERE id",
+        ] {
+            let mut qb = QueryBuilder::<MySql>::new(format!("DELETE FROM {} IN (", table_column));
+            let mut list = qb.separated(", ");
+            for id in &ids {
+                list.push_bind(*id);
+            }
+            qb.push(")");
+            qb.build().execute(&mut *tx).await?;
+        }
+        tx.commit().await?;
+
+        purged += ids.len() as u64;
+        if (ids.len() as i64) < BATCH_SIZE * 10 {
+            return Ok(purged);
+        }
+    }
+}
+
+/// One worker run: fan out new events, then send due deliveries. Returns the number of
+/// delivery attempts.
+pub async fn process_webhooks(db: &MySqlPool) -> Result<u64, sqlx::Error> {
+    dispatch_events(db).await?;
+    let mut attempted = 0u64;
+    loop {
+        let n = deliver_due(db).await?;
+        attempted += n;
+        if (n as i64) < BATCH_SIZE {
+            return Ok(attempted);
+        }
+    }
+}
+
+/// Start the delivery worker.
+///
+/// `WEBHOOK_DELIVERY_INTERVAL_SECS` (default 5, `0` disables the worker) is the time between
+/// runs; `WEBHOOK_RETENTION_DAYS` (default 30) is how long finished events and deliveries are
+/// kept.
+pub fn spawn(db: MySqlPool) {
+    let interval_secs: u64 = std::env::var("WEBHOOK_DELIVERY_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(5);
+    let retention_days: u32 = std::env::var("WEBHOOK_RETENTION_DAYS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(30);
+    if interval_secs == 0 {
+        tracing::info!("webhooks: delivery disabled (WEBHOOK_DELIVERY_INTERVAL_SECS=0)");
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(interval_secs));
+        let mut last_purge: Option<tokio::time::Instant> = None;
+        loop {
+            ticker.tick().await;
+            match process_webhooks(&db).await {
+                Ok(0) => {}
+                Ok(n) => tracing::debug!("webhooks: {} delivery attempts", n),
+                Err(e) => tracing::error!("webhooks: delivery failed: {}", e),
+            }
+            // Old events are removed about once an hour
+            if last_purge.is_none_or(|t| t.elapsed() >= Duration::from_secs(3600)) {
+                last_purge = Some(tokio::time::Instant::now());
+                match purge_old_events(&db, retention_days).await {
+                    Ok(0) => {}
+                    Ok(n) => tracing::info!("webhooks: removed {} old events", n),
+                    Err(e) => tracing::error!("webhooks: event purge failed: {}", e),
+                }
+            }
+        }
+    });
+}
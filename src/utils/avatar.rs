@@ -0,0 +1,242 @@
+use axum::body::Bytes;
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::metadata::Orientation;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader, Limits};
+use std::io::Cursor;
+
+use crate::storage::{BlobError, BlobStore};
+
+/// Media types accepted for uploads.
+pub const ACCEPTED_CONTENT_TYPES: [&str; 4] =
+    ["image/png", "image/jpeg", "image/gif", "image/webp"];
+
+/// Largest width or height accepted before decoding (guards against decompression bombs).
+const MAX_DIMENSION: u32 = 8192;
+
+/// Stored renditions of an avatar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AvatarSize {
+    /// 64x64, cropped to a square
+    Small,
+    /// 256x256, cropped to a square
+    Medium,
+    /// Original aspect ratio, at most 1024 px on the longest side
+    Large,
+}
+
+impl AvatarSize {
+    pub const ALL: [AvatarSize; 3] = [Self::Small, Self::Medium, Self::Large];
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "small" => Some(Self::Small),
+            "medium" => Some(Self::Medium),
+            "large" => Some(Self::Large),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Small => "small",
+            Self::Medium => "medium",
+            Self::Large => "large",
+        }
+    }
+
+    fn render(self, img: &DynamicImage) -> DynamicImage {
+        match self {
+            Self::Small => img.resize_to_fill(64, 64, FilterType::Lanczos3),
+            Self::Medium => img.resize_to_fill(256, 256, FilterType::Lanczos3),
+            Self::Large if img.width() > 1024 || img.height() > 1024 => {
+                img.resize(1024, 1024, FilterType::Lanczos3)
+            }
+            Self::Large => img.clone(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum AvatarError {
+    /// Not one of the accepted formats, or the bytes do not match the declared type
+    Unsupported(String),
+    /// Accepted format but the image cannot be decoded
+    Invalid(String),
+}
+
+/// Re-encoded renditions of an upload, ready to store.
+#[derive(Debug)]
+pub struct ProcessedAvatar {
+    /// `image/jpeg` for JPEG uploads, `image/png` for everything else
+    pub content_type: &'static str,
+    pub variants: Vec<(AvatarSize, Bytes)>,
+}
+
+fn image_format(content_type: &str) -> Option<ImageFormat> {
+    match content_type {
+        "image/png" => Some(ImageFormat::Png),
+        "image/jpeg" => Some(ImageFormat::Jpeg),
+        "image/gif" => Some(ImageFormat::Gif),
+        "image/webp" => Some(ImageFormat::WebP),
+        _ => None,
+    }
+}
+
+/// Decode an upload, apply its EXIF orientation and render every [`AvatarSize`].
+///
+/// The format is sniffed from the bytes and must match `declared_type`. Images are decoded and
+/// re-encoded, so EXIF and other embedded metadata (GPS position, camera serial, ...) are
+/// never stored. CPU-bound: call it from a blocking task.
+pub fn process_avatar(data: &[u8], declared_type: &str) -> Result<ProcessedAvatar, AvatarError> {
+    let declared = image_format(declared_type).ok_or_else(|| {
+        AvatarError::Unsupported(format!(
+            "Content type {} is not accepted; use one of {}",
+            declared_type,
+            ACCEPTED_CONTENT_TYPES.join(", ")
+        ))
+    })?;
+
+    let mut reader = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .map_err(|e| AvatarError::Invalid(e.to_string()))?;
+    if reader.format() != Some(declared) {
+        return Err(AvatarError::Unsupported(format!(
+            "File contents do not match the declared content type {}",
+            declared_type
+        )));
+    }
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DIMENSION);
+    limits.max_image_height = Some(MAX_DIMENSION);
+    reader.limits(limits);
+
+    let invalid = |e: image::ImageError| AvatarError::Invalid(e.to_string());
+    let mut decoder = reader.into_decoder().map_err(invalid)?;
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    let mut img = DynamicImage::from_decoder(decoder).map_err(invalid)?;
+    img.apply_orientation(orientation);
+
+    let jpeg = declared == ImageFormat::Jpeg;
+    let mut variants = Vec::with_capacity(AvatarSize::ALL.len());
+    for size in AvatarSize::ALL {
+        let rendered = size.render(&img);
+        let mut buf = Cursor::new(Vec::new());
+        if jpeg {
+            DynamicImage::ImageRgb8(rendered.to_rgb8())
+                .write_with_encoder(JpegEncoder::new_with_quality(&mut buf, 85))
+                .map_err(invalid)?;
+        } else {
+            rendered
+                .write_to(&mut buf, ImageFormat::Png)
+                .map_err(invalid)?;
+        }
+        variants.push((size, Bytes::from(buf.into_inner())));
+    }
+
+    Ok(ProcessedAvatar {
+        content_type: if jpeg { "image/jpeg" } else { "image/png" },
+        variants,
+    })
+}
+
+/// Storage key of one rendition of an avatar upload.
+pub fn blob_key(
+    user_id: i64,
+    avatar_version: &str,
+    content_type: &str,
+    size: AvatarSize,
+) -> String {
+    let ext = if content_type == "image/jpeg" {
+        "jpg"
+    } else {
+        "png"
+    };
+    format!(
+        "avatars/{}/{}/{}.{}",
+        user_id,
+        avatar_version,
+        size.as_str(),
+        ext
+    )
+}
+
+/// Remove every rendition of an avatar upload.
+pub async fn delete_blobs(
+    store: &dyn BlobStore,
+    user_id: i64,
+    avatar_version: &str,
+    content_type: &str,
+) -> Result<(), BlobError> {
+    for size in AvatarSize::ALL {
+        store
+            .delete(&blob_key(user_id, avatar_version, content_type, size))
+            .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbImage;
+
+    fn encode(img: &DynamicImage, format: ImageFormat) -> Vec<u8> {
+        let mut buf = Cursor::new(Vec::new());
+        img.write_to(&mut buf, format).unwrap();
+        buf.into_inner()
+    }
+
+    fn dimensions(data: &[u8]) -> (u32, u32) {
+        let img = image::load_from_memory(data).unwrap();
+        (img.width(), img.height())
+    }
+
+    #[test]
+    fn test_renders_all_sizes() {
+        let img = DynamicImage::ImageRgb8(RgbImage::new(1600, 1200));
+        let out = process_avatar(&encode(&img, ImageFormat::Png), "image/png").unwrap();
+        assert_eq!(out.content_type, "image/png");
+        let sizes: Vec<(u32, u32)> = out.variants.iter().map(|(_, d)| dimensions(d)).collect();
+        assert_eq!(sizes, [(64, 64), (256, 256), (1024, 768)]);
+    }
+
+    #[test]
+    fn test_applies_orientation_and_strips_exif() {
+        let img = DynamicImage::ImageRgb8(RgbImage::new(300, 200));
+        let jpeg = encode(&img, ImageFormat::Jpeg);
+        // APP1 segment with a big-endian TIFF IFD holding Orientation = 6 (rotate 90° clockwise)
+        let mut exif = vec![0xFF, 0xE1, 0x00, 0x22];
+        exif.extend_from_slice(b"Exif\0\0MM\0\x2a\0\0\0\x08");
+        exif.extend_from_slice(&[0, 1, 0x01, 0x12, 0, 3, 0, 0, 0, 1, 0, 6, 0, 0, 0, 0, 0, 0]);
+        let mut with_exif = jpeg[..2].to_vec();
+        with_exif.extend_from_slice(&exif);
+        with_exif.extend_from_slice(&jpeg[2..]);
+
+        let out = process_avatar(&with_exif, "image/jpeg").unwrap();
+        assert_eq!(out.content_type, "image/jpeg");
+        let (_, large) = &out.variants[2];
+        assert_eq!(dimensions(large), (200, 300));
+        for (_, data) in &out.variants {
+            assert!(!data.windows(4).any(|w| w == b"Exif"));
+        }
+    }
+
+    #[test]
+    fn test_rejects_mismatched_and_unknown_types() {
+        let img = DynamicImage::ImageRgb8(RgbImage::new(10, 10));
+        let png = encode(&img, ImageFormat::Png);
+        assert!(matches!(
+            process_avatar(&png, "image/jpeg"),
+            Err(AvatarError::Unsupported(_))
+        ));
+        assert!(matches!(
+            process_avatar(&png, "image/svg+xml"),
+            Err(AvatarError::Unsupported(_))
+        ));
+        assert!(matches!(
+            process_avatar(&png[..40], "image/png"),
+            Err(AvatarError::Invalid(_))
+        ));
+    }
+}
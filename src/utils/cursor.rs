@@ -0,0 +1,62 @@
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use hmac::{Hmac, Mac};
+use serde_json::Value;
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+// Cursors are signed with CURSOR_SECRET, falling back to JWT_SECRET
+fn cursor_secret() -> Vec<u8> {
+    std::env::var("CURSOR_SECRET")
+        .or_else(|_| std::env::var("JWT_SECRET"))
+        .unwrap_or_default()
+        .into_bytes()
+}
+
+fn mac_for(payload: &str) -> HmacSha256 {
+    let mut mac =
+        HmacSha256::new_from_slice(&cursor_secret()).expect("HMAC accepts keys of any length");
+    mac.update(payload.as_bytes());
+    mac
+}
+
+/// Encode a JSON payload as an opaque, tamper-evident cursor: `base64url(json).base64url(hmac)`.
+pub fn encode_cursor(payload: &Value) -> String {
+    let body = URL_SAFE_NO_PAD.encode(payload.to_string());
+    let sig = URL_SAFE_NO_PAD.encode(mac_for(&body).finalize().into_bytes());
+    format!("{}.{}", body, sig)
+}
+
+/// Decode a cursor produced by [`encode_cursor`]; `None` if malformed or the signature is invalid.
+pub fn decode_cursor(cursor: &str) -> Option<Value> {
+    let (body, sig) = cursor.split_once('.')?;
+    let sig = URL_SAFE_NO_PAD.decode(sig).ok()?;
+    // verify_slice compares in constant time
+    mac_for(body).verify_slice(&sig).ok()?;
+    let json = URL_SAFE_NO_PAD.decode(body).ok()?;
+    serde_json::from_slice(&json).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_cursor_roundtrip_and_tamper() {
+        let payload = json!({ "k": [1, "x"], "d": "next" });
+        let cursor = encode_cursor(&payload);
+        assert_eq!(decode_cursor(&cursor), Some(payload));
+
+        // Changing the body invalidates the signature
+        let (_, sig) = cursor.split_once('.').unwrap();
+        let forged = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(json!({ "k": [2, "x"], "d": "next" }).to_string()),
+            sig
+        );
+        assert_eq!(decode_cursor(&forged), None);
+        assert_eq!(decode_cursor("garbage"), None);
+    }
+}
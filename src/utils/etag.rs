@@ -0,0 +1,81 @@
+use axum::http::{HeaderMap, HeaderName, header};
+
+/// Strong entity tag for a versioned resource (e.g. `"3"`).
+pub fn etag_for(version: i64) -> String {
+    format!("\"{}\"", version)
+}
+
+// All entity tags listed in a (possibly repeated) conditional header
+fn listed_tags(headers: &HeaderMap, name: HeaderName) -> Option<Vec<String>> {
+    let mut tags = Vec::new();
+    let mut present = false;
+    for value in headers.get_all(name) {
+        present = true;
+        let raw = value.to_str().unwrap_or("");
+        tags.extend(
+            raw.split(',')
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .map(str::to_string),
+        );
+    }
+    present.then_some(tags)
+}
+
+/// Evaluate `If-Match` against the current entity tag (strong comparison, RFC 9110 §13.1.1).
+///
+/// Returns `None` when the header is absent, otherwise whether the precondition holds.
+pub fn if_match(headers: &HeaderMap, current: &str) -> Option<bool> {
+    listed_tags(headers, header::IF_MATCH).map(|tags| tags.iter().any(|t| t == "*" || t == current))
+}
+
+/// Evaluate `If-None-Match` (weak comparison, RFC 9110 §13.1.2).
+///
+/// Returns `true` when the client's cached copy is current, i.e. a `GET` may answer `304`.
+pub fn if_none_match(headers: &HeaderMap, current: &str) -> bool {
+    listed_tags(headers, header::IF_NONE_MATCH).is_some_and(|tags| {
+        tags.iter()
+            .any(|t| t == "*" || t.strip_prefix("W/").unwrap_or(t) == current)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(name: HeaderName, value: &'static str) -> HeaderMap {
+        let mut h = HeaderMap::new();
+        h.insert(name, HeaderValue::from_static(value));
+        h
+    }
+
+    #[test]
+    fn test_conditional_headers() {
+        let current = etag_for(3);
+        assert_eq!(current, "\"3\"");
+        assert_eq!(if_match(&HeaderMap::new(), &current), None);
+        assert_eq!(
+            if_match(&headers(header::IF_MATCH, "\"2\", \"3\""), &current),
+            Some(true)
+        );
+        assert_eq!(
+            if_match(&headers(header::IF_MATCH, "*"), &current),
+            Some(true)
+        );
+        // Weak tags never satisfy If-Match
+        assert_eq!(
+            if_match(&headers(header::IF_MATCH, "W/\"3\""), &current),
+            Some(false)
+        );
+        assert!(if_none_match(
+            &headers(header::IF_NONE_MATCH, "W/\"3\""),
+            &current
+        ));
+        assert!(!if_none_match(
+            &headers(header::IF_NONE_MATCH, "\"2\""),
+            &current
+        ));
+        assert!(!if_none_match(&HeaderMap::new(), &current));
+    }
+}
@@ -1,17 +1,40 @@
 use chrono::{Duration, Utc};
 use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::models::user::ROLE_USER;
+
+fn default_role() -> String {
+    ROLE_USER.to_string()
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Claims {
     pub sub: i64, // user id
     pub exp: usize,
+    // role at issue time (tokens issued before roles existed default to "user")
+    #[serde(default = "default_role")]
+    pub role: String,
+    // extra principal attributes used by the permission engine (e.g. region, department)
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub attrs: Map<String, Value>,
 }
 
 //function to create a JWT token
 pub fn create_jwt(
     user_id: i64,
     secret: &str,
+) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    create_jwt_for(user_id, ROLE_USER, Map::new(), secret)
+}
+
+//function to create a JWT token carrying role and principal attributes
+pub fn create_jwt_for(
+    user_id: i64,
+    role: &str,
+    attrs: Map<String, Value>,
+    secret: &str,
 ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
     // Set expiration to 24 hours from now
     let expiration = Utc::now()
@@ -27,6 +50,8 @@ pub fn create_jwt(
         &Claims {
             sub: user_id,
             exp: expiration,
+            role: role.to_string(),
+            attrs,
         },
         &EncodingKey::from_secret(secret.as_ref()),
     )
@@ -59,6 +84,17 @@ pub async fn generate_jwt_token(
     create_jwt(user_id, &secret)
 }
 
+// Async helper to generate a token with role and principal attributes using JWT_SECRET
+pub async fn generate_jwt_token_for(
+    user_id: i64,
+    role: &str,
+    attrs: Map<String, Value>,
+) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let secret = std::env::var("JWT_SECRET")
+        .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?;
+    create_jwt_for(user_id, role, attrs, &secret)
+}
+
 // Async helper to verify a token using JWT_SECRET and return Claims
 pub async fn verify_jwt_token(
     token: &str,
@@ -1,5 +1,23 @@
 pub mod auth;
+pub mod avatar;
+pub mod cursor;
+pub mod etag;
 pub mod handler;
 pub mod jwt;
+pub mod notifications;
+pub mod oidc;
+pub mod policy;
 pub mod response;
+pub mod scim;
+pub mod token;
+pub mod user_attributes;
+pub mod user_data;
+pub mod user_events;
+pub mod user_fields;
+pub mod user_query;
+pub mod user_revisions;
+pub mod user_search;
+pub mod user_status;
+pub mod user_transfer;
 pub mod validation;
+pub mod webhooks;
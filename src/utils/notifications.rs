@@ -0,0 +1,156 @@
+use chrono::Utc;
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use serde_json::{Value, json};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::mpsc;
+
+/// The account can no longer be used (suspended, banned, deleted or erased); the server closes
+/// the connection right after sending it.
+pub const NOTIFY_SESSION_REVOKED: &str = "session_revoked";
+pub const NOTIFY_PASSWORD_CHANGED: &str = "password_changed";
+/// The role changed; tokens carry the role at issue time, so clients should sign in again.
+pub const NOTIFY_ROLE_UPDATED: &str = "role_updated";
+
+/// Close code for missing, invalid or expired tokens.
+pub const CLOSE_UNAUTHORIZED: u16 = 4401;
+/// Close code for revoked sessions (the account is no longer active).
+pub const CLOSE_REVOKED: u16 = 4403;
+
+/// Messages queued per connection; a client that falls this far behind misses notifications.
+const QUEUE_SIZE: usize = 64;
+
+/// What the socket task of a connection is asked to do.
+#[derive(Debug, Clone)]
+pub enum Outgoing {
+    /// Send a JSON text message
+    Message(Value),
+    /// Send a close frame and end the connection
+    Close { code: u16, reason: String },
+}
+
+struct Connection {
+    id: u64,
+    sender: mpsc::Sender<Outgoing>,
+}
+
+// In-memory registry of open WebSocket connections by user id (per instance)
+static CONNECTIONS: Lazy<DashMap<i64, Vec<Connection>>> = Lazy::new(DashMap::new);
+static NEXT_CONNECTION_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Add a connection of `user_id`; returns its id (for [`unregister`]) and its message queue.
+pub fn register(user_id: i64) -> (u64, mpsc::Receiver<Outgoing>) {
+    let id = NEXT_CONNECTION_ID.fetch_add(1, Ordering::Relaxed);
+    let (sender, receiver) = mpsc::channel(QUEUE_SIZE);
+    CONNECTIONS
+        .entry(user_id)
+        .or_default()
+        .push(Connection { id, sender });
+    (id, receiver)
+}
+
+/// Remove a connection once its socket is closed.
+pub fn unregister(user_id: i64, connection_id: u64) {
+    CONNECTIONS.remove_if_mut(&user_id, |_, connections| {
+        connections.retain(|c| c.id != connection_id);
+        connections.is_empty()
+    });
+}
+
+/// Open connections of a user on this instance.
+pub fn connection_count(user_id: i64) -> usize {
+    CONNECTIONS.get(&user_id).map_or(0, |c| c.len())
+}
+
+/// Queue `outgoing` on every connection of a user; returns how many accepted it.
+fn send_to_user(user_id: i64, outgoing: &Outgoing) -> usize {
+    let Some(connections) = CONNECTIONS.get(&user_id) else {
+        return 0;
+    };
+    let mut sent = 0;
+    for connection in connections.iter() {
+        match connection.sender.try_send(outgoing.clone()) {
+            Ok(()) => sent += 1,
+            Err(e) => tracing::warn!(
+                "notifications: dropped message for user_id={} connection={}: {}",
+                user_id,
+                connection.id,
+                e
+            ),
+        }
+    }
+    sent
+}
+
+/// Notification message: `{ "type": ..., "data": ..., "sent_at": ... }`.
+pub fn message(kind: &str, data: Value) -> Value {
+    json!({ "type": kind, "data": data, "sent_at": Utc::now() })
+}
+
+/// Push a notification to every open connection of a user. Returns the number of
+/// connections it was queued on (0 when the user is not connected to this instance).
+pub fn notify_user(user_id: i64, kind: &str, data: Value) -> usize {
+    send_to_user(user_id, &Outgoing::Message(message(kind, data)))
+}
+
+/// Push a notification to every open connection.
+pub fn broadcast(kind: &str, data: Value) -> usize {
+    let outgoing = Outgoing::Message(message(kind, data));
+    let user_ids: Vec<i64> = CONNECTIONS.iter().map(|entry| *entry.key()).collect();
+    user_ids
+        .into_iter()
+        .map(|user_id| send_to_user(user_id, &outgoing))
+        .sum()
+}
+
+/// Tell every connection of a user that its session was revoked, then close them.
+pub fn revoke_sessions(user_id: i64, reason: &str) -> usize {
+    notify_user(user_id, NOTIFY_SESSION_REVOKED, json!({ "reason": reason }));
+    send_to_user(
+        user_id,
+        &Outgoing::Close {
+            code: CLOSE_REVOKED,
+            reason: reason.to_string(),
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_registry_routes_messages_per_user() {
+        // Ids far from anything other tests use, since the registry is global
+        let (a, b) = (-1001, -1002);
+        let (a1, mut a1_rx) = register(a);
+        let (_a2, mut a2_rx) = register(a);
+        let (b1, mut b1_rx) = register(b);
+        assert_eq!(connection_count(a), 2);
+
+        assert_eq!(notify_user(a, NOTIFY_PASSWORD_CHANGED, json!({})), 2);
+        for rx in [&mut a1_rx, &mut a2_rx] {
+            match rx.recv().await {
+                Some(Outgoing::Message(m)) => assert_eq!(m["type"], NOTIFY_PASSWORD_CHANGED),
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+        assert!(b1_rx.try_recv().is_err());
+
+        assert_eq!(revoke_sessions(b, "banned"), 1);
+        assert!(matches!(b1_rx.recv().await, Some(Outgoing::Message(_))));
+        assert!(matches!(
+            b1_rx.recv().await,
+            Some(Outgoing::Close {
+                code: CLOSE_REVOKED,
+                ..
+            })
+        ));
+
+        unregister(a, a1);
+        assert_eq!(connection_count(a), 1);
+        unregister(b, b1);
+        assert_eq!(connection_count(b), 0);
+        assert_eq!(notify_user(b, NOTIFY_ROLE_UPDATED, json!({})), 0);
+    }
+}
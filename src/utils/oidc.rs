@@ -0,0 +1,372 @@
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use dashmap::DashMap;
+use jsonwebtoken::jwk::{Jwk, JwkSet};
+use jsonwebtoken::{
+    Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, decode_header, encode,
+};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::config::oidc::OidcProviderConfig;
+use crate::utils::token::generate_token;
+
+type BoxError = Box<dyn Error + Send + Sync>;
+
+/// Cookie carrying the [`PendingLogin`] between `/start` and `/callback`.
+pub const LOGIN_COOKIE: &str = "oidc_login";
+
+/// Login started by `/auth/oidc/{provider}/start`.
+///
+/// It travels in a signed cookie scoped to the callback path, which binds `state` to the
+/// browser that started the login and lets any instance complete it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingLogin {
+    pub state: String,
+    pub provider: String,
+    pub nonce: String,
+    pub code_verifier: String,
+    exp: i64,
+}
+
+// Discovery documents cached per provider name
+static METADATA_CACHE: Lazy<DashMap<String, ProviderMetadata>> = Lazy::new(DashMap::new);
+
+// Signing keys cached per JWKS URI, with the time they were fetched
+static JWKS_CACHE: Lazy<DashMap<String, (Arc<JwkSet>, Instant)>> = Lazy::new(DashMap::new);
+
+static HTTP_CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .expect("failed to build OIDC HTTP client")
+});
+
+fn env_u64(name: &str, default: u64) -> u64 {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+/// How long a started login may take (`OIDC_STATE_TTL_SECS`, default 600).
+pub fn state_ttl() -> Duration {
+    Duration::from_secs(env_u64("OIDC_STATE_TTL_SECS", 600))
+}
+
+fn jwks_ttl() -> Duration {
+    Duration::from_secs(env_u64("OIDC_JWKS_TTL_SECS", 3600))
+}
+
+/// PKCE S256 code challenge: base64url(SHA-256(verifier)) without padding.
+pub fn pkce_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+/// Create `state`, `nonce` and PKCE verifier for a new login.
+pub fn begin_login(provider: &str) -> PendingLogin {
+    PendingLogin {
+        state: generate_token(),
+        provider: provider.to_string(),
+        nonce: generate_token(),
+        code_verifier: generate_token(),
+        exp: chrono::Utc::now().timestamp() + state_ttl().as_secs() as i64,
+    }
+}
+
+// Login cookies are signed with a key derived from `secret`, so they never pass as API tokens
+fn login_key(secret: &str) -> Vec<u8> {
+    format!("oidc-login:{}", secret).into_bytes()
+}
+
+/// Sign a pending login into a cookie value.
+pub fn seal_login(pending: &PendingLogin, secret: &str) -> Result<String, BoxError> {
+    Ok(encode(
+        &Header::new(Algorithm::HS256),
+        pending,
+        &EncodingKey::from_secret(&login_key(secret)),
+    )?)
+}
+
+/// Pending login from a cookie value; `None` when tampered with or expired.
+pub fn open_login(value: &str, secret: &str) -> Option<PendingLogin> {
+    let mut validation = Validation::new(Algorithm::HS256);
+    validation.leeway = 0;
+    decode::<PendingLogin>(
+        value,
+        &DecodingKey::from_secret(&login_key(secret)),
+        &validation,
+    )
+    .ok()
+    .map(|data| data.claims)
+}
+
+/// `Set-Cookie` value for the login cookie, scoped to the provider's callback path.
+/// An empty `value` with `max_age` 0 clears it.
+pub fn login_cookie(provider: &OidcProviderConfig, value: &str, max_age: Duration) -> String {
+    let redirect = url::Url::parse(&provider.redirect_uri).ok();
+    let path = redirect.as_ref().map_or("/", |u| u.path());
+    let secure = redirect.as_ref().is_some_and(|u| u.scheme() == "https");
+    format!(
+        "{}={}; Path={}; Max-Age={}; HttpOnly; SameSite=Lax{}",
+        LOGIN_COOKIE,
+        value,
+        path,
+        max_age.as_secs(),
+        if secure { "; Secure" } else { "" }
+    )
+}
+
+/// Value of the cookie `name` in a `Cookie` request header.
+pub fn cookie_value<'a>(header: &'a str, name: &str) -> Option<&'a str> {
+    header.split(';').find_map(|pair| {
+        let (k, v) = pair.trim().split_once('=')?;
+        (k == name).then_some(v)
+    })
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+/// Resolve provider endpoints from config, falling back to (cached) discovery.
+pub async fn provider_metadata(
+    provider: &OidcProviderConfig,
+) -> Result<ProviderMetadata, BoxError> {
+    if let (Some(auth), Some(token), Some(jwks)) = (
+        &provider.authorization_endpoint,
+        &provider.token_endpoint,
+        &provider.jwks_uri,
+    ) {
+        return Ok(ProviderMetadata {
+            issuer: provider.issuer.clone(),
+            authorization_endpoint: auth.clone(),
+            token_endpoint: token.clone(),
+            jwks_uri: jwks.clone(),
+        });
+    }
+    if let Some(meta) = METADATA_CACHE.get(&provider.name) {
+        return Ok(meta.clone());
+    }
+
+    let url = format!(
+        "{}/.well-known/openid-configuration",
+        provider.issuer.trim_end_matches('/')
+    );
+    let meta: ProviderMetadata = HTTP_CLIENT
+        .get(&url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    // The discovery document must describe the configured issuer (OIDC Discovery §4.3)
+    if meta.issuer.trim_end_matches('/') != provider.issuer.trim_end_matches('/') {
+        return Err(BoxError::from(format!(
+            "discovered issuer {} does not match configured issuer {}",
+            meta.issuer, provider.issuer
+        )));
+    }
+    METADATA_CACHE.insert(provider.name.clone(), meta.clone());
+    Ok(meta)
+}
+
+/// Build the authorization request URL (authorization code flow with PKCE S256).
+pub fn authorization_url(
+    meta: &ProviderMetadata,
+    provider: &OidcProviderConfig,
+    state: &str,
+    pending: &PendingLogin,
+) -> Result<String, BoxError> {
+    let mut url = url::Url::parse(&meta.authorization_endpoint)?;
+    url.query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", &provider.client_id)
+        .append_pair("redirect_uri", &provider.redirect_uri)
+        .append_pair("scope", &provider.scopes.join(" "))
+        .append_pair("state", state)
+        .append_pair("nonce", &pending.nonce)
+        .append_pair("code_challenge", &pkce_challenge(&pending.code_verifier))
+        .append_pair("code_challenge_method", "S256");
+    Ok(url.to_string())
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+/// Exchange the authorization code for tokens and return the raw ID token.
+pub async fn exchange_code(
+    meta: &ProviderMetadata,
+    provider: &OidcProviderConfig,
+    code: &str,
+    code_verifier: &str,
+) -> Result<String, BoxError> {
+    let res = HTTP_CLIENT
+        .post(&meta.token_endpoint)
+        .form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", provider.redirect_uri.as_str()),
+            ("client_id", provider.client_id.as_str()),
+            ("client_secret", provider.client_secret.as_str()),
+            ("code_verifier", code_verifier),
+        ])
+        .send()
+        .await?;
+    if !res.status().is_success() {
+        let status = res.status();
+        let body = res.text().await.unwrap_or_default();
+        return Err(BoxError::from(format!(
+            "token endpoint returned {}: {}",
+            status, body
+        )));
+    }
+    let tokens: TokenResponse = res.json().await?;
+    Ok(tokens.id_token)
+}
+
+/// Claims we read from a verified ID token; everything else is kept in `extra`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdTokenClaims {
+    pub sub: String,
+    #[serde(default)]
+    pub nonce: Option<String>,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub email_verified: Option<bool>,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Provider signing keys, cached for `OIDC_JWKS_TTL_SECS` (default 3600). `refresh` skips
+/// the cache. The flag tells whether the keys were just fetched.
+async fn provider_jwks(jwks_uri: &str, refresh: bool) -> Result<(Arc<JwkSet>, bool), BoxError> {
+    if !refresh
+        && let Some(entry) = JWKS_CACHE.get(jwks_uri)
+        && entry.1.elapsed() < jwks_ttl()
+    {
+        return Ok((entry.0.clone(), false));
+    }
+    let jwks: Arc<JwkSet> = Arc::new(
+        HTTP_CLIENT
+            .get(jwks_uri)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?,
+    );
+    JWKS_CACHE.insert(jwks_uri.to_string(), (jwks.clone(), Instant::now()));
+    Ok((jwks, true))
+}
+
+fn find_jwk(jwks: &JwkSet, kid: Option<&str>) -> Option<Jwk> {
+    match kid {
+        Some(kid) => jwks.find(kid),
+        None if jwks.keys.len() == 1 => jwks.keys.first(),
+        None => None,
+    }
+    .cloned()
+}
+
+/// Verify signature, issuer, audience, expiry and nonce of an ID token.
+///
+/// Only the provider's `allowed_algorithms` are accepted. HMAC-signed tokens (HS*) are
+/// verified with the client secret; asymmetric ones with the provider's JWKS, selected by
+/// `kid` (an unknown `kid` refetches the keys once, for key rotation).
+pub async fn verify_id_token(
+    meta: &ProviderMetadata,
+    provider: &OidcProviderConfig,
+    id_token: &str,
+    expected_nonce: &str,
+) -> Result<IdTokenClaims, BoxError> {
+    let header = decode_header(id_token)?;
+    if !provider.allowed_algorithms.contains(&header.alg) {
+        return Err(BoxError::from(format!(
+            "ID token algorithm {:?} is not allowed for this provider",
+            header.alg
+        )));
+    }
+    let key = match header.alg {
+        Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => {
+            DecodingKey::from_secret(provider.client_secret.as_bytes())
+        }
+        _ => {
+            let kid = header.kid.as_deref();
+            let (jwks, fresh) = provider_jwks(&meta.jwks_uri, false).await?;
+            let jwk = match find_jwk(&jwks, kid) {
+                Some(jwk) => Some(jwk),
+                None if !fresh => find_jwk(&provider_jwks(&meta.jwks_uri, true).await?.0, kid),
+                None => None,
+            }
+            .ok_or_else(|| BoxError::from("no matching signing key in provider JWKS"))?;
+            DecodingKey::from_jwk(&jwk)?
+        }
+    };
+
+    let mut validation = Validation::new(header.alg);
+    validation.algorithms = provider.allowed_algorithms.clone();
+    validation.set_audience(&[&provider.client_id]);
+    validation.set_issuer(&[&meta.issuer]);
+    let claims = decode::<IdTokenClaims>(id_token, &key, &validation)?.claims;
+
+    if claims.nonce.as_deref() != Some(expected_nonce) {
+        return Err(BoxError::from("ID token nonce mismatch"));
+    }
+    Ok(claims)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pkce_challenge_rfc7636_vector() {
+        // Appendix B of RFC 7636
+        let verifier = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+        assert_eq!(
+            pkce_challenge(verifier),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    #[test]
+    fn test_login_cookie_roundtrip() {
+        let pending = begin_login("corp");
+        let sealed = seal_login(&pending, "secret").unwrap();
+        let opened = open_login(&sealed, "secret").expect("valid cookie");
+        assert_eq!(opened.state, pending.state);
+        assert_eq!(opened.provider, "corp");
+        assert_eq!(opened.code_verifier, pending.code_verifier);
+
+        // Another key, a tampered value or an expired login are rejected
+        assert!(open_login(&sealed, "other").is_none());
+        assert!(open_login(&format!("{}x", sealed), "secret").is_none());
+        let expired = PendingLogin {
+            exp: chrono::Utc::now().timestamp() - 1,
+            ..pending
+        };
+        assert!(open_login(&seal_login(&expired, "secret").unwrap(), "secret").is_none());
+    }
+
+    #[test]
+    fn test_cookie_value() {
+        let header = "theme=dark; oidc_login=abc.def; other=1";
+        assert_eq!(cookie_value(header, LOGIN_COOKIE), Some("abc.def"));
+        assert_eq!(cookie_value(header, "missing"), None);
+    }
+}
@@ -0,0 +1,374 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::sync::{Arc, RwLock};
+
+/// Rule effect. When several rules match, `deny` always wins over `allow`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Effect {
+    Allow,
+    Deny,
+}
+
+fn default_effect() -> Effect {
+    Effect::Deny
+}
+
+/// Comparison operators supported in rule conditions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConditionOp {
+    Eq,
+    Ne,
+    In,
+    NotIn,
+    Contains,
+    Exists,
+}
+
+/// A single condition, e.g. `principal.region eq resource.region`.
+///
+/// `attr` and `value_from` are dotted paths rooted at `principal`, `resource` or `action`.
+/// Exactly one of `value` (literal) or `value_from` (another attribute) is required,
+/// except for `exists` which takes neither. Missing attributes never match.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Condition {
+    pub attr: String,
+    pub op: ConditionOp,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value_from: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyRule {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub effect: Effect,
+    /// Action patterns: exact (`users:read`), prefix wildcard (`users:*`) or `*`.
+    pub actions: Vec<String>,
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicySet {
+    /// Effect applied when no rule matches (default `deny`).
+    #[serde(default = "default_effect")]
+    pub default_effect: Effect,
+    #[serde(default)]
+    pub rules: Vec<PolicyRule>,
+}
+
+/// Result of evaluating a request against the policy set.
+#[derive(Debug, Clone, Serialize)]
+pub struct Decision {
+    pub allowed: bool,
+    pub effect: Effect,
+    pub matched_rules: Vec<String>,
+    pub reason: String,
+}
+
+impl PolicySet {
+    /// Built-in policy used when no `POLICY_FILE` is configured: any authenticated
+    /// principal may perform every `users:*` action (the behavior before policies existed).
+    pub fn permissive_default() -> Self {
+        Self {
+            default_effect: Effect::Deny,
+            rules: vec![PolicyRule {
+                id: "authenticated-users-manage-users".to_string(),
+                description: Some("Default: authenticated users may manage users".to_string()),
+                effect: Effect::Allow,
+                actions: vec!["users:*".to_string()],
+                conditions: Vec::new(),
+            }],
+        }
+    }
+
+    /// Parse a policy document. `format` is `toml` or `json`.
+    pub fn parse(raw: &str, format: &str) -> Result<Self, String> {
+        let set: PolicySet = match format {
+            "toml" => toml::from_str(raw).map_err(|e| format!("invalid TOML policy: {}", e))?,
+            "json" => {
+                serde_json::from_str(raw).map_err(|e| format!("invalid JSON policy: {}", e))?
+            }
+            other => return Err(format!("unsupported policy format: {}", other)),
+        };
+        set.validate()?;
+        Ok(set)
+    }
+
+    /// Reject rules that can never be evaluated correctly so mistakes surface at startup.
+    pub fn validate(&self) -> Result<(), String> {
+        for rule in &self.rules {
+            if rule.id.trim().is_empty() {
+                return Err("policy rule is missing an id".to_string());
+            }
+            if rule.actions.is_empty() {
+                return Err(format!("policy rule '{}' has no actions", rule.id));
+            }
+            for cond in &rule.conditions {
+                if !is_valid_path(&cond.attr) {
+                    return Err(format!(
+                        "policy rule '{}' has invalid attribute path '{}'",
+                        rule.id, cond.attr
+                    ));
+                }
+                match (cond.op, &cond.value, &cond.value_from) {
+                    (ConditionOp::Exists, None, None) => {}
+                    (ConditionOp::Exists, _, _) => {
+                        return Err(format!(
+                            "policy rule '{}': 'exists' takes no value",
+                            rule.id
+                        ));
+                    }
+                    (_, Some(_), None) => {}
+                    (_, None, Some(path)) if is_valid_path(path) => {}
+                    _ => {
+                        return Err(format!(
+                            "policy rule '{}': condition on '{}' needs exactly one of 'value' or a valid 'value_from'",
+                            rule.id, cond.attr
+                        ));
+                    }
+                }
+                if matches!(cond.op, ConditionOp::In | ConditionOp::NotIn)
+                    && cond.value.as_ref().is_some_and(|v| !v.is_array())
+                {
+                    return Err(format!(
+                        "policy rule '{}': 'in'/'not_in' require an array value",
+                        rule.id
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Evaluate `action` for `principal` on `resource` (deny-overrides).
+    pub fn evaluate(&self, principal: &Value, action: &str, resource: &Value) -> Decision {
+        let ctx = json!({ "principal": principal, "resource": resource, "action": action });
+
+        let mut allow_matches = Vec::new();
+        let mut deny_matches = Vec::new();
+        for rule in &self.rules {
+            if !rule.actions.iter().any(|p| action_matches(p, action)) {
+                continue;
+            }
+            if !rule.conditions.iter().all(|c| condition_holds(c, &ctx)) {
+                continue;
+            }
+            match rule.effect {
+                Effect::Allow => allow_matches.push(rule.id.clone()),
+                Effect::Deny => deny_matches.push(rule.id.clone()),
+            }
+        }
+
+        if !deny_matches.is_empty() {
+            Decision {
+                allowed: false,
+                effect: Effect::Deny,
+                reason: format!("denied by rule '{}'", deny_matches[0]),
+                matched_rules: deny_matches,
+            }
+        } else if !allow_matches.is_empty() {
+            Decision {
+                allowed: true,
+                effect: Effect::Allow,
+                reason: format!("allowed by rule '{}'", allow_matches[0]),
+                matched_rules: allow_matches,
+            }
+        } else {
+            Decision {
+                allowed: self.default_effect == Effect::Allow,
+                effect: self.default_effect,
+                matched_rules: Vec::new(),
+                reason: "no rule matched; default effect applied".to_string(),
+            }
+        }
+    }
+}
+
+fn is_valid_path(path: &str) -> bool {
+    let root = path.split('.').next().unwrap_or("");
+    matches!(root, "principal" | "resource" | "action") && !path.split('.').any(str::is_empty)
+}
+
+fn action_matches(pattern: &str, action: &str) -> bool {
+    if pattern == "*" {
+        return true;
+    }
+    match pattern.strip_suffix('*') {
+        Some(prefix) => action.starts_with(prefix),
+        None => pattern == action,
+    }
+}
+
+fn lookup<'a>(ctx: &'a Value, path: &str) -> Option<&'a Value> {
+    let mut cur = ctx;
+    for seg in path.split('.') {
+        cur = cur.get(seg)?;
+    }
+    if cur.is_null() { None } else { Some(cur) }
+}
+
+fn condition_holds(cond: &Condition, ctx: &Value) -> bool {
+    let left = lookup(ctx, &cond.attr);
+    if cond.op == ConditionOp::Exists {
+        return left.is_some();
+    }
+    let right = match (&cond.value, &cond.value_from) {
+        (Some(v), _) => Some(v),
+        (None, Some(path)) => lookup(ctx, path),
+        (None, None) => None,
+    };
+    let (Some(left), Some(right)) = (left, right) else {
+        return false;
+    };
+    match cond.op {
+        ConditionOp::Eq => left == right,
+        ConditionOp::Ne => left != right,
+        ConditionOp::In => right.as_array().is_some_and(|arr| arr.contains(left)),
+        ConditionOp::NotIn => right.as_array().is_some_and(|arr| !arr.contains(left)),
+        ConditionOp::Contains => match left {
+            Value::Array(arr) => arr.contains(right),
+            Value::String(s) => right.as_str().is_some_and(|r| s.contains(r)),
+            _ => false,
+        },
+        ConditionOp::Exists => unreachable!(),
+    }
+}
+
+static POLICIES: Lazy<RwLock<Arc<PolicySet>>> =
+    Lazy::new(|| RwLock::new(Arc::new(PolicySet::permissive_default())));
+
+/// Replace the active policy set (called at startup, also useful for tests).
+pub fn set_policies(set: PolicySet) {
+    if let Ok(mut guard) = POLICIES.write() {
+        tracing::info!("policy: loaded {} rule(s)", set.rules.len());
+        *guard = Arc::new(set);
+    } else {
+        tracing::warn!("policy: failed to acquire write lock to set policies");
+    }
+}
+
+/// Snapshot of the active policy set.
+pub fn current_policies() -> Arc<PolicySet> {
+    match POLICIES.read() {
+        Ok(guard) => guard.clone(),
+        Err(e) => {
+            tracing::warn!("policy: lock poisoned, using previous policies: {}", e);
+            e.into_inner().clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUPPORT_POLICY: &str = r#"
+        default_effect = "deny"
+
+        [[rules]]
+        id = "admins"
+        effect = "allow"
+        actions = ["*"]
+        conditions = [{ attr = "principal.role", op = "eq", value = "admin" }]
+
+        [[rules]]
+        id = "support-read-own-region"
+        effect = "allow"
+        actions = ["users:read"]
+        conditions = [
+            { attr = "principal.role", op = "eq", value = "support" },
+            { attr = "principal.region", op = "eq", value_from = "resource.region" },
+        ]
+
+        [[rules]]
+        id = "support-never-deletes"
+        effect = "deny"
+        actions = ["users:delete"]
+        conditions = [{ attr = "principal.role", op = "eq", value = "support" }]
+    "#;
+
+    #[test]
+    fn test_support_agent_reads_own_region_only() {
+        let set = PolicySet::parse(SUPPORT_POLICY, "toml").expect("parse");
+        let agent = json!({ "id": 7, "role": "support", "region": "eu" });
+
+        let eu_user = json!({ "id": 1, "region": "eu" });
+        let us_user = json!({ "id": 2, "region": "us" });
+        assert!(set.evaluate(&agent, "users:read", &eu_user).allowed);
+        assert!(!set.evaluate(&agent, "users:read", &us_user).allowed);
+
+        // Listings are authorized once, without a resource
+        assert!(!set.evaluate(&agent, "users:list", &json!({})).allowed);
+
+        let d = set.evaluate(&agent, "users:delete", &eu_user);
+        assert!(!d.allowed);
+        assert_eq!(d.matched_rules, vec!["support-never-deletes".to_string()]);
+    }
+
+    #[test]
+    fn test_deny_overrides_allow() {
+        let raw = r#"
+            [[rules]]
+            id = "everyone-manages-users"
+            effect = "allow"
+            actions = ["users:*"]
+
+            [[rules]]
+            id = "support-never-deletes"
+            effect = "deny"
+            actions = ["users:delete"]
+            conditions = [{ attr = "principal.role", op = "eq", value = "support" }]
+        "#;
+        let set = PolicySet::parse(raw, "toml").expect("parse");
+        let agent = json!({ "role": "support" });
+        assert!(set.evaluate(&agent, "users:update", &json!({})).allowed);
+        let d = set.evaluate(&agent, "users:delete", &json!({}));
+        assert!(!d.allowed);
+        assert_eq!(d.effect, Effect::Deny);
+    }
+
+    #[test]
+    fn test_missing_attribute_never_matches() {
+        let set = PolicySet::parse(SUPPORT_POLICY, "toml").expect("parse");
+        let agent = json!({ "role": "support" });
+        assert!(
+            !set.evaluate(&agent, "users:read", &json!({ "region": "eu" }))
+                .allowed
+        );
+    }
+
+    #[test]
+    fn test_json_format_and_validation() {
+        let ok = r#"{ "rules": [{ "id": "r", "effect": "allow", "actions": ["users:*"],
+            "conditions": [{ "attr": "principal.groups", "op": "contains", "value": "ops" }] }] }"#;
+        let set = PolicySet::parse(ok, "json").expect("parse");
+        assert!(
+            set.evaluate(&json!({ "groups": ["ops"] }), "users:update", &json!({}))
+                .allowed
+        );
+
+        let bad = r#"{ "rules": [{ "id": "r", "effect": "allow", "actions": ["users:*"],
+            "conditions": [{ "attr": "principal.role", "op": "eq" }] }] }"#;
+        assert!(PolicySet::parse(bad, "json").is_err());
+    }
+
+    #[test]
+    fn test_permissive_default_allows_users_actions() {
+        let set = PolicySet::permissive_default();
+        assert!(
+            set.evaluate(&json!({ "id": 1 }), "users:delete", &json!({}))
+                .allowed
+        );
+        assert!(
+            !set.evaluate(&json!({ "id": 1 }), "invitations:create", &json!({}))
+                .allowed
+        );
+    }
+}
@@ -0,0 +1,515 @@
+use axum::http::{StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use chrono::{DateTime, Utc};
+use serde_json::{Value, json};
+use sqlx::{MySql, QueryBuilder};
+
+use crate::utils::user_query::escape_like;
+
+/// Core User resource schema (RFC 7643 §4.1).
+pub const SCHEMA_USER: &str = "urn:ietf:params:scim:schemas:core:2.0:User";
+/// Service provider configuration schema (RFC 7643 §5).
+pub const SCHEMA_SERVICE_PROVIDER_CONFIG: &str =
+    "urn:ietf:params:scim:schemas:core:2.0:ServiceProviderConfig";
+/// List response message (RFC 7644 §3.4.2).
+pub const SCHEMA_LIST_RESPONSE: &str = "urn:ietf:params:scim:api:messages:2.0:ListResponse";
+/// PATCH request message (RFC 7644 §3.5.2).
+pub const SCHEMA_PATCH_OP: &str = "urn:ietf:params:scim:api:messages:2.0:PatchOp";
+/// Error response message (RFC 7644 §3.12).
+pub const SCHEMA_ERROR: &str = "urn:ietf:params:scim:api:messages:2.0:Error";
+
+/// Maximum number of resources returned by one list request.
+pub const MAX_RESULTS: i64 = 200;
+
+/// A SCIM JSON body with the `application/scim+json` media type.
+pub struct ScimJson(pub StatusCode, pub Value);
+
+impl IntoResponse for ScimJson {
+    fn into_response(self) -> Response {
+        (
+            self.0,
+            [(header::CONTENT_TYPE, "application/scim+json")],
+            self.1.to_string(),
+        )
+            .into_response()
+    }
+}
+
+/// SCIM error response (RFC 7644 §3.12) used instead of the `ApiResponse` envelope.
+#[derive(Debug)]
+pub struct ScimError {
+    pub status: StatusCode,
+    pub scim_type: Option<&'static str>,
+    pub detail: String,
+}
+
+impl ScimError {
+    pub fn new(
+        status: StatusCode,
+        scim_type: Option<&'static str>,
+        detail: impl Into<String>,
+    ) -> Self {
+        Self {
+            status,
+            scim_type,
+            detail: detail.into(),
+        }
+    }
+
+    pub fn bad_request(scim_type: &'static str, detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, Some(scim_type), detail)
+    }
+
+    pub fn not_found() -> Self {
+        Self::new(StatusCode::NOT_FOUND, None, "Resource not found")
+    }
+
+    pub fn database(context: &str, e: sqlx::Error) -> Self {
+        tracing::error!("scim: {}: {}", context, e);
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, None, context)
+    }
+}
+
+impl IntoResponse for ScimError {
+    fn into_response(self) -> Response {
+        let mut body = json!({
+            "schemas": [SCHEMA_ERROR],
+            "status": self.status.as_u16().to_string(),
+            "detail": self.detail,
+        });
+        if let Some(t) = self.scim_type {
+            body["scimType"] = json!(t);
+        }
+        ScimJson(self.status, body).into_response()
+    }
+}
+
+/// Comparison operators of the SCIM filter grammar (RFC 7644 §3.4.2.2).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Co,
+    Sw,
+    Ew,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl CompareOp {
+    fn parse(s: &str) -> Option<Self> {
+        Some(match s.to_ascii_lowercase().as_str() {
+            "eq" => Self::Eq,
+            "ne" => Self::Ne,
+            "co" => Self::Co,
+            "sw" => Self::Sw,
+            "ew" => Self::Ew,
+            "gt" => Self::Gt,
+            "ge" => Self::Ge,
+            "lt" => Self::Lt,
+            "le" => Self::Le,
+            _ => return None,
+        })
+    }
+}
+
+/// Parsed SCIM filter expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    Compare {
+        attr: String,
+        op: CompareOp,
+        value: Value,
+    },
+    Present(String),
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Literal(Value),
+    Open,
+    Close,
+}
+
+fn invalid_filter(detail: impl Into<String>) -> ScimError {
+    ScimError::bad_request("invalidFilter", detail)
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, ScimError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '(' => {
+                tokens.push(Token::Open);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::Close);
+                i += 1;
+            }
+            '"' => {
+                // JSON string literal, including escapes
+                let start = i;
+                i += 1;
+                while i < chars.len() && chars[i] != '"' {
+                    if chars[i] == '\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                if i >= chars.len() {
+                    return Err(invalid_filter("Unterminated string in filter"));
+                }
+                i += 1;
+                let raw: String = chars[start..i].iter().collect();
+                let value: Value = serde_json::from_str(&raw)
+                    .map_err(|_| invalid_filter("Invalid string in filter"))?;
+                tokens.push(Token::Literal(value));
+            }
+            _ => {
+                let start = i;
+                while i < chars.len()
+                    && !chars[i].is_whitespace()
+                    && chars[i] != '('
+                    && chars[i] != ')'
+                    && chars[i] != '"'
+                {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                if word.contains('[') {
+                    return Err(invalid_filter(
+                        "Complex attribute filters are not supported",
+                    ));
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.tokens.get(self.pos), Some(Token::Word(w)) if w.eq_ignore_ascii_case(keyword))
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let t = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        t
+    }
+
+    fn expr(&mut self) -> Result<Filter, ScimError> {
+        let mut left = self.term()?;
+        while self.peek_keyword("or") {
+            self.pos += 1;
+            left = Filter::Or(Box::new(left), Box::new(self.term()?));
+        }
+        Ok(left)
+    }
+
+    fn term(&mut self) -> Result<Filter, ScimError> {
+        let mut left = self.factor()?;
+        while self.peek_keyword("and") {
+            self.pos += 1;
+            left = Filter::And(Box::new(left), Box::new(self.factor()?));
+        }
+        Ok(left)
+    }
+
+    fn factor(&mut self) -> Result<Filter, ScimError> {
+        if self.peek_keyword("not") {
+            self.pos += 1;
+            if self.next() != Some(Token::Open) {
+                return Err(invalid_filter("Expected '(' after not"));
+            }
+            let inner = self.group_rest()?;
+            return Ok(Filter::Not(Box::new(inner)));
+        }
+        match self.next() {
+            Some(Token::Open) => self.group_rest(),
+            Some(Token::Word(attr)) => {
+                let attr = normalize_attr(&attr);
+                match self.next() {
+                    Some(Token::Word(op)) if op.eq_ignore_ascii_case("pr") => {
+                        Ok(Filter::Present(attr))
+                    }
+                    Some(Token::Word(op)) => {
+                        let op = CompareOp::parse(&op)
+                            .ok_or_else(|| invalid_filter(format!("Unknown operator '{}'", op)))?;
+                        let value = match self.next() {
+                            Some(Token::Literal(v)) => v,
+                            Some(Token::Word(w)) => match w.as_str() {
+                                "true" => Value::Bool(true),
+                                "false" => Value::Bool(false),
+                                "null" => Value::Null,
+                                _ => serde_json::from_str::<serde_json::Number>(&w)
+                                    .map(Value::Number)
+                                    .map_err(|_| {
+                                        invalid_filter(format!("Invalid value '{}'", w))
+                                    })?,
+                            },
+                            _ => return Err(invalid_filter("Expected a comparison value")),
+                        };
+                        Ok(Filter::Compare { attr, op, value })
+                    }
+                    _ => Err(invalid_filter("Expected an operator")),
+                }
+            }
+            _ => Err(invalid_filter("Expected an attribute expression")),
+        }
+    }
+
+    // Parse `expr )` after an opening parenthesis
+    fn group_rest(&mut self) -> Result<Filter, ScimError> {
+        let inner = self.expr()?;
+        if self.next() != Some(Token::Close) {
+            return Err(invalid_filter("Expected ')'"));
+        }
+        Ok(inner)
+    }
+}
+
+/// Attribute names are case-insensitive and may carry the schema URN prefix.
+pub fn normalize_attr(attr: &str) -> String {
+    let prefix = format!("{}:", SCHEMA_USER);
+    let attr = if attr.len() > prefix.len() && attr[..prefix.len()].eq_ignore_ascii_case(&prefix) {
+        &attr[prefix.len()..]
+    } else {
+        attr
+    };
+    attr.to_ascii_lowercase()
+}
+
+/// Parse a SCIM filter string, e.g. `userName eq "bjensen@example.com" and active eq true`.
+pub fn parse_filter(input: &str) -> Result<Filter, ScimError> {
+    let mut parser = Parser {
+        tokens: tokenize(input)?,
+        pos: 0,
+    };
+    let filter = parser.expr()?;
+    if parser.pos < parser.tokens.len() {
+        return Err(invalid_filter("Unexpected trailing tokens in filter"));
+    }
+    Ok(filter)
+}
+
+#[derive(Clone, Copy)]
+enum Column {
+    Text(&'static str),
+    Id,
+    Active,
+    Time(&'static str),
+}
+
+// SCIM User attributes (lowercased) mapped onto the `users` table
+fn column_for(attr: &str) -> Option<Column> {
+    Some(match attr {
+        "id" => Column::Id,
+        "username" | "emails" | "emails.value" => Column::Text("email"),
+        "displayname" | "name.formatted" => Column::Text("name"),
+        "externalid" => Column::Text("external_id"),
+        "active" => Column::Active,
+        "meta.created" => Column::Time("created_at"),
+        "meta.lastmodified" => Column::Time("updated_at"),
+        _ => return None,
+    })
+}
+
+fn sql_op(op: CompareOp) -> &'static str {
+    match op {
+        CompareOp::Eq => " = ",
+        CompareOp::Ne => " <> ",
+        CompareOp::Gt => " > ",
+        CompareOp::Ge => " >= ",
+        CompareOp::Lt => " < ",
+        CompareOp::Le => " <= ",
+        CompareOp::Co | CompareOp::Sw | CompareOp::Ew => " LIKE ",
+    }
+}
+
+/// Append a filter to `qb` as a parameterized SQL condition over the `users` table.
+pub fn push_filter_sql(qb: &mut QueryBuilder<'_, MySql>, filter: &Filter) -> Result<(), ScimError> {
+    match filter {
+        Filter::And(a, b) | Filter::Or(a, b) => {
+            qb.push("(");
+            push_filter_sql(qb, a)?;
+            qb.push(if matches!(filter, Filter::And(..)) {
+                " AND "
+            } else {
+                " OR "
+            });
+            push_filter_sql(qb, b)?;
+            qb.push(")");
+        }
+        Filter::Not(inner) => {
+            qb.push("NOT (");
+            push_filter_sql(qb, inner)?;
+            qb.push(")");
+        }
+        Filter::Present(attr) => {
+            match column_for(attr).ok_or_else(|| unknown_attr(attr))? {
+                Column::Text(col) => {
+                    qb.push(format!("({col} IS NOT NULL AND {col} <> '')"));
+                }
+                // Always-present attributes
+                Column::Id | Column::Active | Column::Time(_) => {
+                    qb.push("TRUE");
+                }
+            }
+        }
+        Filter::Compare { attr, op, value } => {
+            match column_for(attr).ok_or_else(|| unknown_attr(attr))? {
+                Column::Text(col) => {
+                    let s = value
+                        .as_str()
+                        .ok_or_else(|| invalid_filter(format!("'{}' requires a string", attr)))?;
+                    qb.push(col).push(sql_op(*op));
+                    match op {
+                        CompareOp::Co => qb.push_bind(format!("%{}%", escape_like(s))),
+                        CompareOp::Sw => qb.push_bind(format!("{}%", escape_like(s))),
+                        CompareOp::Ew => qb.push_bind(format!("%{}", escape_like(s))),
+                        _ => qb.push_bind(s.to_string()),
+                    };
+                }
+                Column::Id => {
+                    if !matches!(op, CompareOp::Eq | CompareOp::Ne) {
+                        return Err(invalid_filter("'id' supports only eq and ne"));
+                    }
+                    // ids are exposed as strings; a non-numeric id matches nothing
+                    match value.as_str().and_then(|s| s.parse::<i64>().ok()) {
+                        Some(id) => {
+                            qb.push("id").push(sql_op(*op)).push_bind(id);
+                        }
+                        None => {
+                            qb.push(if *op == CompareOp::Eq {
+                                "FALSE"
+                            } else {
+                                "TRUE"
+                            });
+                        }
+                    }
+                }
+                Column::Active => {
+                    let b = value
+                        .as_bool()
+                        .ok_or_else(|| invalid_filter("'active' requires a boolean"))?;
+                    if !matches!(op, CompareOp::Eq | CompareOp::Ne) {
+                        return Err(invalid_filter("'active' supports only eq and ne"));
+                    }
+                    qb.push("active").push(sql_op(*op)).push_bind(b);
+                }
+                Column::Time(col) => {
+                    let t = value
+                        .as_str()
+                        .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+                        .map(|t| t.with_timezone(&Utc))
+                        .ok_or_else(|| {
+                            invalid_filter(format!("'{}' requires an RFC 3339 date-time", attr))
+                        })?;
+                    if matches!(op, CompareOp::Co | CompareOp::Sw | CompareOp::Ew) {
+                        return Err(invalid_filter(format!(
+                            "'{}' does not support substring operators",
+                            attr
+                        )));
+                    }
+                    qb.push(col).push(sql_op(*op)).push_bind(t);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn unknown_attr(attr: &str) -> ScimError {
+    invalid_filter(format!("Filtering on '{}' is not supported", attr))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_simple_and_precedence() {
+        let f = parse_filter(r#"userName eq "a@example.com" or active eq true and externalId pr"#)
+            .expect("parse");
+        assert_eq!(
+            f,
+            Filter::Or(
+                Box::new(Filter::Compare {
+                    attr: "username".into(),
+                    op: CompareOp::Eq,
+                    value: json!("a@example.com"),
+                }),
+                Box::new(Filter::And(
+                    Box::new(Filter::Compare {
+                        attr: "active".into(),
+                        op: CompareOp::Eq,
+                        value: json!(true),
+                    }),
+                    Box::new(Filter::Present("externalid".into())),
+                )),
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_groups_not_and_urn_prefix() {
+        let f =
+            parse_filter(r#"not (urn:ietf:params:scim:schemas:core:2.0:User:userName SW "j\"o")"#)
+                .expect("parse");
+        assert_eq!(
+            f,
+            Filter::Not(Box::new(Filter::Compare {
+                attr: "username".into(),
+                op: CompareOp::Sw,
+                value: json!("j\"o"),
+            }))
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for bad in [
+            "",
+            "userName",
+            r#"userName xx "a""#,
+            r#"(userName eq "a""#,
+            r#"userName eq "a" extra"#,
+            r#"emails[type eq "work"].value eq "a""#,
+        ] {
+            let err = parse_filter(bad).expect_err(bad);
+            assert_eq!(err.scim_type, Some("invalidFilter"));
+        }
+    }
+
+    #[test]
+    fn test_filter_sql_is_parameterized() {
+        let f = parse_filter(r#"userName co "100%" and not (active eq false)"#).expect("parse");
+        let mut qb = QueryBuilder::<MySql>::new("SELECT id FROM users WHERE ");
+        push_filter_sql(&mut qb, &f).expect("sql");
+        assert_eq!(
+            qb.sql(),
+            "SELECT id FROM users WHERE (email LIKE ? AND NOT (active = ?))"
+        );
+
+        let f = parse_filter(r#"nickName eq "x""#).expect("parse");
+        assert!(push_filter_sql(&mut qb, &f).is_err());
+    }
+}
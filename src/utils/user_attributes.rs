@@ -0,0 +1,544 @@
+use chrono::NaiveDate;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+use std::sync::{Arc, RwLock};
+
+/// Value type of a custom attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttributeType {
+    String,
+    Integer,
+    Number,
+    Boolean,
+    /// `YYYY-MM-DD` string
+    Date,
+}
+
+/// Who may read (and write) an attribute.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Visibility {
+    /// Anyone allowed to read the user
+    #[default]
+    Public,
+    /// The user themself and admins
+    Private,
+    /// Admins only
+    Admin,
+}
+
+/// One custom attribute and its validation rules.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AttributeDef {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: AttributeType,
+    /// Must be set by callers that can see it when they write the attributes
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default)]
+    pub visibility: Visibility,
+    /// Length bounds for strings, in characters
+    #[serde(default)]
+    pub min_length: Option<usize>,
+    #[serde(default)]
+    pub max_length: Option<usize>,
+    /// Bounds for integers and numbers (inclusive)
+    #[serde(default)]
+    pub min: Option<f64>,
+    #[serde(default)]
+    pub max: Option<f64>,
+    /// Regular expression a string must match in full
+    #[serde(default)]
+    pub pattern: Option<String>,
+    /// Closed list of accepted values
+    #[serde(default, rename = "enum")]
+    pub allowed: Vec<Value>,
+    #[serde(skip)]
+    regex: Option<Regex>,
+}
+
+/// Attributes of the user making a request, deciding which custom attributes it sees.
+#[derive(Debug, Clone, Copy)]
+pub struct Viewer {
+    pub user_id: i64,
+    pub admin: bool,
+}
+
+impl Viewer {
+    /// Whether the viewer may read and write an attribute of `subject` (`None` for a user
+    /// that is being created).
+    pub fn can_access(&self, visibility: Visibility, subject: Option<i64>) -> bool {
+        match visibility {
+            Visibility::Public => true,
+            Visibility::Private => self.admin || subject == Some(self.user_id),
+            Visibility::Admin => self.admin,
+        }
+    }
+}
+
+/// Server-side schema of the custom attributes stored in `users.attributes`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AttributeSchema {
+    #[serde(default)]
+    pub attributes: Vec<AttributeDef>,
+}
+
+fn valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    name.len() <= 64
+        && chars.next().is_some_and(|c| c.is_ascii_lowercase())
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+impl AttributeDef {
+    /// Check a submitted value against the type and rules (error message on failure).
+    pub fn check(&self, value: &Value) -> Result<(), String> {
+        match self.kind {
+            AttributeType::String => {
+                let s = value.as_str().ok_or("Must be a string")?;
+                let len = s.chars().count();
+                if let Some(min) = self.min_length
+                    && len < min
+                {
+                    return Err(format!("Must be at least {} characters long", min));
+                }
+                if let Some(max) = self.max_length
+                    && len > max
+                {
+                    return Err(format!("Must be at most {} characters long", max));
+                }
+                if let Some(re) = &self.regex
+                    && !re.is_match(s)
+                {
+                    return Err("Has an invalid format".to_string());
+                }
+            }
+            AttributeType::Integer | AttributeType::Number => {
+                let n = if self.kind == AttributeType::Integer {
+                    value.as_i64().ok_or("Must be an integer")? as f64
+                } else {
+                    value.as_f64().ok_or("Must be a number")?
+                };
+                if let Some(min) = self.min
+                    && n < min
+                {
+                    return Err(format!("Must be at least {}", min));
+                }
+                if let Some(max) = self.max
+                    && n > max
+                {
+                    return Err(format!("Must be at most {}", max));
+                }
+            }
+            AttributeType::Boolean => {
+                value.as_bool().ok_or("Must be a boolean")?;
+            }
+            AttributeType::Date => {
+                let s = value.as_str().ok_or("Must be a YYYY-MM-DD date")?;
+                NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                    .map_err(|_| "Must be a YYYY-MM-DD date")?;
+            }
+        }
+        if !self.allowed.is_empty() && !self.allowed.iter().any(|a| same_value(a, value)) {
+            return Err(format!(
+                "Must be one of: {}",
+                self.allowed
+                    .iter()
+                    .map(Value::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        Ok(())
+    }
+
+    /// Typed value of a query string filter (`attr.<name>=<raw>`).
+    pub fn parse_filter(&self, raw: &str) -> Result<Value, String> {
+        let value = match self.kind {
+            AttributeType::String | AttributeType::Date => json!(raw),
+            AttributeType::Integer => json!(raw.parse::<i64>().map_err(|_| "Must be an integer")?),
+            AttributeType::Number => json!(raw.parse::<f64>().map_err(|_| "Must be a number")?),
+            AttributeType::Boolean => match raw {
+                "true" => json!(true),
+                "false" => json!(false),
+                _ => return Err("Must be true or false".to_string()),
+            },
+        };
+        if self.kind == AttributeType::Date {
+            self.check(&value)?;
+        }
+        Ok(value)
+    }
+
+    /// Reject rules that do not apply to the type so mistakes surface at startup.
+    fn validate(&mut self) -> Result<(), String> {
+        let is_string = self.kind == AttributeType::String;
+        let is_numeric = matches!(self.kind, AttributeType::Integer | AttributeType::Number);
+        if !is_string
+            && (self.min_length.is_some() || self.max_length.is_some() || self.pattern.is_some())
+        {
+            return Err(format!(
+                "attribute '{}': min_length, max_length and pattern only apply to strings",
+                self.name
+            ));
+        }
+        if !is_numeric && (self.min.is_some() || self.max.is_some()) {
+            return Err(format!(
+                "attribute '{}': min and max only apply to integers and numbers",
+                self.name
+            ));
+        }
+        if let Some(pattern) = &self.pattern {
+            // Anchored so the whole value has to match
+            let re = Regex::new(&format!("^(?:{})$", pattern))
+                .map_err(|e| format!("attribute '{}': invalid pattern: {}", self.name, e))?;
+            self.regex = Some(re);
+        }
+        let rules_only = AttributeDef {
+            allowed: Vec::new(),
+            ..self.clone()
+        };
+        for value in &self.allowed {
+            rules_only.check(value).map_err(|e| {
+                format!(
+                    "attribute '{}': enum value {} is invalid: {}",
+                    self.name, value, e
+                )
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// Compare JSON values, treating integers and floats with the same value as equal.
+fn same_value(a: &Value, b: &Value) -> bool {
+    match (a.as_f64(), b.as_f64()) {
+        (Some(x), Some(y)) => x == y,
+        _ => a == b,
+    }
+}
+
+impl AttributeSchema {
+    /// Parse an attribute schema document (`toml` or `json`) and validate each definition.
+    pub fn parse(raw: &str, format: &str) -> Result<Self, String> {
+        let mut schema: AttributeSchema = match format {
+            "toml" => toml::from_str(raw).map_err(|e| format!("invalid TOML attributes: {}", e))?,
+            "json" => {
+                serde_json::from_str(raw).map_err(|e| format!("invalid JSON attributes: {}", e))?
+            }
+            other => return Err(format!("unsupported attributes format: {}", other)),
+        };
+        let mut seen: Vec<String> = Vec::new();
+        for def in &mut schema.attributes {
+            if !valid_name(&def.name) {
+                return Err(format!(
+                    "invalid attribute name '{}' (lowercase letters, digits and _)",
+                    def.name
+                ));
+            }
+            if seen.contains(&def.name) {
+                return Err(format!("duplicate attribute '{}'", def.name));
+            }
+            seen.push(def.name.clone());
+            def.validate()?;
+        }
+        Ok(schema)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&AttributeDef> {
+        self.attributes.iter().find(|d| d.name == name)
+    }
+
+    /// The stored attributes of `subject` that `viewer` may see (always an object).
+    ///
+    /// Values of attributes no longer in the schema are kept in storage but never shown.
+    pub fn visible(&self, stored: Option<&Value>, viewer: &Viewer, subject: i64) -> Value {
+        let mut out = Map::new();
+        if let Some(Value::Object(stored)) = stored {
+            for def in &self.attributes {
+                if viewer.can_access(def.visibility, Some(subject))
+                    && let Some(value) = stored.get(&def.name)
+                {
+                    out.insert(def.name.clone(), value.clone());
+                }
+            }
+        }
+        Value::Object(out)
+    }
+
+    /// Text indexed for full-text search: the values of public string attributes (`None` when
+    /// there are none). Hidden attributes are left out so search hits cannot reveal them.
+    pub fn search_text(&self, stored: Option<&Value>) -> Option<String> {
+        let Some(Value::Object(stored)) = stored else {
+            return None;
+        };
+        let values: Vec<&str> = self
+            .attributes
+            .iter()
+            .filter(|d| d.visibility == Visibility::Public && d.kind == AttributeType::String)
+            .filter_map(|d| stored.get(&d.name)?.as_str())
+            .collect();
+        if values.is_empty() {
+            None
+        } else {
+            Some(values.join(" "))
+        }
+    }
+
+    /// Apply submitted attributes on top of the stored ones.
+    ///
+    /// `submitted` replaces everything the viewer can see; attributes hidden from the viewer
+    /// keep their stored value (and are not checked against `required`), `null` removes an
+    /// attribute. Errors are keyed
+    /// `attributes.<name>` like the other validation errors.
+    pub fn apply(
+        &self,
+        stored: Option<&Value>,
+        submitted: &Map<String, Value>,
+        viewer: &Viewer,
+        subject: Option<i64>,
+    ) -> Result<Value, Map<String, Value>> {
+        let mut out = Map::new();
+        if let Some(Value::Object(stored)) = stored {
+            for (name, value) in stored {
+                let hidden = self
+                    .get(name)
+                    .is_none_or(|d| !viewer.can_access(d.visibility, subject));
+                if hidden {
+                    out.insert(name.clone(), value.clone());
+                }
+            }
+        }
+
+        let mut errors = Map::new();
+        for (name, value) in submitted {
+            let key = format!("attributes.{}", name);
+            let def = match self.get(name) {
+                Some(d) if viewer.can_access(d.visibility, subject) => d,
+                _ => {
+                    errors.insert(key, json!(["Unknown attribute"]));
+                    continue;
+                }
+            };
+            if value.is_null() {
+                continue;
+            }
+            match def.check(value) {
+                Ok(()) => {
+                    out.insert(name.clone(), value.clone());
+                }
+                Err(msg) => {
+                    errors.insert(key, json!([msg]));
+                }
+            }
+        }
+        for def in self
+            .attributes
+            .iter()
+            .filter(|d| d.required && viewer.can_access(d.visibility, subject))
+        {
+            let key = format!("attributes.{}", def.name);
+            if !out.contains_key(&def.name) && !errors.contains_key(&key) {
+                errors.insert(key, json!(["Attribute is required"]));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(Value::Object(out))
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+static SCHEMA: Lazy<RwLock<Arc<AttributeSchema>>> =
+    Lazy::new(|| RwLock::new(Arc::new(AttributeSchema::default())));
+
+/// Replace the active attribute schema (called at startup, also useful for tests).
+pub fn set_attribute_schema(schema: AttributeSchema) {
+    if let Ok(mut guard) = SCHEMA.write() {
+        *guard = Arc::new(schema);
+    } else {
+        tracing::warn!("attributes: failed to acquire write lock to set schema");
+    }
+}
+
+/// Snapshot of the active attribute schema (empty unless configured).
+pub fn current_attribute_schema() -> Arc<AttributeSchema> {
+    match SCHEMA.read() {
+        Ok(guard) => guard.clone(),
+        Err(e) => e.into_inner().clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEMA_TOML: &str = r#"
+        [[attributes]]
+        name = "department"
+        type = "string"
+        required = true
+        enum = ["engineering", "sales"]
+
+        [[attributes]]
+        name = "phone"
+        type = "string"
+        visibility = "private"
+        pattern = "\\+[0-9]{6,15}"
+
+        [[attributes]]
+        name = "employee_number"
+        type = "integer"
+        visibility = "admin"
+        min = 1
+    "#;
+
+    const USER: Viewer = Viewer {
+        user_id: 7,
+        admin: false,
+    };
+    const ADMIN: Viewer = Viewer {
+        user_id: 1,
+        admin: true,
+    };
+
+    fn submitted(v: Value) -> Map<String, Value> {
+        v.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_definitions() {
+        assert!(AttributeSchema::parse(SCHEMA_TOML, "toml").is_ok());
+        let cases = [
+            r#"[[attributes]]
+               name = "Dept"
+               type = "string""#,
+            r#"[[attributes]]
+               name = "age"
+               type = "integer"
+               max_length = 3"#,
+            r#"[[attributes]]
+               name = "code"
+               type = "string"
+               pattern = "("#,
+            r#"[[attributes]]
+               name = "level"
+               type = "integer"
+               enum = ["high"]"#,
+            r#"[[attributes]]
+               name = "a"
+               type = "uuid""#,
+        ];
+        for raw in cases {
+            assert!(AttributeSchema::parse(raw, "toml").is_err(), "{}", raw);
+        }
+        let json = r#"{"attributes":[{"name":"vip","type":"boolean"}]}"#;
+        assert!(AttributeSchema::parse(json, "json").is_ok());
+    }
+
+    #[test]
+    fn test_apply_validates_and_checks_visibility() {
+        let schema = AttributeSchema::parse(SCHEMA_TOML, "toml").unwrap();
+
+        let created = schema
+            .apply(
+                None,
+                &submitted(json!({ "department": "sales", "employee_number": 12 })),
+                &ADMIN,
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            created,
+            json!({ "department": "sales", "employee_number": 12 })
+        );
+
+        let errors = schema
+            .apply(
+                None,
+                &submitted(json!({ "phone": "12", "color": "red" })),
+                &ADMIN,
+                None,
+            )
+            .unwrap_err();
+        assert_eq!(errors["attributes.phone"], json!(["Has an invalid format"]));
+        assert_eq!(errors["attributes.color"], json!(["Unknown attribute"]));
+        assert_eq!(
+            errors["attributes.department"],
+            json!(["Attribute is required"])
+        );
+
+        // The user can set their private phone but not see or change the admin-only field,
+        // which keeps its stored value
+        let updated = schema
+            .apply(
+                Some(&created),
+                &submitted(json!({ "department": "engineering", "phone": "+3312345678" })),
+                &USER,
+                Some(7),
+            )
+            .unwrap();
+        assert_eq!(updated["employee_number"], json!(12));
+        assert_eq!(
+            schema.visible(Some(&updated), &USER, 7),
+            json!({ "department": "engineering", "phone": "+3312345678" })
+        );
+        assert_eq!(
+            schema.visible(Some(&updated), &USER, 8),
+            json!({ "department": "engineering" })
+        );
+        let errors = schema
+            .apply(
+                Some(&updated),
+                &submitted(json!({ "department": "sales", "employee_number": 3 })),
+                &USER,
+                Some(7),
+            )
+            .unwrap_err();
+        assert_eq!(
+            errors["attributes.employee_number"],
+            json!(["Unknown attribute"])
+        );
+    }
+
+    #[test]
+    fn test_parse_filter_is_typed() {
+        let schema = AttributeSchema::parse(SCHEMA_TOML, "toml").unwrap();
+        let number = schema.get("employee_number").unwrap();
+        assert_eq!(number.parse_filter("42"), Ok(json!(42)));
+        assert!(number.parse_filter("forty-two").is_err());
+        let def: AttributeSchema = AttributeSchema::parse(
+            r#"[[attributes]]
+               name = "hired_on"
+               type = "date""#,
+            "toml",
+        )
+        .unwrap();
+        assert!(
+            def.get("hired_on")
+                .unwrap()
+                .parse_filter("2026-13-01")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_search_text_only_indexes_public_strings() {
+        let schema = AttributeSchema::parse(SCHEMA_TOML, "toml").unwrap();
+        let stored = json!({ "department": "sales", "phone": "+1234567", "employee_number": 42 });
+        assert_eq!(schema.search_text(Some(&stored)), Some("sales".to_string()));
+        assert_eq!(
+            schema.search_text(Some(&json!({ "phone": "+1234567" }))),
+            None
+        );
+        assert_eq!(schema.search_text(None), None);
+    }
+}
@@ -0,0 +1,268 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{Value, json};
+use sqlx::MySqlPool;
+
+use crate::models::invitation::Invitation;
+use crate::schemas::user_data_schema::UserDataRequestResponseSchema;
+use crate::schemas::user_revision_schema::UserRevisionResponseSchema;
+use crate::storage::blob_store;
+use crate::utils::avatar::delete_blobs;
+use crate::utils::user_fields::AVATAR_URL_SQL;
+use crate::utils::user_revisions::REVISION_COLUMNS;
+use crate::utils::user_status::EFFECTIVE_STATUS_SQL;
+
+pub const KIND_EXPORT: &str = "export";
+pub const KIND_ERASE: &str = "erase";
+
+/// Columns of [`UserDataRequestResponseSchema`]; the download link (a `/v1` path) only
+/// exists while the archive does.
+pub const REQUEST_COLUMNS: &str = "id, user_id, kind, status, requested_by, scheduled_for, \
+     completed_at, \
+     IF(archive_key IS NOT NULL, CONCAT('/v1/data-requests/', id, '/download'), NULL) AS download_url, \
+     expires_at, error, created_at";
+
+/// Days between an erasure request and its execution, during which it can be cancelled
+/// (`USER_ERASURE_GRACE_DAYS`, default 14).
+pub fn erasure_grace_days() -> u32 {
+    std::env::var("USER_ERASURE_GRACE_DAYS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(14)
+}
+
+/// Hours an export archive stays downloadable (`USER_EXPORT_TTL_HOURS`, default 72).
+pub fn export_ttl_hours() -> u32 {
+    std::env::var("USER_EXPORT_TTL_HOURS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(72)
+}
+
+/// Blob store key of an export archive.
+pub fn archive_key(user_id: i64, request_id: i64) -> String {
+    format!("exports/{}/{}.json", user_id, request_id)
+}
+
+/// Placeholder address of an erased user; `.invalid` can never be delivered to.
+pub fn erased_email(user_id: i64) -> String {
+    format!("erased-{}@invalid", user_id)
+}
+
+#[derive(sqlx::FromRow, Serialize)]
+struct ProfileRow {
+    id: i64,
+    name: String,
+    email: String,
+    role: String,
+    status: String,
+    status_reason: Option<String>,
+    status_until: Option<DateTime<Utc>>,
+    external_id: Option<String>,
+    active: bool,
+    attributes: Option<Value>,
+    avatar_url: Option<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    deleted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(sqlx::FromRow, Serialize)]
+struct IdentityRow {
+    provider: String,
+    subject: String,
+    email: Option<String>,
+    created_at: DateTime<Utc>,
+    last_login_at: Option<DateTime<Utc>>,
+}
+
+const INVITATION_COLUMNS: &str = "id, email, role, invited_by, expires_at, accepted_at, \
+     accepted_user_id, revoked_at, created_at, updated_at";
+
+/// Everything stored about a user, as written to an export archive (`None` if the user does
+/// not exist or was erased).
+///
+/// Secrets are left out: the password hash and invitation token hashes.
+pub async fn collect_user_data(db: &MySqlPool, user_id: i64) -> Result<Option<Value>, sqlx::Error> {
+    let profile = sqlx::query_as::<_, ProfileRow>(&format!(
+        r#"
+        SELECT id, name, email, role, {} AS status, status_reason, status_until, external_id,
+               active, attributes, {} AS avatar_url, created_at, updated_at, deleted_at
+        FROM users
+        WHERE id = ? AND erased_at IS NULL
+        "#,
+        EFFECTIVE_STATUS_SQL, AVATAR_URL_SQL
+    ))
+    .bind(user_id)
+    .fetch_optional(db)
+    .await?;
+    let Some(profile) = profile else {
+        return Ok(None);
+    };
+
+    let identities = sqlx::query_as::<_, IdentityRow>(
+        "SELECT provider, subject, email, created_at, last_login_at \
+         FROM user_identities WHERE user_id = ? ORDER BY id",
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await?;
+    let invitations_received = sqlx::query_as::<_, Invitation>(&format!(
+        "SELECT {} FROM invitations WHERE email = ? OR accepted_user_id = ? ORDER BY id",
+        INVITATION_COLUMNS
+    ))
+    .bind(&profile.email)
+    .bind(user_id)
+    .fetch_all(db)
+    .await?;
+    let invitations_sent = sqlx::query_as::<_, Invitation>(&format!(
+        "SELECT {} FROM invitations WHERE invited_by = ? ORDER BY id",
+        INVITATION_COLUMNS
+    ))
+    .bind(user_id)
+    .fetch_all(db)
+    .await?;
+    let data_requests = sqlx::query_as::<_, UserDataRequestResponseSchema>(&format!(
+        "SELECT {} FROM user_data_requests WHERE user_id = ? ORDER BY id",
+        REQUEST_COLUMNS
+    ))
+    .bind(user_id)
+    .fetch_all(db)
+    .await?;
+    let revisions = sqlx::query_as::<_, UserRevisionResponseSchema>(&format!(
+        "SELECT {} FROM user_revisions WHERE user_id = ? ORDER BY id",
+        REVISION_COLUMNS
+    ))
+    .bind(user_id)
+    .fetch_all(db)
+    .await?;
+
+    Ok(Some(json!({
+        "exported_at": Utc::now(),
+        "user": profile,
+        "identities": identities,
+        "invitations_received": invitations_received,
+        "invitations_sent": invitations_sent,
+        "data_requests": data_requests,
+        "revisions": revisions,
+    })))
+}
+
+/// Anonymize a user: personal data is overwritten or deleted, while the row itself is kept
+/// (soft-deleted, with `erased_at`) so references to its id stay valid.
+///
+/// Also removes linked identities, the change history, webhook events about the user (with
+/// their deliveries), invitations addressed to the user (pending ones are revoked), export
+/// archives and avatar files. Returns `false` if the user does not exist or
+/// was already erased.
+pub async fn erase_user(db: &MySqlPool, user_id: i64) -> Result<bool, sqlx::Error> {
+    let mut tx = db.begin().await?;
+    let row: Option<(String, Option<String>, Option<String>)> = sqlx::query_as(
+        "SELECT email, avatar_version, avatar_content_type FROM users \
+         WHERE id = ? AND erased_at IS NULL FOR UPDATE",
+    )
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some((email, avatar_version, avatar_content_type)) = row else {
+        tx.rollback().await?;
+        return Ok(false);
+    };
+    let anonymized = erased_email(user_id);
+
+    sqlx::query(
+        r#"
+        UPDATE invitations
+        SET email = ?, revoked_at = IF(accepted_at IS NULL, COALESCE(revoked_at, NOW()), revoked_at)
+        WHERE email = ? OR accepted_user_id = ?
+        "#,
+    )
+    .bind(&anonymized)
+    .bind(&email)
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+    for table in ["user_identities", "user_revisions"] {
+        sqlx::query(&format!("DELETE FROM {} WHERE user_id = ?", table))
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    // Outbox events carry the user's profile in `data.user`; dropping them (and their
+    // deliveries) keeps it from being redelivered or replayed over the SSE stream
+    sqlx::query(
+        r#"
+        DELETE d FROM webhook_deliveries d
+        JOIN webhook_events e ON e.id = d.event_id
+        WHERE JSON_EXTRACT(e.payload, '$.user.id') = ?
+        "#,
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("DELETE FROM webhook_events WHERE JSON_EXTRACT(payload, '$.user.id') = ?")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        r#"
+        UPDATE users
+        SET name = 'Erased user', email = ?, password = '', external_id = NULL, active = FALSE,
+            attributes = NULL, attributes_search = NULL, avatar_version = NULL, avatar_content_type = NULL,
+            status_reason = NULL, deleted_at = COALESCE(deleted_at, NOW()), erased_at = NOW(),
+            version = version + 1
+        WHERE id = ?
+        "#,
+    )
+    .bind(&anonymized)
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    let archives: Vec<String> = sqlx::query_scalar(
+        "SELECT archive_key FROM user_data_requests \
+         WHERE user_id = ? AND archive_key IS NOT NULL FOR UPDATE",
+    )
+    .bind(user_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    sqlx::query(
+        "UPDATE user_data_requests SET archive_key = NULL, status = 'expired' \
+         WHERE user_id = ? AND archive_key IS NOT NULL",
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    remove_files(user_id, avatar_version.zip(avatar_content_type), &archives).await;
+    Ok(true)
+}
+
+/// Delete an erased user's files; failures are logged and leave orphaned files.
+async fn remove_files(user_id: i64, avatar: Option<(String, String)>, archives: &[String]) {
+    if avatar.is_none() && archives.is_empty() {
+        return;
+    }
+    let store = match blob_store() {
+        Ok(store) => store,
+        Err(e) => {
+            tracing::warn!("user erasure: storage unavailable: {}", e);
+            return;
+        }
+    };
+    if let Some((token, content_type)) = avatar
+        && let Err(e) = delete_blobs(store.as_ref(), user_id, &token, &content_type).await
+    {
+        tracing::warn!(
+            "user erasure: failed to remove avatar of user {}: {}",
+            user_id,
+            e
+        );
+    }
+    for key in archives {
+        if let Err(e) = store.delete(key).await {
+            tracing::warn!("user erasure: failed to remove archive {}: {}", key, e);
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use chrono::{DateTime, Utc};
+use serde_json::{Value, json};
+use sqlx::MySqlPool;
+use sqlx::types::Json;
+use std::time::{Duration, Instant};
+
+use crate::utils::policy::current_policies;
+use crate::utils::webhooks::{EVENT_USER_CREATED, EVENT_USER_DELETED, EVENT_USER_UPDATED};
+
+/// Event types pushed on `GET /events/users` (logins are left out).
+pub const STREAM_EVENT_TYPES: [&str; 3] =
+    [EVENT_USER_CREATED, EVENT_USER_UPDATED, EVENT_USER_DELETED];
+
+/// How long the stream waits for a missing event id before skipping it. Ids are assigned at
+/// insert but become visible at commit, so a lower id can show up after a higher one; a gap
+/// that never fills is a rolled-back transaction.
+pub const GAP_WAIT: Duration = Duration::from_secs(2);
+
+fn env_u64(name: &str, default: u64) -> u64 {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+/// Time between polls of the event log (`USER_EVENTS_POLL_MS`, default 1000).
+pub fn poll_interval() -> Duration {
+    Duration::from_millis(env_u64("USER_EVENTS_POLL_MS", 1000).max(50))
+}
+
+/// Time between keep-alive comments (`USER_EVENTS_KEEPALIVE_SECS`, default 15).
+pub fn keep_alive_interval() -> Duration {
+    Duration::from_secs(env_u64("USER_EVENTS_KEEPALIVE_SECS", 15).max(1))
+}
+
+/// Most events replayed after `Last-Event-ID` (`USER_EVENTS_REPLAY_LIMIT`, default 1000);
+/// clients further behind are told to reload instead.
+pub fn replay_limit() -> i64 {
+    env_u64("USER_EVENTS_REPLAY_LIMIT", 1000).max(1) as i64
+}
+
+/// An event of the outbox (`webhook_events`), which doubles as the stream's log.
+#[derive(sqlx::FromRow, Debug)]
+pub struct LoggedEvent {
+    pub id: i64,
+    pub event_type: String,
+    pub payload: Json<Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Events after `cursor`, oldest first (all types, so id gaps can be told apart from events
+/// the stream does not send).
+pub async fn events_after(
+    db: &MySqlPool,
+    cursor: i64,
+    limit: i64,
+) -> Result<Vec<LoggedEvent>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT id, event_type, payload, created_at FROM webhook_events \
+         WHERE id > ? ORDER BY id LIMIT ?",
+    )
+    .bind(cursor)
+    .bind(limit)
+    .fetch_all(db)
+    .await
+}
+
+/// Oldest and newest event ids in the log (`None` when it is empty).
+pub async fn log_bounds(db: &MySqlPool) -> Result<Option<(i64, i64)>, sqlx::Error> {
+    let (oldest, newest): (Option<i64>, Option<i64>) =
+        sqlx::query_as("SELECT MIN(id), MAX(id) FROM webhook_events")
+            .fetch_one(db)
+            .await?;
+    Ok(oldest.zip(newest))
+}
+
+/// Where a stream starts, and why the client must reload instead of resuming (if it must).
+#[derive(Debug, PartialEq, Eq)]
+pub struct Resume {
+    pub cursor: i64,
+    pub reset: Option<&'static str>,
+}
+
+/// Resolve the stream start for a `Last-Event-ID`: resume after it when the log still holds
+/// every later event and there are at most `limit` of them, otherwise start at the newest
+/// event with a reset reason. Without one the stream starts at the newest event.
+pub fn resume_from(last_event_id: Option<i64>, bounds: Option<(i64, i64)>, limit: i64) -> Resume {
+    let newest = bounds.map(|(_, newest)| newest).unwrap_or(0);
+    let reset = |reason| Resume {
+        cursor: newest,
+        reset: Some(reason),
+    };
+    let Some(last) = last_event_id else {
+        return Resume {
+            cursor: newest,
+            reset: None,
+        };
+    };
+    match bounds {
+        _ if last > newest => reset("unknown_event_id"),
+        Some((oldest, _)) if oldest > last + 1 => reset("expired"),
+        _ if newest - last > limit => reset("too_far_behind"),
+        _ => Resume {
+            cursor: last,
+            reset: None,
+        },
+    }
+}
+
+/// Number of leading `ids` the stream can move past from `cursor`: it stops at a missing id
+/// until the gap is older than `wait` (tracked in `gap_since`), then skips it.
+pub fn contiguous_prefix(
+    cursor: i64,
+    ids: &[i64],
+    gap_since: &mut Option<Instant>,
+    wait: Duration,
+) -> usize {
+    let mut expected = cursor + 1;
+    for (i, &id) in ids.iter().enumerate() {
+        if id != expected {
+            let since = *gap_since.get_or_insert_with(Instant::now);
+            if since.elapsed() < wait {
+                return i;
+            }
+        }
+        *gap_since = None;
+        expected = id + 1;
+    }
+    ids.len()
+}
+
+/// Whether the caller may see an event: `users:read` on the user it is about.
+pub fn visible_to(principal: &Value, payload: &Value) -> bool {
+    let user = &payload["user"];
+    let resource = json!({ "id": user["id"], "email": user["email"], "role": user["role"] });
+    current_policies()
+        .evaluate(principal, "users:read", &resource)
+        .allowed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resume_from() {
+        let bounds = Some((10, 50));
+        assert_eq!(resume_from(None, bounds, 100).cursor, 50);
+        assert_eq!(
+            resume_from(Some(40), bounds, 100),
+            Resume {
+                cursor: 40,
+                reset: None
+            }
+        );
+        assert_eq!(resume_from(Some(9), bounds, 100).reset, None);
+        assert_eq!(resume_from(Some(5), bounds, 100).reset, Some("expired"));
+        assert_eq!(
+            resume_from(Some(60), bounds, 100).reset,
+            Some("unknown_event_id")
+        );
+        assert_eq!(
+            resume_from(Some(20), bounds, 10),
+            Resume {
+                cursor: 50,
+                reset: Some("too_far_behind")
+            }
+        );
+        assert_eq!(resume_from(Some(0), None, 100).reset, None);
+    }
+
+    #[test]
+    fn test_contiguous_prefix_waits_for_gaps() {
+        let mut gap_since = None;
+        assert_eq!(
+            contiguous_prefix(4, &[5, 6, 8], &mut gap_since, GAP_WAIT),
+            2
+        );
+        assert!(gap_since.is_some());
+        // The gap filled in
+        assert_eq!(contiguous_prefix(6, &[7, 8], &mut gap_since, GAP_WAIT), 2);
+        assert!(gap_since.is_none());
+        // A gap that outlived the wait is skipped
+        let mut gap_since = Some(Instant::now() - GAP_WAIT);
+        assert_eq!(contiguous_prefix(4, &[6, 7], &mut gap_since, GAP_WAIT), 2);
+        assert!(gap_since.is_none());
+    }
+}
@@ -0,0 +1,212 @@
+use chrono::{DateTime, Utc};
+use serde_json::{Map, Value, json};
+use sqlx::{MySql, MySqlPool, QueryBuilder};
+use std::collections::HashMap;
+
+use crate::utils::user_status::EFFECTIVE_STATUS_SQL;
+
+/// SQL expression behind the computed `avatar_url` field (a `/v1` path, stable across the
+/// unversioned aliases and later API versions).
+pub const AVATAR_URL_SQL: &str = "CONCAT('/v1/users/', id, '/avatar?v=', avatar_version)";
+
+/// Fields `GET /users` can return with `?fields=`.
+pub const LIST_FIELDS: [&str; 9] = [
+    "id",
+    "name",
+    "email",
+    "status",
+    "created_at",
+    "updated_at",
+    "avatar_url",
+    "attributes",
+    "deleted_at",
+];
+
+/// Fields `GET /users/{id}` can return with `?fields=`.
+pub const SHOW_FIELDS: [&str; 9] = [
+    "id",
+    "name",
+    "email",
+    "status",
+    "created_at",
+    "updated_at",
+    "version",
+    "avatar_url",
+    "attributes",
+];
+
+/// Related data that `?include=` can embed in each user.
+pub const INCLUDES: [&str; 1] = ["identities"];
+
+/// Parse a comma-separated list against a whitelist (duplicates are ignored, order is kept).
+fn parse_names(
+    raw: &str,
+    allowed: &[&'static str],
+    what: &str,
+) -> Result<Vec<&'static str>, String> {
+    let mut names: Vec<&'static str> = Vec::new();
+    for part in raw.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let name = allowed
+            .iter()
+            .find(|a| a.eq_ignore_ascii_case(part))
+            .ok_or_else(|| {
+                format!(
+                    "Unknown {} '{}'; allowed: {}",
+                    what,
+                    part,
+                    allowed.join(", ")
+                )
+            })?;
+        if !names.contains(name) {
+            names.push(name);
+        }
+    }
+    Ok(names)
+}
+
+/// Parse `fields=id,name` (`None` when absent: the full representation).
+pub fn parse_fields(
+    raw: Option<&str>,
+    allowed: &[&'static str],
+) -> Result<Option<Vec<&'static str>>, String> {
+    let Some(raw) = raw else {
+        return Ok(None);
+    };
+    let fields = parse_names(raw, allowed, "field")?;
+    if fields.is_empty() {
+        return Err("List at least one field".to_string());
+    }
+    Ok(Some(fields))
+}
+
+/// Parse `include=identities` against [`INCLUDES`].
+pub fn parse_includes(raw: Option<&str>) -> Result<Vec<&'static str>, String> {
+    parse_names(raw.unwrap_or(""), &INCLUDES, "include")
+}
+
+/// `SELECT` column list for the requested fields plus the columns the handler needs itself
+/// (primary key, sort keys, version).
+pub fn select_list(fields: &[&str], required: &[&str]) -> String {
+    let mut columns: Vec<&str> = Vec::new();
+    for field in fields.iter().chain(required) {
+        if !columns.contains(field) {
+            columns.push(field);
+        }
+    }
+    columns
+        .iter()
+        .map(|c| match *c {
+            "avatar_url" => format!("{} AS avatar_url", AVATAR_URL_SQL),
+            "status" => format!("{} AS status", EFFECTIVE_STATUS_SQL),
+            other => other.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Keep only the requested fields of a serialized user (missing ones become `null`).
+pub fn project(user: &Value, fields: &[&str]) -> Value {
+    let object: Map<String, Value> = fields
+        .iter()
+        .map(|f| (f.to_string(), user.get(*f).cloned().unwrap_or(Value::Null)))
+        .collect();
+    Value::Object(object)
+}
+
+/// Add the requested relations to each user document, keyed by user id.
+///
+/// One query per relation for the whole page (no N+1).
+pub async fn embed_includes(
+    db: &MySqlPool,
+    includes: &[&str],
+    users: &mut [(i64, Value)],
+) -> Result<(), sqlx::Error> {
+    if users.is_empty() {
+        return Ok(());
+    }
+    for include in includes {
+        let mut related = match *include {
+            "identities" => load_identities(db, users).await?,
+            _ => continue,
+        };
+        for (id, user) in users.iter_mut() {
+            if let Value::Object(object) = user {
+                object.insert(
+                    include.to_string(),
+                    Value::Array(related.remove(id).unwrap_or_default()),
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+type IdentityRow = (
+    i64,
+    String,
+    String,
+    Option<String>,
+    Option<DateTime<Utc>>,
+    Option<DateTime<Utc>>,
+);
+
+/// Linked federated identities (OIDC) per user.
+async fn load_identities(
+    db: &MySqlPool,
+    users: &[(i64, Value)],
+) -> Result<HashMap<i64, Vec<Value>>, sqlx::Error> {
+    let mut qb = QueryBuilder::<MySql>::new(
+        "SELECT user_id, provider, subject, email, created_at, last_login_at \
+         FROM user_identities WHERE user_id IN (",
+    );
+    let mut ids = qb.separated(", ");
+    for (id, _) in users {
+        ids.push_bind(*id);
+    }
+    qb.push(") ORDER BY user_id, id");
+    let rows: Vec<IdentityRow> = qb.build_query_as().fetch_all(db).await?;
+
+    let mut by_user: HashMap<i64, Vec<Value>> = HashMap::new();
+    for (user_id, provider, subject, email, created_at, last_login_at) in rows {
+        by_user.entry(user_id).or_default().push(json!({
+            "provider": provider,
+            "subject": subject,
+            "email": email,
+            "created_at": created_at,
+            "last_login_at": last_login_at,
+        }));
+    }
+    Ok(by_user)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fields() {
+        assert_eq!(parse_fields(None, &LIST_FIELDS), Ok(None));
+        assert_eq!(
+            parse_fields(Some(" name, ID ,name"), &LIST_FIELDS),
+            Ok(Some(vec!["name", "id"]))
+        );
+        assert!(parse_fields(Some(""), &LIST_FIELDS).is_err());
+        let err = parse_fields(Some("id,password"), &SHOW_FIELDS).unwrap_err();
+        assert!(err.contains("'password'"));
+        assert!(parse_fields(Some("version"), &LIST_FIELDS).is_err());
+        assert!(parse_includes(Some("identities,roles")).is_err());
+    }
+
+    #[test]
+    fn test_select_list_and_project() {
+        assert_eq!(
+            select_list(&["avatar_url", "name"], &["name", "id"]),
+            format!("{} AS avatar_url, name, id", AVATAR_URL_SQL)
+        );
+        let user = json!({ "id": 1, "name": "Ann", "email": "a@example.com" });
+        assert_eq!(
+            project(&user, &["name", "deleted_at"]),
+            json!({ "name": "Ann", "deleted_at": null })
+        );
+    }
+}
@@ -0,0 +1,502 @@
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
+use serde::Serialize;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use sqlx::{MySql, QueryBuilder};
+use std::collections::BTreeMap;
+
+use crate::models::user::User;
+use crate::utils::cursor::{decode_cursor, encode_cursor};
+use crate::utils::user_status::{EFFECTIVE_STATUS_SQL, UserStatus};
+
+/// Columns `GET /users` may be sorted by (`sort=name,-created_at`).
+pub const SORTABLE_COLUMNS: [&str; 5] = ["id", "name", "email", "created_at", "updated_at"];
+
+/// Escape `%`, `_` and `\` so user input is matched literally inside a `LIKE` pattern.
+pub fn escape_like(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// One `ORDER BY` term from the whitelist.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortField {
+    pub column: &'static str,
+    pub descending: bool,
+}
+
+/// Parse `sort=name,-created_at` against [`SORTABLE_COLUMNS`].
+///
+/// `id` is appended as a final tie-breaker (same direction as the last field) so the order
+/// is total and stable between pages.
+pub fn parse_sort(raw: Option<&str>) -> Result<Vec<SortField>, String> {
+    let mut fields: Vec<SortField> = Vec::new();
+    for part in raw.unwrap_or("").split(',').map(str::trim) {
+        if part.is_empty() {
+            continue;
+        }
+        let (name, descending) = match part.strip_prefix('-') {
+            Some(rest) => (rest, true),
+            None => (part.strip_prefix('+').unwrap_or(part), false),
+        };
+        let column = SORTABLE_COLUMNS
+            .iter()
+            .find(|c| c.eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                format!(
+                    "Cannot sort by '{}'; allowed: {}",
+                    name,
+                    SORTABLE_COLUMNS.join(", ")
+                )
+            })?;
+        if fields.iter().any(|f| f.column == *column) {
+            return Err(format!("Duplicate sort field '{}'", column));
+        }
+        fields.push(SortField { column, descending });
+    }
+    if !fields.iter().any(|f| f.column == "id") {
+        let descending = fields.last().map(|f| f.descending).unwrap_or(false);
+        fields.push(SortField {
+            column: "id",
+            descending,
+        });
+    }
+    Ok(fields)
+}
+
+/// Render sort fields back into the `sort` parameter syntax (echoed in `meta`).
+pub fn sort_to_string(fields: &[SortField]) -> String {
+    fields
+        .iter()
+        .map(|f| format!("{}{}", if f.descending { "-" } else { "" }, f.column))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Parse an RFC 3339 timestamp or a plain `YYYY-MM-DD` date (midnight UTC).
+pub fn parse_datetime_param(raw: &str) -> Option<DateTime<Utc>> {
+    let raw = raw.trim();
+    if let Ok(t) = DateTime::parse_from_rfc3339(raw) {
+        return Some(t.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(raw, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|dt| dt.and_utc())
+}
+
+/// Filters applied by `GET /users`; serialized as-is into the response `meta`.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct UserFilters {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub q: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    /// Effective account status, any of (`status=suspended,banned`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub status: Vec<UserStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_after: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_before: Option<DateTime<Utc>>,
+    /// Also return soft-deleted users (admin only)
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub include_deleted: bool,
+    /// Exact matches on custom attributes (`attr.<name>=value`), typed by the attribute schema
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, Value>,
+}
+
+impl UserFilters {
+    /// Whether no filter narrows the listing (soft-deleted rows aside).
+    pub fn is_empty(&self) -> bool {
+        self.q.is_none()
+            && self.email.is_none()
+            && self.status.is_empty()
+            && self.created_after.is_none()
+            && self.created_before.is_none()
+            && self.attributes.is_empty()
+    }
+
+    /// Append ` WHERE ...` (if any filter is set) with bound parameters.
+    ///
+    /// Soft-deleted rows are excluded unless `include_deleted` is set.
+    /// Returns whether a `WHERE` clause was started, so callers can add more conditions.
+    pub fn push_where(&self, qb: &mut QueryBuilder<'_, MySql>) -> bool {
+        let mut sep = " WHERE ";
+        if !self.include_deleted {
+            qb.push(sep).push("deleted_at IS NULL");
+            sep = " AND ";
+        }
+        if let Some(q) = &self.q {
+            let pattern = format!("%{}%", escape_like(q));
+            qb.push(sep)
+                .push("(name LIKE ")
+                .push_bind(pattern.clone())
+                .push(" OR email LIKE ")
+                .push_bind(pattern)
+                .push(")");
+            sep = " AND ";
+        }
+        if let Some(email) = &self.email {
+            qb.push(sep).push("email = ").push_bind(email.clone());
+            sep = " AND ";
+        }
+        if !self.status.is_empty() {
+            qb.push(sep).push(EFFECTIVE_STATUS_SQL).push(" IN (");
+            let mut values = qb.separated(", ");
+            for status in &self.status {
+                values.push_bind(status.as_str());
+            }
+            qb.push(")");
+            sep = " AND ";
+        }
+        if let Some(after) = self.created_after {
+            qb.push(sep).push("created_at >= ").push_bind(after);
+            sep = " AND ";
+        }
+        if let Some(before) = self.created_before {
+            qb.push(sep).push("created_at < ").push_bind(before);
+            sep = " AND ";
+        }
+        // Names come from the attribute schema; path and value are bound all the same
+        for (name, value) in &self.attributes {
+            qb.push(sep)
+                .push("JSON_EXTRACT(attributes, ")
+                .push_bind(format!("$.\"{}\"", name))
+                .push(") = CAST(")
+                .push_bind(value.to_string())
+                .push(" AS JSON)");
+            sep = " AND ";
+        }
+        sep == " AND "
+    }
+}
+
+/// Append ` ORDER BY ...` for whitelisted sort fields.
+pub fn push_order_by(qb: &mut QueryBuilder<'_, MySql>, fields: &[SortField]) {
+    qb.push(" ORDER BY ");
+    for (i, f) in fields.iter().enumerate() {
+        if i > 0 {
+            qb.push(", ");
+        }
+        qb.push(f.column)
+            .push(if f.descending { " DESC" } else { " ASC" });
+    }
+}
+
+/// Reverse every sort direction (used to read the page before a cursor).
+pub fn reversed(fields: &[SortField]) -> Vec<SortField> {
+    fields
+        .iter()
+        .map(|f| SortField {
+            column: f.column,
+            descending: !f.descending,
+        })
+        .collect()
+}
+
+/// How `GET /users` reports the total (`count=exact|estimate|none`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CountMode {
+    /// `COUNT(1)` over the filtered rows
+    Exact,
+    /// Row estimate of the whole table from statistics, soft-deleted rows included (cheap on
+    /// large tables; only used for unfiltered listings)
+    Estimate,
+    /// No total at all
+    None,
+}
+
+impl CountMode {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "exact" => Some(Self::Exact),
+            "estimate" => Some(Self::Estimate),
+            "none" => Some(Self::None),
+            _ => None,
+        }
+    }
+
+    /// The mode used for a listing with `filters`: table statistics cannot count filtered
+    /// rows, so an estimate falls back to an exact count when any filter is set.
+    pub fn for_filters(self, filters: &UserFilters) -> Self {
+        match self {
+            Self::Estimate if !filters.is_empty() => Self::Exact,
+            mode => mode,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Exact => "exact",
+            Self::Estimate => "estimate",
+            Self::None => "none",
+        }
+    }
+}
+
+/// Which side of the cursor row a keyset page lies on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageDirection {
+    Next,
+    Prev,
+}
+
+/// Typed value of one sort column, bound when seeking past a cursor row.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SortKey {
+    Int(i64),
+    Text(String),
+    Time(DateTime<Utc>),
+}
+
+impl SortKey {
+    fn of(user: &User, column: &str) -> Self {
+        match column {
+            "name" => Self::Text(user.name.clone()),
+            "email" => Self::Text(user.email.clone()),
+            "created_at" => Self::Time(user.created_at),
+            "updated_at" => Self::Time(user.updated_at),
+            _ => Self::Int(user.id),
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        match self {
+            Self::Int(v) => json!(v),
+            Self::Text(v) => json!(v),
+            Self::Time(v) => json!(v.to_rfc3339_opts(SecondsFormat::AutoSi, true)),
+        }
+    }
+
+    fn from_json(column: &str, value: &Value) -> Option<Self> {
+        match column {
+            "id" => value.as_i64().map(Self::Int),
+            "created_at" | "updated_at" => value
+                .as_str()
+                .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+                .map(|t| Self::Time(t.with_timezone(&Utc))),
+            _ => value.as_str().map(|s| Self::Text(s.to_string())),
+        }
+    }
+
+    fn push_bind(&self, qb: &mut QueryBuilder<'_, MySql>) {
+        match self {
+            Self::Int(v) => qb.push_bind(*v),
+            Self::Text(v) => qb.push_bind(v.clone()),
+            Self::Time(v) => qb.push_bind(*v),
+        };
+    }
+}
+
+/// Decoded keyset cursor: the sort keys of the boundary row and the page direction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeysetCursor {
+    pub direction: PageDirection,
+    pub keys: Vec<SortKey>,
+}
+
+/// Fingerprint of the filters and sort a cursor was issued for.
+///
+/// Cursors only make sense for the exact query that produced them, so reusing one with
+/// different filters or sort is rejected instead of returning a silently wrong page.
+pub fn query_fingerprint(filters: &UserFilters, sort: &[SortField]) -> String {
+    let raw = format!(
+        "{}|{}",
+        sort_to_string(sort),
+        serde_json::to_string(filters).unwrap_or_default()
+    );
+    hex::encode(&Sha256::digest(raw.as_bytes())[..8])
+}
+
+/// Issue a signed cursor pointing at `user` (the first or last row of a page).
+pub fn encode_keyset_cursor(
+    fingerprint: &str,
+    direction: PageDirection,
+    user: &User,
+    sort: &[SortField],
+) -> String {
+    let keys: Vec<Value> = sort
+        .iter()
+        .map(|f| SortKey::of(user, f.column).to_json())
+        .collect();
+    let direction = match direction {
+        PageDirection::Next => "next",
+        PageDirection::Prev => "prev",
+    };
+    encode_cursor(&json!({ "f": fingerprint, "d": direction, "k": keys }))
+}
+
+/// Verify and decode a cursor issued by [`encode_keyset_cursor`] for the same query.
+pub fn decode_keyset_cursor(
+    raw: &str,
+    fingerprint: &str,
+    sort: &[SortField],
+) -> Result<KeysetCursor, String> {
+    let payload = decode_cursor(raw.trim()).ok_or("Invalid or tampered cursor")?;
+    if payload["f"].as_str() != Some(fingerprint) {
+        return Err("Cursor does not match the current filters or sort".to_string());
+    }
+    let direction = match payload["d"].as_str() {
+        Some("next") => PageDirection::Next,
+        Some("prev") => PageDirection::Prev,
+        _ => return Err("Invalid or tampered cursor".to_string()),
+    };
+    let values = payload["k"]
+        .as_array()
+        .ok_or("Invalid or tampered cursor")?;
+    if values.len() != sort.len() {
+        return Err("Invalid or tampered cursor".to_string());
+    }
+    let keys = sort
+        .iter()
+        .zip(values)
+        .map(|(f, v)| SortKey::from_json(f.column, v))
+        .collect::<Option<Vec<_>>>()
+        .ok_or("Invalid or tampered cursor")?;
+    Ok(KeysetCursor { direction, keys })
+}
+
+/// Decode a cursor for forward-only pagination (GraphQL `after`, gRPC `page_token`), which
+/// only accepts cursors pointing at the next page.
+pub fn decode_forward_cursor(
+    raw: &str,
+    fingerprint: &str,
+    sort: &[SortField],
+) -> Result<KeysetCursor, String> {
+    match decode_keyset_cursor(raw, fingerprint, sort)? {
+        c if c.direction == PageDirection::Next => Ok(c),
+        _ => Err("Invalid or tampered cursor".to_string()),
+    }
+}
+
+/// Append a condition selecting rows strictly after `keys` in `fields` order.
+///
+/// Expands the row comparison so mixed directions work:
+/// `(a > ?) OR (a = ? AND b < ?) OR (a = ? AND b = ? AND id > ?)`.
+pub fn push_keyset(qb: &mut QueryBuilder<'_, MySql>, fields: &[SortField], keys: &[SortKey]) {
+    qb.push("(");
+    for i in 0..fields.len() {
+        if i > 0 {
+            qb.push(" OR ");
+        }
+        qb.push("(");
+        for j in 0..i {
+            qb.push(fields[j].column).push(" = ");
+            keys[j].push_bind(qb);
+            qb.push(" AND ");
+        }
+        qb.push(fields[i].column)
+            .push(if fields[i].descending { " < " } else { " > " });
+        keys[i].push_bind(qb);
+        qb.push(")");
+    }
+    qb.push(")");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sort_whitelist_and_tiebreaker() {
+        let fields = parse_sort(Some("name,-created_at")).expect("sort");
+        assert_eq!(sort_to_string(&fields), "name,-created_at,-id");
+        assert_eq!(sort_to_string(&parse_sort(None).unwrap()), "id");
+        assert_eq!(sort_to_string(&parse_sort(Some("-id")).unwrap()), "-id");
+        assert!(parse_sort(Some("password")).is_err());
+        assert!(parse_sort(Some("name,name")).is_err());
+    }
+
+    #[test]
+    fn test_parse_datetime_param() {
+        assert_eq!(
+            parse_datetime_param("2026-01-02").unwrap().to_rfc3339(),
+            "2026-01-02T00:00:00+00:00"
+        );
+        assert!(parse_datetime_param("2026-01-02T10:00:00+02:00").is_some());
+        assert!(parse_datetime_param("yesterday").is_none());
+    }
+
+    #[test]
+    fn test_filters_sql_is_parameterized() {
+        let filters = UserFilters {
+            q: Some("50%".into()),
+            email: Some("a@example.com".into()),
+            status: vec![UserStatus::Suspended, UserStatus::Banned],
+            created_after: parse_datetime_param("2026-01-01"),
+            created_before: None,
+            include_deleted: false,
+            attributes: BTreeMap::from([("department".to_string(), json!("sales"))]),
+        };
+        let mut qb = QueryBuilder::<MySql>::new("SELECT id FROM users");
+        filters.push_where(&mut qb);
+        push_order_by(&mut qb, &parse_sort(Some("-name")).unwrap());
+        assert_eq!(
+            qb.sql(),
+            format!(
+                "SELECT id FROM users WHERE deleted_at IS NULL AND (name LIKE ? OR email LIKE ?) AND email = ? AND {} IN (?, ?) AND created_at >= ? AND JSON_EXTRACT(attributes, ?) = CAST(? AS JSON) ORDER BY name DESC, id DESC",
+                EFFECTIVE_STATUS_SQL
+            )
+        );
+    }
+
+    #[test]
+    fn test_estimate_only_for_unfiltered_listings() {
+        let unfiltered = UserFilters {
+            include_deleted: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            CountMode::Estimate.for_filters(&unfiltered),
+            CountMode::Estimate
+        );
+        let filtered = UserFilters {
+            email: Some("a@example.com".into()),
+            ..Default::default()
+        };
+        assert_eq!(CountMode::Estimate.for_filters(&filtered), CountMode::Exact);
+        assert_eq!(CountMode::None.for_filters(&filtered), CountMode::None);
+    }
+
+    fn user(id: i64, name: &str) -> User {
+        let t = parse_datetime_param("2026-01-01T00:00:00Z").unwrap();
+        User {
+            id,
+            name: name.into(),
+            email: format!("{}@example.com", id),
+            status: None,
+            created_at: t,
+            updated_at: t,
+            deleted_at: None,
+            avatar_url: None,
+            attributes: None,
+        }
+    }
+
+    #[test]
+    fn test_keyset_cursor_roundtrip_and_binding() {
+        let sort = parse_sort(Some("name,-created_at")).unwrap();
+        let fp = query_fingerprint(&UserFilters::default(), &sort);
+        let cursor = encode_keyset_cursor(&fp, PageDirection::Next, &user(7, "Ann"), &sort);
+
+        let decoded = decode_keyset_cursor(&cursor, &fp, &sort).expect("decode");
+        assert_eq!(decoded.direction, PageDirection::Next);
+        assert_eq!(decoded.keys[0], SortKey::Text("Ann".into()));
+        assert_eq!(decoded.keys[2], SortKey::Int(7));
+
+        // A cursor is bound to the filters and sort it was issued for
+        let other = parse_sort(Some("email")).unwrap();
+        let other_fp = query_fingerprint(&UserFilters::default(), &other);
+        assert!(decode_keyset_cursor(&cursor, &other_fp, &other).is_err());
+
+        let mut qb = QueryBuilder::<MySql>::new("SELECT id FROM users WHERE ");
+        push_keyset(&mut qb, &sort, &decoded.keys);
+        assert_eq!(
+            qb.sql(),
+            "SELECT id FROM users WHERE ((name > ?) OR (name = ? AND created_at < ?) OR (name = ? AND created_at = ? AND id < ?))"
+        );
+    }
+}
@@ -0,0 +1,255 @@
+use chrono::{DateTime, Utc};
+use serde_json::{Map, Value, json};
+use sqlx::MySqlConnection;
+use sqlx::types::Json;
+
+use crate::utils::user_attributes::{AttributeSchema, Viewer};
+
+pub const ACTION_CREATE: &str = "create";
+pub const ACTION_UPDATE: &str = "update";
+pub const ACTION_DELETE: &str = "delete";
+pub const ACTION_RESTORE: &str = "restore";
+pub const ACTION_REVERT: &str = "revert";
+
+/// Custom attributes appear in diffs as `attributes.<name>`.
+const ATTRIBUTE_PREFIX: &str = "attributes.";
+
+/// Columns of `user_revisions` in `UserRevisionResponseSchema` order.
+pub const REVISION_COLUMNS: &str =
+    "id, user_id, actor_id, action, changes, version, reverted_to, created_at";
+
+/// The recorded fields of a user at one point in time (the password is never recorded).
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct UserSnapshot {
+    pub name: String,
+    pub email: String,
+    pub attributes: Option<Value>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub version: i64,
+}
+
+impl UserSnapshot {
+    /// Flat field map: `name`, `email`, `deleted_at` and one `attributes.<name>` per attribute.
+    fn fields(&self) -> Map<String, Value> {
+        let mut out = Map::new();
+        out.insert("name".to_string(), json!(self.name));
+        out.insert("email".to_string(), json!(self.email));
+        out.insert("deleted_at".to_string(), json!(self.deleted_at));
+        if let Some(Value::Object(attributes)) = &self.attributes {
+            for (name, value) in attributes {
+                out.insert(format!("{}{}", ATTRIBUTE_PREFIX, name), value.clone());
+            }
+        }
+        out
+    }
+}
+
+/// Who made a change and how, recorded along with it.
+#[derive(Debug, Clone, Copy)]
+pub struct ChangeContext<'a> {
+    pub action: &'a str,
+    pub actor_id: Option<i64>,
+    /// For reverts, the revision whose state is restored
+    pub reverted_to: Option<i64>,
+}
+
+impl<'a> ChangeContext<'a> {
+    pub fn new(action: &'a str, actor_id: Option<i64>) -> Self {
+        Self {
+            action,
+            actor_id,
+            reverted_to: None,
+        }
+    }
+}
+
+/// Lock a user row and read its recorded fields (`None` if missing).
+pub async fn snapshot(
+    conn: &mut MySqlConnection,
+    user_id: i64,
+) -> Result<Option<UserSnapshot>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT name, email, attributes, deleted_at, version FROM users WHERE id = ? FOR UPDATE",
+    )
+    .bind(user_id)
+    .fetch_optional(conn)
+    .await
+}
+
+/// Field-level diff `{ "<field>": { "from": ..., "to": ... } }` between two states of a user
+/// (`before` is `None` for a creation). Absent attributes are `null`.
+pub fn diff(before: Option<&UserSnapshot>, after: &UserSnapshot) -> Map<String, Value> {
+    let before = before.map(UserSnapshot::fields).unwrap_or_default();
+    let after = after.fields();
+    let mut out = Map::new();
+    for key in before.keys().chain(after.keys()) {
+        let from = before.get(key).unwrap_or(&Value::Null);
+        let to = after.get(key).unwrap_or(&Value::Null);
+        if from != to && !out.contains_key(key) {
+            out.insert(key.clone(), json!({ "from": from, "to": to }));
+        }
+    }
+    out
+}
+
+/// Record a change of a user from its `before` state to its current one. Call it inside the
+/// transaction that made the change, after the write.
+pub async fn record_change(
+    conn: &mut MySqlConnection,
+    user_id: i64,
+    context: ChangeContext<'_>,
+    before: Option<&UserSnapshot>,
+) -> Result<(), sqlx::Error> {
+    let Some(after) = snapshot(&mut *conn, user_id).await? else {
+        return Ok(());
+    };
+    sqlx::query(
+        r#"
+        INSERT INTO user_revisions (user_id, actor_id, action, changes, version, reverted_to)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(user_id)
+    .bind(context.actor_id)
+    .bind(context.action)
+    .bind(Json(Value::Object(diff(before, &after))))
+    .bind(after.version)
+    .bind(context.reverted_to)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Whether `viewer` may see a changed field of `subject`: custom attributes follow their
+/// visibility, and attributes no longer in the schema are shown to admins only.
+pub fn can_see_field(schema: &AttributeSchema, viewer: &Viewer, subject: i64, key: &str) -> bool {
+    match key.strip_prefix(ATTRIBUTE_PREFIX) {
+        None => true,
+        Some(name) => match schema.get(name) {
+            Some(def) => viewer.can_access(def.visibility, Some(subject)),
+            None => viewer.admin,
+        },
+    }
+}
+
+/// A revision's changes without the fields `viewer` may not see.
+pub fn visible_changes(
+    changes: &Value,
+    schema: &AttributeSchema,
+    viewer: &Viewer,
+    subject: i64,
+) -> Value {
+    let Value::Object(changes) = changes else {
+        return changes.clone();
+    };
+    let visible = changes
+        .iter()
+        .filter(|(key, _)| can_see_field(schema, viewer, subject, key))
+        .map(|(key, change)| (key.clone(), change.clone()))
+        .collect();
+    Value::Object(visible)
+}
+
+/// Values to write back when reverting to a revision, given the `changes` of every newer
+/// revision, oldest first: for each field changed since, the value it had before the first of
+/// those changes. `deleted_at` is left out (use restore / delete for that).
+pub fn values_at_revision(later: &[Value]) -> Map<String, Value> {
+    let mut out = Map::new();
+    for changes in later {
+        let Value::Object(changes) = changes else {
+            continue;
+        };
+        for (key, change) in changes {
+            if key == "deleted_at" || out.contains_key(key) {
+                continue;
+            }
+            out.insert(key.clone(), change["from"].clone());
+        }
+    }
+    out
+}
+
+/// Apply `attributes.<name>` values from [`values_at_revision`] to stored attributes (`null`
+/// removes one). Fields rejected by `keep` are left as stored.
+pub fn revert_attributes(
+    stored: Option<&Value>,
+    values: &Map<String, Value>,
+    keep: impl Fn(&str) -> bool,
+) -> Value {
+    let mut attributes = match stored {
+        Some(Value::Object(stored)) => stored.clone(),
+        _ => Map::new(),
+    };
+    for (key, value) in values {
+        let Some(name) = key.strip_prefix(ATTRIBUTE_PREFIX) else {
+            continue;
+        };
+        if !keep(key) {
+            continue;
+        }
+        if value.is_null() {
+            attributes.remove(name);
+        } else {
+            attributes.insert(name.to_string(), value.clone());
+        }
+    }
+    Value::Object(attributes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(name: &str, attributes: Value) -> UserSnapshot {
+        UserSnapshot {
+            name: name.to_string(),
+            email: "a@example.com".to_string(),
+            attributes: Some(attributes),
+            deleted_at: None,
+            version: 1,
+        }
+    }
+
+    #[test]
+    fn test_diff_is_field_level() {
+        let before = user("Alice", json!({ "dept": "sales", "phone": "1" }));
+        let after = user("Alicia", json!({ "dept": "ops" }));
+        let changes = diff(Some(&before), &after);
+        assert_eq!(
+            Value::Object(changes),
+            json!({
+                "name": { "from": "Alice", "to": "Alicia" },
+                "attributes.dept": { "from": "sales", "to": "ops" },
+                "attributes.phone": { "from": "1", "to": null },
+            })
+        );
+
+        let created = diff(None, &before);
+        assert_eq!(
+            created["email"],
+            json!({ "from": null, "to": "a@example.com" })
+        );
+        assert!(!created.contains_key("deleted_at"));
+    }
+
+    #[test]
+    fn test_values_at_revision_take_first_later_change() {
+        let later = vec![
+            json!({ "name": { "from": "B", "to": "C" } }),
+            json!({ "name": { "from": "C", "to": "D" }, "attributes.dept": { "from": null, "to": "ops" } }),
+            json!({ "deleted_at": { "from": null, "to": "2026-01-01T00:00:00Z" } }),
+        ];
+        let values = values_at_revision(&later);
+        assert_eq!(
+            Value::Object(values.clone()),
+            json!({ "name": "B", "attributes.dept": null })
+        );
+
+        let stored = json!({ "dept": "ops", "phone": "1" });
+        assert_eq!(
+            revert_attributes(Some(&stored), &values, |_| true),
+            json!({ "phone": "1" })
+        );
+        assert_eq!(revert_attributes(Some(&stored), &values, |_| false), stored);
+    }
+}
@@ -0,0 +1,371 @@
+use serde::Serialize;
+use sqlx::{MySql, QueryBuilder};
+
+/// Columns covered by the `ft_users_search` FULLTEXT index (the list must match it exactly).
+const MATCH_SQL: &str = "MATCH(name, email, attributes_search)";
+
+/// Longest accepted `q`, in characters.
+pub const MAX_QUERY_CHARS: usize = 200;
+
+/// Shortest term used for matching; shorter words would match too much as prefixes.
+const MIN_TERM_CHARS: usize = 2;
+
+/// Most terms taken from a query.
+const MAX_TERMS: usize = 10;
+
+/// How `q` is interpreted by `GET /users/search`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchMode {
+    /// Plain words; each word also matches as a prefix (`ali` finds `alice`)
+    Natural,
+    /// MySQL boolean syntax: `+required -excluded prefix* "exact phrase"`
+    Boolean,
+}
+
+impl SearchMode {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "natural" => Some(Self::Natural),
+            "boolean" => Some(Self::Boolean),
+            _ => None,
+        }
+    }
+}
+
+/// A validated full-text query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchQuery {
+    pub mode: SearchMode,
+    /// Lowercased words of `q`, used for prefix matching and highlighting
+    pub terms: Vec<String>,
+    /// Text passed to `AGAINST (... IN BOOLEAN MODE)`
+    boolean: String,
+}
+
+/// Words of a query: runs of letters, digits and `_` (the characters the FULLTEXT parser keeps).
+fn words(raw: &str) -> impl Iterator<Item = &str> {
+    raw.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|w| !w.is_empty())
+}
+
+/// Rewrite a boolean-mode query into syntax InnoDB always accepts: operators (`+ - ~ < >`)
+/// only in front of a word, phrase or group, `*` only at the end of a plain word, and words
+/// with other punctuation (`alice@example.com`, `jean-luc`) quoted as phrases. Stray
+/// operators are dropped; unbalanced quotes or parentheses are an error. Returns the tokens
+/// (words, phrases and parentheses, with their operators).
+fn normalize_boolean(q: &str) -> Result<Vec<String>, String> {
+    let mut tokens: Vec<String> = Vec::new();
+    let mut depth = 0usize;
+    let mut chars = q.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let op = match c {
+            '+' | '-' | '~' | '<' | '>' => {
+                chars.next();
+                // Repeated operators keep the first one
+                while chars.next_if(|c| "+-~<>".contains(*c)).is_some() {}
+                Some(c)
+            }
+            _ => None,
+        };
+        let op = op.map(String::from).unwrap_or_default();
+        match chars.peek() {
+            None => {}
+            Some(c) if c.is_whitespace() => {}
+            Some('"') => {
+                chars.next();
+                let mut phrase = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => phrase.push(c),
+                        None => return Err("Search query has an unbalanced quote".to_string()),
+                    }
+                }
+                if words(&phrase).next().is_some() {
+                    tokens.push(format!("{}\"{}\"", op, phrase.trim()));
+                }
+            }
+            Some('(') => {
+                chars.next();
+                depth += 1;
+                tokens.push(format!("{}(", op));
+            }
+            Some(')') => {
+                chars.next();
+                if depth == 0 {
+                    return Err("Search query has an unbalanced parenthesis".to_string());
+                }
+                depth -= 1;
+                // Empty groups are a syntax error
+                if tokens.last().is_some_and(|t| t.ends_with('(')) {
+                    tokens.pop();
+                } else {
+                    tokens.push(")".to_string());
+                }
+            }
+            Some(_) => {
+                let mut word = String::new();
+                while let Some(c) =
+                    chars.next_if(|c| !c.is_whitespace() && !matches!(c, '"' | '(' | ')'))
+                {
+                    word.push(c);
+                }
+                let stem = word.trim_end_matches('*');
+                if stem.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                    if !stem.is_empty() {
+                        let prefix = if stem.len() < word.len() { "*" } else { "" };
+                        tokens.push(format!("{}{}{}", op, stem, prefix));
+                    }
+                } else if words(stem).next().is_some() {
+                    tokens.push(format!("{}\"{}\"", op, stem));
+                }
+            }
+        }
+    }
+    if depth != 0 {
+        return Err("Search query has an unbalanced parenthesis".to_string());
+    }
+    Ok(tokens)
+}
+
+fn prefix_query(terms: &[String]) -> String {
+    terms
+        .iter()
+        .map(|t| format!("{}*", t))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl SearchQuery {
+    /// Validate `q` for a mode (error message on failure).
+    pub fn parse(q: &str, mode: SearchMode) -> Result<Self, String> {
+        let q = q.trim();
+        if q.is_empty() {
+            return Err("Search query is required".to_string());
+        }
+        if q.chars().count() > MAX_QUERY_CHARS {
+            return Err(format!(
+                "Search query must be at most {} characters",
+                MAX_QUERY_CHARS
+            ));
+        }
+        let tokens = match mode {
+            SearchMode::Natural => Vec::new(),
+            SearchMode::Boolean => normalize_boolean(q)?,
+        };
+        // Excluded words (`-word`) are not highlighted
+        let source: Vec<&str> = match mode {
+            SearchMode::Natural => vec![q],
+            SearchMode::Boolean => tokens
+                .iter()
+                .map(String::as_str)
+                .filter(|t| !t.starts_with('-'))
+                .collect(),
+        };
+        let mut terms: Vec<String> = Vec::new();
+        for word in source.into_iter().flat_map(words) {
+            let word = word.to_lowercase();
+            if word.chars().count() >= MIN_TERM_CHARS && !terms.contains(&word) {
+                terms.push(word);
+            }
+        }
+        terms.truncate(MAX_TERMS);
+        if terms.is_empty() {
+            return Err(format!(
+                "Search query needs a word of at least {} characters",
+                MIN_TERM_CHARS
+            ));
+        }
+        let boolean = match mode {
+            SearchMode::Natural => prefix_query(&terms),
+            SearchMode::Boolean => tokens.join(" "),
+        };
+        Ok(Self {
+            mode,
+            terms,
+            boolean,
+        })
+    }
+
+    /// A looser natural-language query to retry with when nothing matched: each longer word is
+    /// cut to its first two thirds and matched as a prefix, which tolerates typos near the end
+    /// of words (`alcie` still finds `alice`). `None` if it would not differ.
+    pub fn fuzzy(&self) -> Option<Self> {
+        if self.mode != SearchMode::Natural {
+            return None;
+        }
+        let mut terms: Vec<String> = Vec::new();
+        for term in &self.terms {
+            let chars: Vec<char> = term.chars().collect();
+            let keep = if chars.len() >= 5 {
+                (chars.len() * 2 / 3).max(3)
+            } else {
+                chars.len()
+            };
+            let cut: String = chars[..keep].iter().collect();
+            if !terms.contains(&cut) {
+                terms.push(cut);
+            }
+        }
+        if terms == self.terms {
+            return None;
+        }
+        Some(Self {
+            mode: self.mode,
+            boolean: prefix_query(&terms),
+            terms,
+        })
+    }
+
+    /// Push the relevance score expression. Natural queries add the natural-language score
+    /// (whole-word relevance) to the prefix match score.
+    pub fn push_score(&self, qb: &mut QueryBuilder<'_, MySql>) {
+        qb.push("(");
+        if self.mode == SearchMode::Natural {
+            qb.push(MATCH_SQL)
+                .push(" AGAINST (")
+                .push_bind(self.terms.join(" "))
+                .push(" IN NATURAL LANGUAGE MODE) + ");
+        }
+        qb.push(MATCH_SQL)
+            .push(" AGAINST (")
+            .push_bind(self.boolean.clone())
+            .push(" IN BOOLEAN MODE))");
+    }
+
+    /// Push the match condition (without `WHERE` / `AND`).
+    pub fn push_match(&self, qb: &mut QueryBuilder<'_, MySql>) {
+        qb.push(MATCH_SQL)
+            .push(" AGAINST (")
+            .push_bind(self.boolean.clone())
+            .push(" IN BOOLEAN MODE)");
+    }
+}
+
+fn escape_html(out: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+}
+
+/// HTML-escaped `text` with every word starting with one of `terms` wrapped in `<em>`
+/// (`None` if no word matches).
+pub fn highlight(text: &str, terms: &[String]) -> Option<String> {
+    let mut out = String::with_capacity(text.len() + 16);
+    let mut matched = false;
+    let mut rest = text;
+    while !rest.is_empty() {
+        let is_word = |c: char| c.is_alphanumeric() || c == '_';
+        let first_is_word = rest.chars().next().is_some_and(is_word);
+        let end = rest
+            .find(|c: char| is_word(c) != first_is_word)
+            .unwrap_or(rest.len());
+        let (run, tail) = rest.split_at(end);
+        let lower = run.to_lowercase();
+        if first_is_word && terms.iter().any(|t| lower.starts_with(t.as_str())) {
+            matched = true;
+            out.push_str("<em>");
+            escape_html(&mut out, run);
+            out.push_str("</em>");
+        } else {
+            escape_html(&mut out, run);
+        }
+        rest = tail;
+    }
+    matched.then_some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_queries() {
+        let q = SearchQuery::parse("Alice  anders@Example.com a", SearchMode::Natural).unwrap();
+        assert_eq!(q.terms, vec!["alice", "anders", "example", "com"]);
+        assert_eq!(q.boolean, "alice* anders* example* com*");
+
+        let q = SearchQuery::parse("+alice -bob \"sales team\"", SearchMode::Boolean).unwrap();
+        assert_eq!(q.boolean, "+alice -bob \"sales team\"");
+        assert_eq!(q.terms, vec!["alice", "sales", "team"]);
+
+        assert!(SearchQuery::parse("  ", SearchMode::Natural).is_err());
+        assert!(SearchQuery::parse("-bob", SearchMode::Boolean).is_err());
+        assert!(SearchQuery::parse("a !", SearchMode::Natural).is_err());
+        assert!(SearchQuery::parse(&"x".repeat(201), SearchMode::Natural).is_err());
+    }
+
+    #[test]
+    fn test_boolean_syntax_is_normalized() {
+        let boolean = |q: &str| SearchQuery::parse(q, SearchMode::Boolean).map(|q| q.boolean);
+        // Punctuated words become phrases instead of operators
+        assert_eq!(
+            boolean("alice@example.com").unwrap(),
+            "\"alice@example.com\""
+        );
+        assert_eq!(
+            boolean("+jean-luc -bob.smith").unwrap(),
+            "+\"jean-luc\" -\"bob.smith\""
+        );
+        // Groups, prefixes and operators that apply to something are kept
+        assert_eq!(
+            boolean("+(alice bob*) ~carol >dave").unwrap(),
+            "+( alice bob* ) ~carol >dave"
+        );
+        // Stray operators and empty groups are dropped
+        assert_eq!(boolean("+ alice - * @ ++bob ()").unwrap(), "alice +bob");
+        assert_eq!(boolean("ab*cd").unwrap(), "\"ab*cd\"");
+        // Unbalanced quotes and parentheses are rejected
+        assert!(boolean("\"sales team").is_err());
+        assert!(boolean("(alice").is_err());
+        assert!(boolean("alice)").is_err());
+        let q = SearchQuery::parse("+alice -\"bob smith\"", SearchMode::Boolean).unwrap();
+        assert_eq!(q.terms, vec!["alice"]);
+    }
+
+    #[test]
+    fn test_fuzzy_shortens_long_terms() {
+        let q = SearchQuery::parse("alcie bob", SearchMode::Natural).unwrap();
+        let fuzzy = q.fuzzy().unwrap();
+        assert_eq!(fuzzy.terms, vec!["alc", "bob"]);
+        assert_eq!(fuzzy.boolean, "alc* bob*");
+        assert!(
+            SearchQuery::parse("bob", SearchMode::Natural)
+                .unwrap()
+                .fuzzy()
+                .is_none()
+        );
+        assert!(
+            SearchQuery::parse("alcie", SearchMode::Boolean)
+                .unwrap()
+                .fuzzy()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_highlight() {
+        let terms = vec!["ali".to_string(), "example".to_string()];
+        assert_eq!(
+            highlight("Alice <Anders>", &terms).as_deref(),
+            Some("<em>Alice</em> &lt;Anders&gt;")
+        );
+        assert_eq!(
+            highlight("alice@example.com", &terms).as_deref(),
+            Some("<em>alice</em>@<em>example</em>.com")
+        );
+        assert_eq!(highlight("Bob", &terms), None);
+    }
+}
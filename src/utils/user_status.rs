@@ -0,0 +1,147 @@
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+
+/// SQL expression for a user's effective status: a suspension whose end date has passed
+/// counts as `active` without waiting for anyone to lift it.
+pub const EFFECTIVE_STATUS_SQL: &str = "(CASE WHEN status = 'suspended' AND status_until IS NOT NULL AND status_until <= NOW() THEN 'active' ELSE status END)";
+
+/// Lifecycle state of an account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UserStatus {
+    Active,
+    /// Temporarily blocked, optionally until a given date
+    Suspended,
+    /// Permanently blocked until an admin reactivates the account
+    Banned,
+    /// Registered but not yet approved by an admin
+    Pending,
+}
+
+impl UserStatus {
+    pub const ALL: [UserStatus; 4] = [Self::Active, Self::Suspended, Self::Banned, Self::Pending];
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "active" => Some(Self::Active),
+            "suspended" => Some(Self::Suspended),
+            "banned" => Some(Self::Banned),
+            "pending" => Some(Self::Pending),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Active => "active",
+            Self::Suspended => "suspended",
+            Self::Banned => "banned",
+            Self::Pending => "pending",
+        }
+    }
+
+    /// Allowed transitions. Suspending a suspended account updates its reason and end date;
+    /// nothing moves back to `pending`.
+    pub fn can_transition_to(self, next: UserStatus) -> bool {
+        use UserStatus::*;
+        matches!(
+            (self, next),
+            (Pending, Active | Suspended | Banned)
+                | (Active, Suspended | Banned)
+                | (Suspended, Active | Suspended | Banned)
+                | (Banned, Active)
+        )
+    }
+
+    /// Error shown when an account in this state tries to authenticate (`None` if allowed).
+    pub fn login_error(self) -> Option<&'static str> {
+        match self {
+            Self::Active => None,
+            Self::Suspended => Some("Account is suspended"),
+            Self::Banned => Some("Account is banned"),
+            Self::Pending => Some("Account is pending approval"),
+        }
+    }
+}
+
+/// Status given to self-registered accounts (`USER_REGISTRATION_STATUS=pending` requires an
+/// admin to activate them; default `active`).
+pub fn registration_status() -> UserStatus {
+    match std::env::var("USER_REGISTRATION_STATUS").as_deref() {
+        Ok("pending") => UserStatus::Pending,
+        _ => UserStatus::Active,
+    }
+}
+
+/// Access state of a live user: its effective status and whether SCIM deprovisioning
+/// (`active = false`) disabled it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountState {
+    pub status: UserStatus,
+    pub active: bool,
+}
+
+impl AccountState {
+    /// Error shown when this account tries to authenticate (`None` if allowed).
+    pub fn login_error(self) -> Option<&'static str> {
+        if !self.active {
+            return Some("Account is disabled");
+        }
+        self.status.login_error()
+    }
+}
+
+/// Access state of a live user (`None` if missing or soft-deleted).
+pub async fn account_state(
+    db: &MySqlPool,
+    user_id: i64,
+) -> Result<Option<AccountState>, sqlx::Error> {
+    let row: Option<(String, bool)> = sqlx::query_as(&format!(
+        "SELECT {} AS status, active FROM users WHERE id = ? AND deleted_at IS NULL",
+        EFFECTIVE_STATUS_SQL
+    ))
+    .bind(user_id)
+    .fetch_optional(db)
+    .await?;
+    Ok(row.map(|(status, active)| AccountState {
+        // Unknown values (edited by hand) block access rather than grant it
+        status: UserStatus::parse(&status).unwrap_or(UserStatus::Suspended),
+        active,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transitions() {
+        use UserStatus::*;
+        assert!(Active.can_transition_to(Suspended));
+        assert!(Suspended.can_transition_to(Suspended));
+        assert!(Banned.can_transition_to(Active));
+        assert!(Pending.can_transition_to(Active));
+        assert!(!Banned.can_transition_to(Suspended));
+        assert!(!Active.can_transition_to(Active));
+        assert!(!Active.can_transition_to(Pending));
+        for status in UserStatus::ALL {
+            assert_eq!(UserStatus::parse(status.as_str()), Some(status));
+        }
+        assert_eq!(Active.login_error(), None);
+        assert!(Banned.login_error().is_some());
+    }
+
+    #[test]
+    fn test_account_state_login_error() {
+        let state = |status, active| AccountState { status, active };
+        assert_eq!(state(UserStatus::Active, true).login_error(), None);
+        assert_eq!(
+            state(UserStatus::Active, false).login_error(),
+            Some("Account is disabled")
+        );
+        assert_eq!(
+            state(UserStatus::Banned, true).login_error(),
+            Some("Account is banned")
+        );
+    }
+}
@@ -0,0 +1,178 @@
+/// Wire formats for `GET /users/export` and `POST /users/import`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferFormat {
+    Csv,
+    Ndjson,
+}
+
+impl TransferFormat {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "csv" => Some(Self::Csv),
+            "ndjson" | "jsonl" => Some(Self::Ndjson),
+            _ => None,
+        }
+    }
+
+    /// Infer the format of an upload from its `Content-Type`.
+    pub fn from_content_type(raw: &str) -> Option<Self> {
+        let media_type = raw.split(';').next().unwrap_or("").trim();
+        match media_type.to_ascii_lowercase().as_str() {
+            "text/csv" => Some(Self::Csv),
+            "application/x-ndjson" | "application/ndjson" | "application/jsonl" => {
+                Some(Self::Ndjson)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Ndjson => "ndjson",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Ndjson => "application/x-ndjson",
+        }
+    }
+}
+
+/// Longest record accepted by [`RecordSplitter`] (guards against unterminated quotes).
+pub const MAX_RECORD_BYTES: usize = 64 * 1024;
+
+/// Splits an incoming byte stream into records without buffering the whole body.
+///
+/// NDJSON records end at `\n`. CSV records end at a `\n` outside double quotes, so quoted
+/// fields may contain line breaks (RFC 4180). A trailing `\r` is stripped.
+pub struct RecordSplitter {
+    format: TransferFormat,
+    buf: Vec<u8>,
+    scanned: usize,
+    in_quotes: bool,
+}
+
+impl RecordSplitter {
+    pub fn new(format: TransferFormat) -> Self {
+        Self {
+            format,
+            buf: Vec::new(),
+            scanned: 0,
+            in_quotes: false,
+        }
+    }
+
+    /// Append a chunk and return the records it completed.
+    pub fn push(&mut self, chunk: &[u8]) -> Result<Vec<Vec<u8>>, String> {
+        self.buf.extend_from_slice(chunk);
+        let mut records = Vec::new();
+        let mut start = 0;
+        let mut i = self.scanned;
+        while i < self.buf.len() {
+            match self.buf[i] {
+                b'"' if self.format == TransferFormat::Csv => self.in_quotes = !self.in_quotes,
+                b'\n' if !self.in_quotes => {
+                    records.push(trim_cr(&self.buf[start..i]).to_vec());
+                    start = i + 1;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        self.buf.drain(..start);
+        self.scanned = self.buf.len();
+        if self.buf.len() > MAX_RECORD_BYTES {
+            return Err(format!(
+                "Record exceeds {} bytes (unterminated quote?)",
+                MAX_RECORD_BYTES
+            ));
+        }
+        Ok(records)
+    }
+
+    /// The last record when the body does not end with a newline.
+    pub fn finish(self) -> Option<Vec<u8>> {
+        let rest = trim_cr(&self.buf);
+        (!rest.is_empty()).then(|| rest.to_vec())
+    }
+}
+
+fn trim_cr(record: &[u8]) -> &[u8] {
+    record.strip_suffix(b"\r").unwrap_or(record)
+}
+
+/// Parse a single CSV record produced by [`RecordSplitter`].
+pub fn parse_csv_record(record: &[u8]) -> Result<Vec<String>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(record);
+    match reader.records().next() {
+        Some(Ok(r)) => Ok(r.iter().map(str::to_string).collect()),
+        Some(Err(e)) => Err(e.to_string()),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Render one CSV line (with trailing newline).
+///
+/// Cells starting with `=`, `+`, `-`, `@`, tab or carriage return are prefixed with `'` so
+/// spreadsheets do not evaluate them as formulas (CSV injection).
+pub fn csv_line(cells: &[&str]) -> Vec<u8> {
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(false)
+        .from_writer(Vec::new());
+    let escaped: Vec<String> = cells
+        .iter()
+        .map(|c| {
+            if c.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+                format!("'{}", c)
+            } else {
+                c.to_string()
+            }
+        })
+        .collect();
+    // Writing into a Vec cannot fail
+    let _ = writer.write_record(&escaped);
+    writer.into_inner().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_records_split_across_chunks_and_quoted_newlines() {
+        let mut splitter = RecordSplitter::new(TransferFormat::Csv);
+        let mut records = splitter.push(b"name,email\r\n\"Ann\nSmith\",a").unwrap();
+        assert_eq!(records, vec![b"name,email".to_vec()]);
+        records = splitter
+            .push(b"nn@example.com\nBob,bob@example.com")
+            .unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(
+            parse_csv_record(&records[0]).unwrap(),
+            ["Ann\nSmith", "ann@example.com"]
+        );
+        assert_eq!(splitter.finish().unwrap(), b"Bob,bob@example.com".to_vec());
+    }
+
+    #[test]
+    fn test_ndjson_ignores_quotes() {
+        let mut splitter = RecordSplitter::new(TransferFormat::Ndjson);
+        let records = splitter.push(b"{\"name\":\"a\\\"\"}\n{}\n").unwrap();
+        assert_eq!(records.len(), 2);
+        assert!(splitter.finish().is_none());
+    }
+
+    #[test]
+    fn test_csv_line_escapes_formulas() {
+        assert_eq!(
+            String::from_utf8(csv_line(&["=SUM(A1)", "a,b", "plain"])).unwrap(),
+            "'=SUM(A1),\"a,b\",plain\n"
+        );
+    }
+}
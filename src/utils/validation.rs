@@ -3,25 +3,32 @@ use axum::{Json, http::StatusCode};
 use serde_json::{Value, json};
 use validator::Validate;
 
+/// Collect validation failures into the `{ "field": ["message", ...] }` map used in API
+/// responses (`None` when the payload is valid).
+pub fn validation_errors<T: Validate>(payload: &T) -> Option<serde_json::Map<String, Value>> {
+    let errors = payload.validate().err()?;
+    let mut errors_map = serde_json::Map::new();
+    for (field, errs) in errors.field_errors().iter() {
+        let msgs: Vec<String> = errs
+            .iter()
+            .map(|e| {
+                e.message
+                    .clone()
+                    .unwrap_or_else(|| "Invalid input".into())
+                    .to_string()
+            })
+            .collect();
+        errors_map.insert(field.to_string(), json!(msgs));
+    }
+    Some(errors_map)
+}
+
 /// Validate a payload implementing `validator::Validate` and return an axum-compatible
 /// error tuple on validation failure so handlers can `?` it.
 pub fn validate_payload<T: Validate>(
     payload: &T,
 ) -> Result<(), (StatusCode, Json<ApiResponse<Value>>)> {
-    if let Err(errors) = payload.validate() {
-        let mut errors_map = serde_json::Map::new();
-        for (field, errs) in errors.field_errors().iter() {
-            let msgs: Vec<String> = errs
-                .iter()
-                .map(|e| {
-                    e.message
-                        .clone()
-                        .unwrap_or_else(|| "Invalid input".into())
-                        .to_string()
-                })
-                .collect();
-            errors_map.insert(field.to_string(), json!(msgs));
-        }
+    if let Some(errors_map) = validation_errors(payload) {
         let response = ApiResponse::error_with_data(
             "Validation error",
             json!({ "errors": serde_json::Value::Object(errors_map) }),
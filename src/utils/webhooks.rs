@@ -0,0 +1,237 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use serde_json::{Value, json};
+use sha2::Sha256;
+use sqlx::types::Json;
+use sqlx::{MySqlConnection, MySqlPool};
+use std::time::Duration;
+
+use crate::utils::token::generate_token;
+use crate::utils::user_status::EFFECTIVE_STATUS_SQL;
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub const EVENT_USER_CREATED: &str = "user.created";
+pub const EVENT_USER_UPDATED: &str = "user.updated";
+pub const EVENT_USER_DELETED: &str = "user.deleted";
+pub const EVENT_USER_LOGGED_IN: &str = "user.logged_in";
+
+/// Event types subscribers can register for.
+pub const EVENT_TYPES: [&str; 4] = [
+    EVENT_USER_CREATED,
+    EVENT_USER_UPDATED,
+    EVENT_USER_DELETED,
+    EVENT_USER_LOGGED_IN,
+];
+
+pub const STATUS_PENDING: &str = "pending";
+pub const STATUS_DELIVERED: &str = "delivered";
+pub const STATUS_DEAD: &str = "dead";
+
+/// Columns of `webhook_subscriptions` in `WebhookSubscriptionResponseSchema` order (the secret
+/// is only shown when it is set).
+pub const SUBSCRIPTION_COLUMNS: &str =
+    "id, url, events, description, active, created_by, created_at, updated_at";
+
+/// Columns of `webhook_deliveries d JOIN webhook_events e` in `WebhookDeliveryResponseSchema`
+/// order.
+pub const DELIVERY_COLUMNS: &str = "d.id, d.event_id, e.event_type, d.subscription_id, d.status, \
+     d.attempts, d.next_attempt_at, d.last_status_code, d.last_error, d.delivered_at, \
+     d.created_at, d.updated_at";
+
+fn env_u64(name: &str, default: u64) -> u64 {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+/// Attempts before a delivery is dead-lettered (`WEBHOOK_MAX_ATTEMPTS`, default 8).
+pub fn max_attempts() -> u32 {
+    env_u64("WEBHOOK_MAX_ATTEMPTS", 8).max(1) as u32
+}
+
+/// Timeout of one delivery request (`WEBHOOK_TIMEOUT_SECS`, default 10).
+pub fn request_timeout() -> Duration {
+    Duration::from_secs(env_u64("WEBHOOK_TIMEOUT_SECS", 10).max(1))
+}
+
+/// Wait before retrying after the `attempt`-th failure: `WEBHOOK_RETRY_BASE_SECS` (default 30)
+/// doubled per attempt, capped at `WEBHOOK_RETRY_MAX_SECS` (default 6 hours).
+pub fn retry_delay(attempt: u32) -> Duration {
+    let base = env_u64("WEBHOOK_RETRY_BASE_SECS", 30);
+    let max = env_u64("WEBHOOK_RETRY_MAX_SECS", 6 * 3600);
+    backoff(base, max, attempt)
+}
+
+fn backoff(base: u64, max: u64, attempt: u32) -> Duration {
+    let factor = 1u64
+        .checked_shl(attempt.saturating_sub(1))
+        .unwrap_or(u64::MAX);
+    Duration::from_secs(base.saturating_mul(factor).min(max))
+}
+
+/// A new random signing secret.
+pub fn generate_secret() -> String {
+    format!("whsec_{}", generate_token())
+}
+
+/// `Webhook-Signature` value: `v1=` + hex HMAC-SHA256 of `"{timestamp}.{body}"` keyed with the
+/// subscription secret. Receivers recompute it and reject stale timestamps to stop replays.
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("v1={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Whether a subscription to `patterns` (exact types, `user.*` or `*`) receives `event_type`.
+pub fn subscribes_to(patterns: &[String], event_type: &str) -> bool {
+    patterns.iter().any(|p| {
+        p == "*"
+            || p == event_type
+            || p.strip_suffix('*')
+                .is_some_and(|prefix| prefix.ends_with('.') && event_type.starts_with(prefix))
+    })
+}
+
+/// Check an event pattern for a subscription.
+pub fn valid_pattern(pattern: &str) -> bool {
+    pattern == "*"
+        || EVENT_TYPES.contains(&pattern)
+        || pattern.strip_suffix(".*").is_some_and(|prefix| {
+            EVENT_TYPES
+                .iter()
+                .any(|t| t.split('.').next() == Some(prefix))
+        })
+}
+
+/// Check a subscriber URL: absolute `http` or `https` with a host.
+pub fn valid_url(raw: &str) -> bool {
+    url::Url::parse(raw)
+        .is_ok_and(|u| matches!(u.scheme(), "http" | "https") && u.host_str().is_some())
+}
+
+/// Write an event into the outbox. Call it on the connection (transaction) that made the
+/// change, so the event exists exactly when the change is committed.
+pub async fn enqueue(
+    conn: &mut MySqlConnection,
+    event_type: &str,
+    data: Value,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO webhook_events (event_type, payload) VALUES (?, ?)")
+        .bind(event_type)
+        .bind(Json(data))
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+/// Envelope of an outbox event as sent to subscribers (also used by the SSE stream).
+pub fn event_body(id: i64, event_type: &str, created_at: DateTime<Utc>, data: &Value) -> Value {
+    json!({
+        "id": id,
+        "type": event_type,
+        "created_at": created_at,
+        "data": data,
+    })
+}
+
+/// The user fields sent with user events (custom attributes are left out since their
+/// visibility depends on the reader).
+#[derive(sqlx::FromRow, Debug, Serialize)]
+struct WebhookUser {
+    id: i64,
+    name: String,
+    email: String,
+    role: String,
+    status: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    deleted_at: Option<DateTime<Utc>>,
+    version: i64,
+}
+
+/// Enqueue a user event with the user's current state as `data.user`, plus `extra` fields
+/// (e.g. `actor_id`).
+pub async fn enqueue_user_event(
+    conn: &mut MySqlConnection,
+    event_type: &str,
+    user_id: i64,
+    extra: Value,
+) -> Result<(), sqlx::Error> {
+    let user = sqlx::query_as::<_, WebhookUser>(&format!(
+        "SELECT id, name, email, role, {} AS status, created_at, updated_at, deleted_at, version \
+         FROM users WHERE id = ?",
+        EFFECTIVE_STATUS_SQL
+    ))
+    .bind(user_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let Some(user) = user else {
+        return Ok(());
+    };
+    let mut data = json!({ "user": user });
+    if let (Value::Object(data), Value::Object(extra)) = (&mut data, extra) {
+        data.extend(extra);
+    }
+    enqueue(conn, event_type, data).await
+}
+
+/// Enqueue `user.logged_in` after a successful sign-in. Logins change nothing to commit it
+/// with, and a failed enqueue only logs a warning rather than failing the login.
+pub async fn record_login(db: &MySqlPool, user_id: i64, extra: Value) {
+    let result = async {
+        let mut conn = db.acquire().await?;
+        enqueue_user_event(&mut conn, EVENT_USER_LOGGED_IN, user_id, extra).await
+    }
+    .await;
+    if let Err(e) = result {
+        tracing::warn!(
+            "webhooks: failed to enqueue login of user {}: {}",
+            user_id,
+            e
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_is_hmac_of_timestamp_and_body() {
+        let signature = sign("whsec_test", 1_700_000_000, br#"{"a":1}"#);
+        assert!(signature.starts_with("v1="));
+        assert_eq!(signature.len(), 3 + 64);
+        assert_eq!(signature, sign("whsec_test", 1_700_000_000, br#"{"a":1}"#));
+        assert_ne!(signature, sign("whsec_test", 1_700_000_001, br#"{"a":1}"#));
+        assert_ne!(signature, sign("whsec_other", 1_700_000_000, br#"{"a":1}"#));
+    }
+
+    #[test]
+    fn test_backoff_doubles_and_caps() {
+        assert_eq!(backoff(30, 3600, 1), Duration::from_secs(30));
+        assert_eq!(backoff(30, 3600, 2), Duration::from_secs(60));
+        assert_eq!(backoff(30, 3600, 4), Duration::from_secs(240));
+        assert_eq!(backoff(30, 3600, 10), Duration::from_secs(3600));
+        assert_eq!(backoff(30, 3600, 200), Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn test_event_patterns() {
+        let patterns = vec!["user.created".to_string()];
+        assert!(subscribes_to(&patterns, "user.created"));
+        assert!(!subscribes_to(&patterns, "user.deleted"));
+        assert!(subscribes_to(&["user.*".to_string()], "user.logged_in"));
+        assert!(subscribes_to(&["*".to_string()], "user.updated"));
+
+        assert!(valid_pattern("user.*"));
+        assert!(valid_pattern("user.logged_in"));
+        assert!(!valid_pattern("user.renamed"));
+        assert!(!valid_pattern("group.*"));
+    }
+}
@@ -1,6 +1,7 @@
 use axum::body::Body;
 use axum::http::{Method, Request};
 use backend_api_jwt::app::build_router;
+use serde_json::Value;
 use tower::util::ServiceExt; // for oneshot
 
 #[test]
@@ -144,3 +145,71 @@ async fn cors_specific_origin_allowed() {
         }
     }
 }
+
+async fn get_users(uri: &str, accept: Option<&str>) -> (axum::http::Response<Body>, Value) {
+    let mut req = Request::builder().method(Method::GET).uri(uri);
+    if let Some(accept) = accept {
+        req = req.header("Accept", accept);
+    }
+    let resp = build_router()
+        .oneshot(req.body(Body::empty()).unwrap())
+        .await
+        .expect("request failed");
+    let (parts, body) = resp.into_parts();
+    let bytes = axum::body::to_bytes(body, usize::MAX).await.expect("body");
+    let value = serde_json::from_slice(&bytes).expect("json body");
+    (
+        axum::http::Response::from_parts(parts, Body::empty()),
+        value,
+    )
+}
+
+fn header<'a>(resp: &'a axum::http::Response<Body>, name: &str) -> Option<&'a str> {
+    resp.headers().get(name).and_then(|v| v.to_str().ok())
+}
+
+#[tokio::test]
+async fn unversioned_paths_are_deprecated_v1_aliases() {
+    let (resp, body) = get_users("/users?limit=5", None).await;
+    assert_eq!(resp.status().as_u16(), 401);
+    assert_eq!(body["success"], false);
+    assert_eq!(header(&resp, "deprecation"), Some("@1792281600"));
+    assert_eq!(
+        header(&resp, "sunset"),
+        Some("Sun, 18 Apr 2027 00:00:00 GMT")
+    );
+    assert_eq!(
+        header(&resp, "link"),
+        Some("</v1/users?limit=5>; rel=\"successor-version\"")
+    );
+    assert_eq!(header(&resp, "vary"), Some("Accept"));
+
+    // The same route under /v1 (or picked through Accept) is not deprecated
+    let (resp, v1_body) = get_users("/v1/users", None).await;
+    assert_eq!(resp.status().as_u16(), 401);
+    assert_eq!(v1_body, body);
+    assert!(header(&resp, "deprecation").is_none());
+    let (resp, accept_body) = get_users("/users", Some("application/vnd.backend.v1+json")).await;
+    assert_eq!(accept_body, body);
+    assert!(header(&resp, "deprecation").is_none());
+}
+
+#[tokio::test]
+async fn v2_is_served_under_v2_and_through_accept() {
+    for (uri, accept) in [
+        ("/v2/users", None),
+        ("/users", Some("application/vnd.backend.v2+json")),
+    ] {
+        let (resp, body) = get_users(uri, accept).await;
+        assert_eq!(resp.status().as_u16(), 401, "{}", uri);
+        assert_eq!(
+            header(&resp, "content-type"),
+            Some("application/problem+json")
+        );
+        assert!(header(&resp, "deprecation").is_none());
+        assert_eq!(body["status"], 401);
+        assert_eq!(body["title"], "Unauthorized");
+        assert_eq!(body["error"], "Missing Authorization header");
+        assert!(body.get("success").is_none());
+    }
+}
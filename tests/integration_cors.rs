@@ -95,6 +95,44 @@ async fn cors_wildcard_allows_any_origin() {
     if let Some(a) = allowed {
         assert!(a == "*" || a == "http://example.com");
     }
+
+    // Conditional PATCH: the method and If-Match are allowed
+    let res = client
+        .request(reqwest::Method::OPTIONS, format!("http://{}/users/1", addr))
+        .header("Origin", "http://example.com")
+        .header("Access-Control-Request-Method", "PATCH")
+        .header(
+            "Access-Control-Request-Headers",
+            "authorization,content-type,if-match",
+        )
+        .send()
+        .await
+        .expect("request failed");
+    assert!(res.status().is_success());
+    let header = |name: &str| {
+        res.headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_ascii_lowercase()
+    };
+    assert!(header("access-control-allow-methods").contains("patch"));
+    assert!(header("access-control-allow-headers").contains("if-match"));
+
+    // ETag and Link are readable by browser clients (on every response, errors included)
+    let res = client
+        .get(format!("http://{}/users", addr))
+        .header("Origin", "http://example.com")
+        .send()
+        .await
+        .expect("request failed");
+    let exposed = res
+        .headers()
+        .get("access-control-expose-headers")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    assert!(exposed.contains("etag") && exposed.contains("link"));
 }
 
 #[tokio::test]
@@ -75,11 +75,22 @@ async fn user_delete_flow() {
         .await
         .expect("insert user");
     let user_id = insert_res.last_insert_id() as i64;
+    let observer_id = sqlx::query("INSERT INTO users (name, email, password) VALUES (?, ?, ?)")
+        .bind("Observer")
+        .bind("observer@example.com")
+        .bind("unused")
+        .execute(&pool)
+        .await
+        .expect("insert observer")
+        .last_insert_id() as i64;
 
-    // generate token
+    // generate tokens
     let token = backend_api_jwt::utils::jwt::generate_jwt_token(user_id)
         .await
         .expect("generate token");
+    let observer_token = backend_api_jwt::utils::jwt::generate_jwt_token(observer_id)
+        .await
+        .expect("generate token");
 
     // start app
     let app = create_app(pool.clone());
@@ -121,12 +132,21 @@ async fn user_delete_flow() {
     // Verify deleted -> GET returns 404
     let res2 = client
         .get(&url)
-        .header("Authorization", format!("Bearer {}", token))
+        .header("Authorization", format!("Bearer {}", observer_token))
         .send()
         .await
         .expect("request failed");
     assert_eq!(res2.status().as_u16(), 404);
 
+    // The deleted user's own token no longer works
+    let res3 = client
+        .get(&url)
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("request failed");
+    assert_eq!(res3.status().as_u16(), 401);
+
     admin_pool
         .execute(format!("DROP DATABASE IF EXISTS {}", test_db).as_str())
         .await